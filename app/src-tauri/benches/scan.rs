@@ -0,0 +1,69 @@
+//! スキャン（ハッシュ計算を含む）のベンチマーク
+//!
+//! 小さなファイルが多数と巨大なファイルが数個ある合成ツリーを、スレッド数を変えて走査する。
+//! 実行: `cargo bench --bench scan`
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use secure_backup_lib::backup::DirectoryScanner;
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+/// 小さなファイルの数とサイズ
+const SMALL_FILES: usize = 5_000;
+const SMALL_FILE_SIZE: usize = 4 * 1024;
+
+/// 巨大なファイルの数とサイズ
+const LARGE_FILES: usize = 3;
+const LARGE_FILE_SIZE: usize = 128 * 1024 * 1024;
+
+/// 合成ツリーを作成
+///
+/// # Returns
+/// 合計バイト数
+fn create_tree(root: &Path) -> u64 {
+    for i in 0..SMALL_FILES {
+        let path = root.join(format!("small/{:02}/{:05}.txt", i % 100, i));
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        let data: Vec<u8> = (0..SMALL_FILE_SIZE).map(|j| (i + j) as u8).collect();
+        fs::write(path, data).unwrap();
+    }
+
+    let mut data: Vec<u8> = (0..LARGE_FILE_SIZE).map(|j| (j % 251) as u8).collect();
+    for i in 0..LARGE_FILES {
+        data[0] = i as u8;
+        fs::write(root.join(format!("large-{}.img", i)), &data).unwrap();
+    }
+
+    (SMALL_FILES * SMALL_FILE_SIZE + LARGE_FILES * LARGE_FILE_SIZE) as u64
+}
+
+fn bench_scan(c: &mut Criterion) {
+    let temp = TempDir::new().unwrap();
+    let total_bytes = create_tree(temp.path());
+
+    let mut group = c.benchmark_group("scan_with_hash");
+    group.sample_size(10);
+    group.throughput(Throughput::Bytes(total_bytes));
+
+    let cpus = std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+    let mut thread_counts = vec![1, 2, 4, cpus];
+    thread_counts.retain(|&n| n <= cpus);
+    thread_counts.dedup();
+
+    for threads in thread_counts {
+        group.bench_with_input(BenchmarkId::from_parameter(threads), &threads, |b, &threads| {
+            b.iter(|| {
+                DirectoryScanner::new(temp.path())
+                    .with_hash()
+                    .with_threads(threads)
+                    .scan()
+                    .unwrap()
+            });
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_scan);
+criterion_main!(benches);
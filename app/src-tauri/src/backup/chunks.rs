@@ -0,0 +1,302 @@
+//! チャンクストア - 内容定義チャンク分割と重複排除
+//!
+//! ファイルを FastCDC で可変長チャンクに分割し、BLAKE3 ハッシュをIDとして
+//! `chunks/<先頭2文字>/<ID>` に保存する。同じ内容のチャンクは一度しか保存しない。
+//! 暗号化リポジトリではIDにリポジトリ鍵による鍵付き BLAKE3 を使う。
+
+use super::content_hasher;
+use crate::crypto::Encryptor;
+use fastcdc::v2020::StreamCDC;
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+
+/// チャンクストアのディレクトリ名
+pub const CHUNKS_DIR: &str = "chunks";
+
+/// 最小チャンクサイズ（256KiB）
+const MIN_CHUNK_SIZE: u32 = 256 * 1024;
+
+/// 平均チャンクサイズ（1MiB）
+const AVG_CHUNK_SIZE: u32 = 1024 * 1024;
+
+/// 最大チャンクサイズ（4MiB）
+const MAX_CHUNK_SIZE: u32 = 4 * 1024 * 1024;
+
+/// チャンクファイル先頭のフラグ: 圧縮済み
+const FLAG_COMPRESSED: u8 = 0b01;
+
+/// チャンクファイル先頭のフラグ: 暗号化済み
+const FLAG_ENCRYPTED: u8 = 0b10;
+
+/// チャンクIDの長さ（BLAKE3 ハッシュの16進表記）
+const CHUNK_ID_LEN: usize = 64;
+
+/// チャンクIDとして正しい形式か（小文字16進64文字）
+///
+/// マニフェストから読んだIDをそのままパスにすると `chunks/` の外を指せるため、保存先を作る前に確認する。
+pub fn is_valid_chunk_id(id: &str) -> bool {
+    id.len() == CHUNK_ID_LEN && id.bytes().all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f'))
+}
+
+/// チャンクの暗号化コンテキスト（IDを認証し、別のチャンクへの差し替えを検出する）
+fn chunk_context(id: &str) -> String {
+    format!("chunk:{}", id)
+}
+
+/// ファイル保存結果
+#[derive(Debug, Clone)]
+pub struct StoredFile {
+    /// チャンクID一覧（ファイル内の順序）
+    pub chunks: Vec<String>,
+
+    /// 元のサイズ
+    pub original_size: u64,
+
+    /// 今回新たに保存したバイト数（重複排除されたチャンクは含まない）
+    pub stored_size: u64,
+}
+
+/// チャンクストア
+pub struct ChunkStore<'a> {
+    /// チャンクディレクトリ
+    root: PathBuf,
+
+    /// 圧縮するか
+    compress: bool,
+
+    /// 暗号化エンジン（暗号化しない場合はNone）
+    encryptor: Option<&'a Encryptor>,
+}
+
+impl<'a> ChunkStore<'a> {
+    /// リポジトリのチャンクストアを開く
+    pub fn new(repo_dir: &Path, compress: bool, encryptor: Option<&'a Encryptor>) -> Self {
+        Self {
+            root: repo_dir.join(CHUNKS_DIR),
+            compress,
+            encryptor,
+        }
+    }
+
+    /// チャンクのパス（不正なIDはエラー）
+    pub fn chunk_path(&self, id: &str) -> io::Result<PathBuf> {
+        if !is_valid_chunk_id(id) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("不正なチャンクIDです: {}", id),
+            ));
+        }
+        Ok(self.root.join(&id[..2]).join(id))
+    }
+
+    /// 保存時のフラグ
+    fn flags(&self) -> u8 {
+        (if self.compress { FLAG_COMPRESSED } else { 0 })
+            | (if self.encryptor.is_some() { FLAG_ENCRYPTED } else { 0 })
+    }
+
+    /// ファイルを分割して保存
+    pub fn store_file(&self, reader: impl Read) -> io::Result<StoredFile> {
+        let mut chunks = Vec::new();
+        let mut original_size = 0u64;
+        let mut stored_size = 0u64;
+        // 暗号化リポジトリではチャンクIDも鍵付きにし、既知のデータの有無を推測させない
+        let hasher = content_hasher(self.encryptor);
+
+        for chunk in StreamCDC::new(reader, MIN_CHUNK_SIZE, AVG_CHUNK_SIZE, MAX_CHUNK_SIZE) {
+            let chunk = chunk?;
+            let id = hasher.clone().update(&chunk.data).finalize().to_hex().to_string();
+
+            stored_size += self.put(&id, &chunk.data)?;
+            original_size += chunk.length as u64;
+            chunks.push(id);
+        }
+
+        Ok(StoredFile {
+            chunks,
+            original_size,
+            stored_size,
+        })
+    }
+
+    /// チャンクを保存（同じ設定で保存済みなら何もしない）
+    ///
+    /// # Returns
+    /// 書き込んだバイト数
+    fn put(&self, id: &str, data: &[u8]) -> io::Result<u64> {
+        let path = self.chunk_path(id)?;
+        let flags = self.flags();
+
+        // 既存チャンクは圧縮・暗号化の設定が同じ場合のみ再利用する
+        if path.exists() {
+            let mut existing = [0u8; 1];
+            if File::open(&path)?.read_exact(&mut existing).is_ok() && existing[0] == flags {
+                return Ok(0);
+            }
+        }
+
+        let data = if self.compress {
+            zstd::encode_all(data, 3)?
+        } else {
+            data.to_vec()
+        };
+        let data = match self.encryptor {
+            Some(encryptor) => encryptor.encrypt(&data, &chunk_context(id))
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?,
+            None => data,
+        };
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        // 一時ファイル経由で書き込み、中途半端なチャンクを残さない
+        let tmp_path = path.with_extension("tmp");
+        let mut file = File::create(&tmp_path)?;
+        file.write_all(&[flags])?;
+        file.write_all(&data)?;
+        file.sync_all()?;
+        fs::rename(&tmp_path, &path)?;
+
+        Ok(data.len() as u64 + 1)
+    }
+
+    /// チャンクを読み込み、復号・解凍して返す
+    pub fn read_chunk(&self, id: &str) -> io::Result<Vec<u8>> {
+        let path = self.chunk_path(id)?;
+        if !path.exists() {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("チャンクが見つかりません: {}", id),
+            ));
+        }
+
+        let data = fs::read(&path)?;
+        let (flags, data) = data.split_first()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "空のチャンクファイル"))?;
+
+        let data = if flags & FLAG_ENCRYPTED != 0 {
+            let encryptor = self.encryptor.ok_or_else(|| {
+                io::Error::new(io::ErrorKind::InvalidData, crate::crypto::CryptoError::DecryptionFailed)
+            })?;
+            encryptor.decrypt(data, &chunk_context(id))
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?
+        } else {
+            data.to_vec()
+        };
+
+        if flags & FLAG_COMPRESSED != 0 {
+            zstd::decode_all(data.as_slice())
+        } else {
+            Ok(data)
+        }
+    }
+
+    /// チャンク列を連結して読み出すリーダーを作成
+    pub fn reader(&'a self, chunks: &'a [String]) -> ChunkReader<'a> {
+        ChunkReader {
+            store: self,
+            chunks,
+            next: 0,
+            current: Vec::new(),
+            position: 0,
+        }
+    }
+}
+
+/// チャンク列を順に読み出すリーダー（メモリ上にはチャンク1つ分のみ保持）
+pub struct ChunkReader<'a> {
+    store: &'a ChunkStore<'a>,
+    chunks: &'a [String],
+    next: usize,
+    current: Vec<u8>,
+    position: usize,
+}
+
+impl Read for ChunkReader<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.position == self.current.len() {
+            let Some(id) = self.chunks.get(self.next) else {
+                return Ok(0);
+            };
+            self.current = self.store.read_chunk(id)?;
+            self.position = 0;
+            self.next += 1;
+        }
+
+        let n = buf.len().min(self.current.len() - self.position);
+        buf[..n].copy_from_slice(&self.current[self.position..self.position + n]);
+        self.position += n;
+        Ok(n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn sample(len: usize, seed: u64) -> Vec<u8> {
+        // 擬似乱数（チャンク境界が内容に依存するよう、単調なデータは避ける）
+        let mut x = seed;
+        (0..len)
+            .map(|_| {
+                x ^= x << 13;
+                x ^= x >> 7;
+                x ^= x << 17;
+                x as u8
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_chunk_path_rejects_invalid_ids() {
+        let repo = TempDir::new().unwrap();
+        let store = ChunkStore::new(repo.path(), false, None);
+        let id = blake3::hash(b"chunk").to_hex().to_string();
+        assert_eq!(store.chunk_path(&id).unwrap(), repo.path().join(CHUNKS_DIR).join(&id[..2]).join(&id));
+
+        for id in ["../../x", "aé", "", &id.to_uppercase()] {
+            assert!(store.chunk_path(id).is_err());
+            assert!(store.read_chunk(id).is_err());
+        }
+    }
+
+    #[test]
+    fn test_store_and_read_back() {
+        let repo = TempDir::new().unwrap();
+        let store = ChunkStore::new(repo.path(), true, None);
+        let data = sample(5 * 1024 * 1024, 1);
+
+        let stored = store.store_file(data.as_slice()).unwrap();
+        assert!(stored.chunks.len() > 1);
+        assert_eq!(stored.original_size, data.len() as u64);
+
+        let mut restored = Vec::new();
+        store.reader(&stored.chunks).read_to_end(&mut restored).unwrap();
+        assert_eq!(restored, data);
+    }
+
+    #[test]
+    fn test_deduplication() {
+        let repo = TempDir::new().unwrap();
+        let store = ChunkStore::new(repo.path(), true, None);
+        let data = sample(5 * 1024 * 1024, 2);
+
+        let first = store.store_file(data.as_slice()).unwrap();
+        assert!(first.stored_size > 0);
+
+        // 同じ内容は保存しない
+        let second = store.store_file(data.as_slice()).unwrap();
+        assert_eq!(second.stored_size, 0);
+        assert_eq!(first.chunks, second.chunks);
+
+        // 先頭1バイトの変更では、後続のチャンクは再利用される
+        let mut modified = data.clone();
+        modified[0] ^= 0xff;
+        let third = store.store_file(modified.as_slice()).unwrap();
+        assert_ne!(third.chunks[0], first.chunks[0]);
+        assert_eq!(third.chunks.last(), first.chunks.last());
+    }
+}
@@ -1,10 +1,16 @@
 //! バックアップ実行エンジン
 
-use super::{DiffResult, ScanResult, DirectoryScanner, BackupManifest};
-use crate::crypto::Encryptor;
+use super::{DiffResult, ScanFilters, ScanResult, DirectoryScanner, BackupManifest};
+use super::{open_or_init_encryptor, open_or_init_recipient_encryptor, new_snapshot_id, write_snapshot, ChunkStore, SnapshotMeta};
+use super::write_signature;
+use super::{prune, RetentionPolicy};
+use super::{content_hasher, encode_manifest, load_keyfile, read_manifest_file, MANIFEST_CONTEXT, MANIFEST_FILE};
+use crate::crypto::{CipherSuite, ContentKey, Encryptor, KdfParams, Recipient, SnapshotSigner};
+use secrecy::{ExposeSecret, SecretString};
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fs::{self, File};
-use std::io::{Read, Write};
+use std::io::{self, BufReader, BufWriter, Read};
 use std::path::PathBuf;
 use chrono::{DateTime, Utc};
 use thiserror::Error;
@@ -21,14 +27,32 @@ pub enum BackupError {
     #[error("暗号化エラー: {0}")]
     Crypto(#[from] crate::crypto::CryptoError),
 
+    #[error("リポジトリエラー: {0}")]
+    Repository(#[from] super::RepositoryError),
+
     #[error("シリアライズエラー: {0}")]
     Serialize(#[from] serde_json::Error),
 
+    #[error("整理エラー: {0}")]
+    Prune(#[from] super::PruneError),
+
     #[error("圧縮エラー")]
     Compression,
 
     #[error("バックアップ先が存在しません: {0}")]
     DestinationNotFound(PathBuf),
+
+    #[error("暗号化されたリポジトリです（暗号化を有効にしてパスワードを指定してください）")]
+    EncryptedRepository,
+
+    #[error("受信者の公開鍵のみでは{0}を利用できません")]
+    WriteOnly(&'static str),
+
+    #[error("受信者の公開鍵のみでチャンク分割するには内容ID用の鍵が必要です")]
+    ContentKeyRequired,
+
+    #[error("マニフェストの読み込みに失敗しました: {0}")]
+    Manifest(#[from] super::RestoreError),
 }
 
 /// バックアップ設定
@@ -49,8 +73,39 @@ pub struct BackupConfig {
     /// 差分バックアップを行うか
     pub incremental: bool,
 
-    /// 除外パターン
-    pub exclude_patterns: Vec<String>,
+    /// 絞り込み条件（除外・再包含パターン、サイズ・更新日時）
+    #[serde(default)]
+    pub filters: ScanFilters,
+
+    /// 鍵導出パラメータ（暗号化時）
+    #[serde(default)]
+    pub kdf: KdfParams,
+
+    /// 暗号スイート（暗号化時、リポジトリ作成時のみ設定できる。省略時は既存の設定または AES-256-GCM）
+    #[serde(default)]
+    pub cipher: Option<CipherSuite>,
+
+    /// チャンク分割による重複排除を行うか（無効時はファイル単位で data/ に保存）
+    #[serde(default = "default_chunking")]
+    pub chunking: bool,
+
+    /// 暗号化時、ファイル単位の保存先を元のパスから推測できない名前にするか
+    ///
+    /// 元のパスは暗号化されたマニフェストにのみ記録される。チャンク分割時は常にチャンクIDで保存する。
+    #[serde(default)]
+    pub opaque_names: bool,
+
+    /// 保持ポリシー（設定時はバックアップ後に古いスナップショットを整理する）
+    #[serde(default)]
+    pub retention: RetentionPolicy,
+
+    /// スキャン・ハッシュ計算に使うスレッド数（0 の場合は論理CPU数）
+    #[serde(default)]
+    pub scan_threads: usize,
+}
+
+fn default_chunking() -> bool {
+    true
 }
 
 impl Default for BackupConfig {
@@ -61,11 +116,13 @@ impl Default for BackupConfig {
             encrypt: false,
             compress: true,
             incremental: true,
-            exclude_patterns: vec![
-                ".git".to_string(),
-                "node_modules".to_string(),
-                "target".to_string(),
-            ],
+            filters: ScanFilters::default(),
+            kdf: KdfParams::default(),
+            cipher: None,
+            chunking: true,
+            opaque_names: false,
+            retention: RetentionPolicy::default(),
+            scan_threads: 0,
         }
     }
 }
@@ -121,6 +178,9 @@ pub struct BackupResult {
     /// 終了日時
     pub finished_at: DateTime<Utc>,
 
+    /// 作成したスナップショットID
+    pub snapshot_id: String,
+
     /// バックアップしたファイル数
     pub backed_up_files: usize,
 
@@ -133,14 +193,40 @@ pub struct BackupResult {
     /// エラーが発生したファイル
     pub failed_files: Vec<String>,
 
+    /// 保持ポリシーにより削除したスナップショット数
+    pub pruned_snapshots: usize,
+
+    /// 暗号化リポジトリを新規作成した場合の回復フレーズ（この結果でのみ得られる）
+    #[serde(default)]
+    pub recovery_phrase: Option<String>,
+
     /// 成功したか
     pub success: bool,
 }
 
+/// 単一ファイルのバックアップ結果
+#[derive(Debug, Clone)]
+struct BackedUpFile {
+    /// オリジナルサイズ
+    original_size: u64,
+
+    /// 保存したバイト数
+    stored_size: u64,
+
+    /// チャンクID一覧（ファイル単位で保存した場合はNone）
+    chunks: Option<Vec<String>>,
+
+    /// 保存先（リポジトリからの相対パス、チャンク分割した場合はNone）
+    blob: Option<String>,
+}
+
 /// バックアップ実行エンジン
 pub struct BackupExecutor {
     config: BackupConfig,
-    encryptor: Option<Encryptor>,
+    password: Option<SecretString>,
+    recipients: Vec<Recipient>,
+    content_key: Option<ContentKey>,
+    signer: Option<SnapshotSigner>,
     progress_callback: Option<Box<dyn Fn(BackupProgress) + Send + Sync>>,
 }
 
@@ -149,18 +235,50 @@ impl BackupExecutor {
     pub fn new(config: BackupConfig) -> Self {
         Self {
             config,
-            encryptor: None,
+            password: None,
+            recipients: Vec::new(),
+            content_key: None,
+            signer: None,
             progress_callback: None,
         }
     }
 
     /// 暗号化を設定
-    pub fn with_encryption(mut self, password: &str) -> Self {
-        self.encryptor = Some(Encryptor::new(password));
+    ///
+    /// 鍵はバックアップ先のキーファイルから取り出す（初回は作成する）。
+    pub fn with_encryption(mut self, password: impl Into<SecretString>) -> Self {
+        self.password = Some(password.into());
         self.config.encrypt = true;
         self
     }
 
+    /// 受信者の公開鍵による暗号化を設定
+    ///
+    /// このマシンはバックアップを作成できるが復号はできない（復元にはアイデンティティが必要）。
+    /// 前回のマニフェストを読めないため、毎回全ファイルを対象とし、重複排除はチャンク単位でのみ行う。
+    pub fn with_recipients(mut self, recipients: Vec<Recipient>) -> Self {
+        self.recipients = recipients;
+        self.config.encrypt = true;
+        self
+    }
+
+    /// 受信者方式のリポジトリの内容ID用の鍵を設定
+    ///
+    /// チャンクIDを平文の BLAKE3 で計算すると既知のファイルとの照合で中身を推測できるため、
+    /// 受信者方式でチャンク分割する場合は必須。鍵はリポジトリに平文で保存されないため、ジョブ側で保管する。
+    pub fn with_content_key(mut self, content_key: ContentKey) -> Self {
+        self.content_key = Some(content_key);
+        self
+    }
+
+    /// スナップショットの署名鍵を設定
+    ///
+    /// 書き込むスナップショットと manifest.json に署名し、復元・検証時に作成元を確認できるようにする。
+    pub fn with_signer(mut self, signer: SnapshotSigner) -> Self {
+        self.signer = Some(signer);
+        self
+    }
+
     /// 進捗コールバックを設定
     pub fn with_progress_callback<F>(mut self, callback: F) -> Self
     where
@@ -173,6 +291,7 @@ impl BackupExecutor {
     /// バックアップを実行
     pub fn execute(&self) -> Result<BackupResult, BackupError> {
         let started_at = Utc::now();
+        let snapshot_id = new_snapshot_id(started_at);
 
         // 進捗を報告
         self.report_progress(BackupProgress {
@@ -185,16 +304,57 @@ impl BackupExecutor {
             error: None,
         });
 
-        // ソースをスキャン
-        let mut scanner = DirectoryScanner::new(&self.config.source_dir);
-        for pattern in &self.config.exclude_patterns {
-            scanner = scanner.exclude(pattern);
-        }
-        let current_scan = scanner.with_hash().scan()?;
+        // ソースをスキャン（ハッシュは暗号化エンジンの準備後に計算する）
+        let mut current_scan = DirectoryScanner::new(&self.config.source_dir)
+            .with_filters(self.config.filters.clone())
+            .with_threads(self.config.scan_threads)
+            .scan()?;
 
         // バックアップ先ディレクトリを作成
         fs::create_dir_all(&self.config.dest_dir)?;
 
+        // 暗号化エンジンを準備（リポジトリを新規作成した場合は回復キーも生成される）
+        let mut recovery = None;
+        let encryptor = match (&self.password, self.config.encrypt) {
+            (Some(password), true) => {
+                let (encryptor, created) = open_or_init_encryptor(
+                    &self.config.dest_dir,
+                    password.expose_secret(),
+                    self.config.kdf,
+                    self.config.cipher,
+                )?;
+                recovery = created;
+                Some(encryptor)
+            }
+            (None, true) if !self.recipients.is_empty() => Some(open_or_init_recipient_encryptor(
+                &self.config.dest_dir,
+                &self.recipients,
+                self.config.cipher,
+                self.content_key.as_ref(),
+            )?),
+            _ => None,
+        };
+
+        // 書き込み専用ではマニフェストを読めないため、それを必要とする機能は使えない
+        let write_only = encryptor.as_ref().is_some_and(|e| !e.can_open_metadata());
+        if write_only && self.config.retention.is_enabled() {
+            return Err(BackupError::WriteOnly("保持ポリシーによる整理"));
+        }
+        if write_only && self.config.opaque_names {
+            return Err(BackupError::WriteOnly("ファイル名の秘匿"));
+        }
+        if write_only && self.config.chunking && self.content_key.is_none() {
+            return Err(BackupError::ContentKeyRequired);
+        }
+
+        // 暗号化リポジトリに平文のマニフェストを書き込まない
+        if encryptor.is_none() && load_keyfile(&self.config.dest_dir)?.is_some() {
+            return Err(BackupError::EncryptedRepository);
+        }
+
+        // 暗号化リポジトリではハッシュをリポジトリ鍵で計算し、既知のファイルとの照合を防ぐ
+        current_scan.compute_hashes(&content_hasher(encryptor.as_ref()), self.config.scan_threads)?;
+
         // 差分計算
         self.report_progress(BackupProgress {
             processed_files: 0,
@@ -206,13 +366,20 @@ impl BackupExecutor {
             error: None,
         });
 
+        let previous = if write_only {
+            None
+        } else {
+            self.load_previous_manifest(encryptor.as_ref())?
+        };
         let (files_to_backup, skipped_count) = if self.config.incremental {
-            self.compute_incremental_files(&current_scan)?
+            self.compute_incremental_files(&current_scan, previous.as_ref())
         } else {
             (current_scan.files.keys().cloned().collect::<Vec<_>>(), 0)
         };
 
         // バックアップ実行
+        let chunk_store = ChunkStore::new(&self.config.dest_dir, self.config.compress, encryptor.as_ref());
+        let mut backed_up = HashMap::new();
         let mut backed_up_files = 0usize;
         let mut backed_up_bytes = 0u64;
         let mut failed_files = Vec::new();
@@ -228,10 +395,17 @@ impl BackupExecutor {
                 error: None,
             });
 
-            match self.backup_file(file_path) {
-                Ok(size) => {
+            let result = if self.config.chunking {
+                self.backup_file_chunked(file_path, &chunk_store)
+            } else {
+                self.backup_file(file_path, &snapshot_id, encryptor.as_ref())
+            };
+
+            match result {
+                Ok(file) => {
                     backed_up_files += 1;
-                    backed_up_bytes += size;
+                    backed_up_bytes += file.original_size;
+                    backed_up.insert(file_path.clone(), file);
                 }
                 Err(e) => {
                     failed_files.push(format!("{}: {}", file_path, e));
@@ -240,7 +414,23 @@ impl BackupExecutor {
         }
 
         // マニフェストを保存
-        self.save_manifest(&current_scan)?;
+        let snapshot = SnapshotMeta {
+            id: snapshot_id.clone(),
+            parent: previous.as_ref()
+                .and_then(|m| m.snapshot.as_ref())
+                .map(|s| s.id.clone()),
+            timestamp: started_at,
+        };
+        self.save_manifest(&current_scan, snapshot, previous.as_ref(), &backed_up, encryptor.as_ref())?;
+
+        // 保持ポリシー外のスナップショットを整理
+        let pruned_snapshots = if self.config.retention.is_enabled() {
+            prune(&self.config.dest_dir, &self.config.retention, false, encryptor.as_ref())?
+                .forgotten_snapshots
+                .len()
+        } else {
+            0
+        };
 
         let finished_at = Utc::now();
 
@@ -267,90 +457,172 @@ impl BackupExecutor {
         Ok(BackupResult {
             started_at,
             finished_at,
+            snapshot_id,
             backed_up_files,
             backed_up_bytes,
             skipped_files: skipped_count,
             failed_files,
+            pruned_snapshots,
+            recovery_phrase: recovery.map(|r| r.phrase()),
             success,
         })
     }
 
-    /// 差分バックアップ対象ファイルを計算
-    fn compute_incremental_files(&self, current_scan: &ScanResult) -> Result<(Vec<String>, usize), BackupError> {
-        let manifest_path = self.config.dest_dir.join("manifest.json");
+    /// 前回のマニフェストを読み込み（初回はNone）
+    fn load_previous_manifest(&self, encryptor: Option<&Encryptor>) -> Result<Option<BackupManifest>, BackupError> {
+        let manifest_path = self.config.dest_dir.join(MANIFEST_FILE);
 
         if manifest_path.exists() {
-            let manifest_data = fs::read_to_string(&manifest_path)?;
-            let manifest: BackupManifest = serde_json::from_str(&manifest_data)?;
+            Ok(Some(read_manifest_file(&self.config.dest_dir, &manifest_path, MANIFEST_CONTEXT, encryptor)?))
+        } else {
+            Ok(None)
+        }
+    }
 
-            // 前回のスキャン結果と比較
-            let diff = compute_diff_from_manifest(&manifest, current_scan);
+    /// 差分バックアップ対象ファイルを計算
+    fn compute_incremental_files(&self, current_scan: &ScanResult, previous: Option<&BackupManifest>) -> (Vec<String>, usize) {
+        match previous {
+            Some(manifest) => {
+                // 前回のスキャン結果と比較
+                let diff = compute_diff_from_manifest(manifest, current_scan);
 
-            let files_to_backup: Vec<String> = diff.added.into_iter()
-                .chain(diff.modified.into_iter())
-                .collect();
+                let files_to_backup: Vec<String> = diff.added.into_iter()
+                    .chain(diff.modified)
+                    .collect();
 
-            Ok((files_to_backup, diff.unchanged.len()))
-        } else {
+                (files_to_backup, diff.unchanged.len())
+            }
             // 初回バックアップ
-            Ok((current_scan.files.keys().cloned().collect(), 0))
+            None => (current_scan.files.keys().cloned().collect(), 0),
         }
     }
 
+    /// 単一ファイルをチャンク分割してバックアップ
+    fn backup_file_chunked(&self, relative_path: &str, store: &ChunkStore) -> Result<BackedUpFile, BackupError> {
+        let source_path = self.config.source_dir.join(relative_path);
+        let stored = store.store_file(BufReader::new(File::open(&source_path)?))?;
+
+        Ok(BackedUpFile {
+            original_size: stored.original_size,
+            stored_size: stored.stored_size,
+            chunks: Some(stored.chunks),
+            blob: None,
+        })
+    }
+
     /// 単一ファイルをバックアップ
-    fn backup_file(&self, relative_path: &str) -> Result<u64, BackupError> {
+    ///
+    /// 過去のスナップショットのファイルを上書きしないよう、`data/<スナップショットID>/` 以下に保存する。
+    fn backup_file(
+        &self,
+        relative_path: &str,
+        snapshot_id: &str,
+        encryptor: Option<&Encryptor>,
+    ) -> Result<BackedUpFile, BackupError> {
         let source_path = self.config.source_dir.join(relative_path);
-        let dest_path = self.config.dest_dir.join("data").join(relative_path);
+        let encryptor = encryptor.filter(|_| self.config.encrypt);
+
+        // 暗号化する場合は.enc拡張子（名前を秘匿する場合は鍵付きハッシュのみ）
+        let blob = match encryptor {
+            Some(encryptor) if self.config.opaque_names => {
+                let name = encryptor.opaque_name(&format!("{}/{}", snapshot_id, relative_path))?;
+                format!("data/{}/{}/{}.enc", snapshot_id, &name[..2], name)
+            }
+            Some(_) => format!("data/{}/{}.enc", snapshot_id, relative_path),
+            None => format!("data/{}/{}", snapshot_id, relative_path),
+        };
+        let dest_path = self.config.dest_dir.join(&blob);
 
         // 親ディレクトリを作成
         if let Some(parent) = dest_path.parent() {
             fs::create_dir_all(parent)?;
         }
 
-        // ファイルを読み込み
-        let mut data = Vec::new();
-        File::open(&source_path)?.read_to_end(&mut data)?;
-        let original_size = data.len() as u64;
+        // 読み込み側で圧縮し、書き込み側で暗号化する（ファイル全体をメモリに載せない）
+        let source = File::open(&source_path)?;
+        let original_size = source.metadata()?.len();
 
-        // 圧縮
-        let data = if self.config.compress {
-            zstd::encode_all(data.as_slice(), 3)
-                .map_err(|_| BackupError::Compression)?
+        let mut reader: Box<dyn Read> = if self.config.compress {
+            Box::new(
+                zstd::stream::read::Encoder::new(BufReader::new(source), 3)
+                    .map_err(|_| BackupError::Compression)?,
+            )
         } else {
-            data
+            Box::new(BufReader::new(source))
         };
 
         // 暗号化
-        let (data, dest_path) = if self.config.encrypt {
-            if let Some(ref encryptor) = self.encryptor {
-                let encrypted = encryptor.encrypt(&data)?;
-                let mut encrypted_path = dest_path;
-                encrypted_path.set_extension(
-                    encrypted_path.extension()
-                        .map(|e| format!("{}.enc", e.to_string_lossy()))
-                        .unwrap_or_else(|| "enc".to_string())
-                );
-                (encrypted, encrypted_path)
-            } else {
-                (data, dest_path)
+        let dest = BufWriter::new(File::create(&dest_path)?);
+        let dest = match encryptor {
+            Some(encryptor) => {
+                let mut writer = encryptor.encrypt_writer(dest, &format!("blob:{}", blob))?;
+                io::copy(&mut reader, &mut writer)?;
+                writer.finish()?
+            }
+            None => {
+                let mut writer = dest;
+                io::copy(&mut reader, &mut writer)?;
+                writer
             }
-        } else {
-            (data, dest_path)
         };
+        let stored_size = dest.into_inner().map_err(|e| e.into_error())?.metadata()?.len();
 
-        // 書き込み
-        let mut file = File::create(&dest_path)?;
-        file.write_all(&data)?;
-
-        Ok(original_size)
+        Ok(BackedUpFile {
+            original_size,
+            stored_size,
+            chunks: None,
+            blob: Some(blob),
+        })
     }
 
     /// マニフェストを保存
-    fn save_manifest(&self, scan: &ScanResult) -> Result<(), BackupError> {
-        let manifest = BackupManifest::from_scan(scan, &self.config);
-        let manifest_path = self.config.dest_dir.join("manifest.json");
-        let data = serde_json::to_string_pretty(&manifest)?;
-        fs::write(manifest_path, data)?;
+    ///
+    /// 今回バックアップしたファイルは保存先の情報を記録し、スキップしたファイルは
+    /// 前回のエントリを引き継ぐ。バックアップに失敗したファイルは前回のエントリを残す。
+    fn save_manifest(
+        &self,
+        scan: &ScanResult,
+        snapshot: SnapshotMeta,
+        previous: Option<&BackupManifest>,
+        backed_up: &HashMap<String, BackedUpFile>,
+        encryptor: Option<&Encryptor>,
+    ) -> Result<(), BackupError> {
+        let mut manifest = BackupManifest::from_scan(scan, &self.config);
+        manifest.config.keyed_hashes = encryptor.and_then(Encryptor::content_hasher).is_some();
+        // ハッシュの方式が変わった場合、前回のエントリを引き継ぐとハッシュが混在する
+        let carried = previous.filter(|m| m.config.keyed_hashes == manifest.config.keyed_hashes);
+
+        manifest.files.retain(|path, entry| {
+            if let Some(file) = backed_up.get(path) {
+                entry.backed_up_size = file.stored_size;
+                entry.chunks = file.chunks.clone();
+                entry.blob = file.blob.clone();
+                true
+            } else if let Some(old) = carried.and_then(|m| m.files.get(path)) {
+                *entry = old.clone();
+                true
+            } else {
+                false
+            }
+        });
+
+        manifest.stats.total_files = manifest.files.len();
+        manifest.stats.total_original_size = manifest.files.values().map(|e| e.original_size).sum();
+        manifest.stats.total_backed_up_size = manifest.files.values().map(|e| e.backed_up_size).sum();
+        if let Some(previous) = previous {
+            manifest.created_at = previous.created_at;
+            manifest.stats.backup_count = previous.stats.backup_count + 1;
+        }
+        manifest.snapshot = Some(snapshot);
+
+        // 不変のスナップショットを先に書き込み、最新の写しとして manifest.json を更新する
+        let signer = self.signer.as_ref();
+        write_snapshot(&self.config.dest_dir, &manifest, encryptor, signer)?;
+
+        let manifest_path = self.config.dest_dir.join(MANIFEST_FILE);
+        let data = encode_manifest(&manifest, MANIFEST_CONTEXT, encryptor)?;
+        fs::write(&manifest_path, &data)?;
+        write_signature(&manifest_path, &data, MANIFEST_CONTEXT, signer)?;
         Ok(())
     }
 
@@ -419,7 +691,7 @@ mod tests {
             encrypt: false,
             compress: true,
             incremental: false,
-            exclude_patterns: vec![],
+            ..BackupConfig::default()
         };
 
         let executor = BackupExecutor::new(config);
@@ -428,4 +700,143 @@ mod tests {
         assert!(result.success);
         assert_eq!(result.backed_up_files, 1);
     }
+
+    #[test]
+    fn test_filters_are_recorded() {
+        let source = TempDir::new().unwrap();
+        let dest = TempDir::new().unwrap();
+        fs::write(source.path().join("keep.txt"), "keep").unwrap();
+        fs::write(source.path().join("skip.tmp"), "skip").unwrap();
+
+        let filters = ScanFilters {
+            exclude: vec!["*.tmp".to_string()],
+            max_file_size: Some(1 << 20),
+            ..ScanFilters::default()
+        };
+        let config = BackupConfig {
+            source_dir: source.path().to_path_buf(),
+            dest_dir: dest.path().to_path_buf(),
+            filters: filters.clone(),
+            ..BackupConfig::default()
+        };
+        assert_eq!(BackupExecutor::new(config).execute().unwrap().backed_up_files, 1);
+
+        let manifest = crate::backup::load_backup_manifest(&dest.path().to_path_buf(), None).unwrap();
+        assert_eq!(manifest.files.keys().collect::<Vec<_>>(), vec!["keep.txt"]);
+        assert_eq!(manifest.config.filters, Some(filters));
+    }
+
+    #[test]
+    fn test_cipher_is_repository_setting() {
+        let source = TempDir::new().unwrap();
+        let dest = TempDir::new().unwrap();
+        fs::write(source.path().join("test.txt"), "Hello, Backup!").unwrap();
+
+        let config = |cipher| BackupConfig {
+            source_dir: source.path().to_path_buf(),
+            dest_dir: dest.path().to_path_buf(),
+            cipher,
+            ..BackupConfig::default()
+        };
+        let backup = |cipher| BackupExecutor::new(config(cipher))
+            .with_encryption("test_password_123")
+            .execute();
+
+        // 作成時の暗号スイートがキーファイルに記録され、以降は省略しても引き継ぐ
+        let first = backup(Some(CipherSuite::XChaCha20Poly1305)).unwrap();
+        assert!(first.success);
+        let keyfile = load_keyfile(dest.path()).unwrap().unwrap();
+        assert_eq!(keyfile.cipher, CipherSuite::XChaCha20Poly1305);
+
+        // 回復フレーズはリポジトリ作成時のみ返される
+        assert!(first.recovery_phrase.is_some() && keyfile.recovery.is_some());
+
+        fs::write(source.path().join("test.txt"), "Hello again!").unwrap();
+        let second = backup(None).unwrap();
+        assert!(second.success);
+        assert!(second.recovery_phrase.is_none());
+        assert!(matches!(
+            backup(Some(CipherSuite::Aes256Gcm)),
+            Err(BackupError::Repository(crate::backup::RepositoryError::CipherMismatch { .. }))
+        ));
+    }
+
+    #[test]
+    fn test_keyed_content_hashes() {
+        let source = TempDir::new().unwrap();
+        let dest = TempDir::new().unwrap();
+        let data = "Hello, Backup!";
+        fs::write(source.path().join("test.txt"), data).unwrap();
+
+        let backup = || BackupExecutor::new(BackupConfig {
+            source_dir: source.path().to_path_buf(),
+            dest_dir: dest.path().to_path_buf(),
+            ..BackupConfig::default()
+        })
+            .with_encryption("test_password_123")
+            .execute()
+            .unwrap();
+        assert_eq!(backup().backed_up_files, 1);
+
+        // ファイルのハッシュ・チャンクIDとも平文の BLAKE3 とは一致しない
+        let (encryptor, _) = open_or_init_encryptor(dest.path(), "test_password_123", KdfParams::default(), None).unwrap();
+        let manifest = crate::backup::load_backup_manifest(&dest.path().to_path_buf(), Some(&encryptor)).unwrap();
+        let plain = blake3::hash(data.as_bytes()).to_hex().to_string();
+        let entry = &manifest.files["test.txt"];
+        assert!(manifest.config.keyed_hashes);
+        assert_ne!(entry.hash, plain);
+        assert!(!entry.chunks.as_ref().unwrap().contains(&plain));
+
+        // 鍵付きのハッシュでも変更のないファイルは差分で除外され、検証も通る
+        let second = backup();
+        assert_eq!((second.backed_up_files, second.skipped_files), (0, 1));
+        let report = crate::backup::verify_backup(dest.path(), None, crate::backup::VerifyMode::Full, Some(&crate::backup::RepositoryKey::Password("test_password_123".into())), &[]).unwrap();
+        assert!(report.is_ok());
+    }
+
+    #[test]
+    fn test_recipient_chunk_ids_are_keyed() {
+        let source = TempDir::new().unwrap();
+        let dest = TempDir::new().unwrap();
+        let data = "Hello, Backup!";
+        fs::write(source.path().join("test.txt"), data).unwrap();
+
+        let recipient = crate::crypto::Identity::generate().recipient();
+        let backup = |content_key: Option<ContentKey>| {
+            let executor = BackupExecutor::new(BackupConfig {
+                source_dir: source.path().to_path_buf(),
+                dest_dir: dest.path().to_path_buf(),
+                ..BackupConfig::default()
+            })
+                .with_recipients(vec![recipient]);
+            match content_key {
+                Some(key) => executor.with_content_key(key),
+                None => executor,
+            }
+            .execute()
+        };
+
+        // 内容ID用の鍵なしではチャンク分割できない
+        assert!(matches!(backup(None), Err(BackupError::ContentKeyRequired)));
+
+        let content_key = ContentKey::generate();
+        assert!(backup(Some(content_key.clone())).unwrap().success);
+
+        // チャンクのファイル名は平文の BLAKE3 と一致しない
+        let plain = blake3::hash(data.as_bytes()).to_hex().to_string();
+        let names: Vec<String> = fs::read_dir(dest.path().join(crate::backup::CHUNKS_DIR))
+            .unwrap()
+            .flat_map(|dir| fs::read_dir(dir.unwrap().path()).unwrap())
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        assert_eq!(names.len(), 1);
+        assert!(!names.contains(&plain));
+
+        // 別の鍵ではリポジトリに記録された鍵と一致しない
+        assert!(matches!(
+            backup(Some(ContentKey::generate())),
+            Err(BackupError::Repository(crate::backup::RepositoryError::Crypto(crate::crypto::CryptoError::ContentKeyMismatch)))
+        ));
+        assert!(backup(Some(content_key)).unwrap().success);
+    }
 }
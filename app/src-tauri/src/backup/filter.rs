@@ -0,0 +1,265 @@
+//! ファイル単位の絞り込み - サイズ・更新日時・内容の種類による除外
+//!
+//! 除外パターンを通過したファイルに条件を順に適用し、最初に一致した条件を除外理由とする。
+//! 内容の種類は拡張子ではなく先頭のマジックバイトから判定する（`infer` の判定にディスクイメージを追加）。
+//! Apple のディスクイメージ（.dmg）のように末尾にしか識別子のない形式は判定できない。
+
+use super::{ExcludeReason, FileInfo, ScanFilters};
+use chrono::{DateTime, Duration, Utc};
+use infer::Infer;
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+
+/// 内容の判定に読み込む先頭のバイト数（ISO 9660 のボリューム記述子 0x8001 を含む）
+const SNIFF_LEN: u64 = 0x8800;
+
+/// マジックバイトで判定できるディスクイメージの MIME タイプ
+pub const DISK_IMAGE_CONTENT_TYPES: &[&str] = &[
+    "application/x-iso9660-image",
+    "application/x-qemu-disk",
+    "application/x-vmdk",
+    "application/x-vhd",
+    "application/x-vhdx",
+    "application/x-virtualbox-vdi",
+];
+
+/// ファイル単位の絞り込み条件
+pub trait FileFilter: Send + Sync {
+    /// 除外する場合はその理由
+    ///
+    /// # Arguments
+    /// * `file` - 走査したファイルの情報
+    /// * `path` - ファイルのフルパス（内容を読む条件のみ使用）
+    fn check(&self, file: &FileInfo, path: &Path) -> io::Result<Option<ExcludeReason>>;
+}
+
+/// サイズが上限を超えるファイルを除外
+pub struct MaxSizeFilter {
+    limit: u64,
+}
+
+impl MaxSizeFilter {
+    pub fn new(limit: u64) -> Self {
+        Self { limit }
+    }
+}
+
+impl FileFilter for MaxSizeFilter {
+    fn check(&self, file: &FileInfo, _path: &Path) -> io::Result<Option<ExcludeReason>> {
+        Ok((file.size > self.limit).then_some(ExcludeReason::TooLarge {
+            size: file.size,
+            limit: self.limit,
+        }))
+    }
+}
+
+/// 最終更新から指定した日数を超えたファイルを除外
+pub struct MaxAgeFilter {
+    max_age_days: u32,
+    now: DateTime<Utc>,
+}
+
+impl MaxAgeFilter {
+    /// # Arguments
+    /// * `max_age_days` - 最終更新からの日数の上限
+    /// * `now` - 基準日時（スキャン日時）
+    pub fn new(max_age_days: u32, now: DateTime<Utc>) -> Self {
+        Self { max_age_days, now }
+    }
+}
+
+impl FileFilter for MaxAgeFilter {
+    fn check(&self, file: &FileInfo, _path: &Path) -> io::Result<Option<ExcludeReason>> {
+        let too_old = self.now - file.modified > Duration::days(self.max_age_days.into());
+        Ok(too_old.then_some(ExcludeReason::TooOld {
+            modified: file.modified,
+            max_age_days: self.max_age_days,
+        }))
+    }
+}
+
+/// 内容の種類（MIME タイプ）で除外
+///
+/// パターンは `application/x-iso9660-image` のような MIME タイプか、`video/*` のような種類ごとの指定。
+/// 種類を判定できないファイルは除外しない。
+pub struct ContentTypeFilter {
+    patterns: Vec<String>,
+    infer: Infer,
+}
+
+impl ContentTypeFilter {
+    pub fn new(patterns: &[String]) -> Self {
+        let mut infer = Infer::new();
+        infer.add("application/x-iso9660-image", "iso", is_iso);
+        infer.add("application/x-qemu-disk", "qcow2", is_qcow);
+        infer.add("application/x-vmdk", "vmdk", is_vmdk);
+        infer.add("application/x-vhd", "vhd", is_vhd);
+        infer.add("application/x-vhdx", "vhdx", is_vhdx);
+        infer.add("application/x-virtualbox-vdi", "vdi", is_vdi);
+
+        Self {
+            patterns: patterns.iter().map(|p| p.trim().to_ascii_lowercase()).collect(),
+            infer,
+        }
+    }
+
+    /// ファイルの先頭を読んで MIME タイプを判定
+    pub fn sniff(&self, path: &Path) -> io::Result<Option<&'static str>> {
+        let mut head = Vec::new();
+        File::open(path)?.take(SNIFF_LEN).read_to_end(&mut head)?;
+        Ok(self.infer.get(&head).map(|kind| kind.mime_type()))
+    }
+
+    /// MIME タイプがパターンのいずれかに一致するか
+    fn matches(&self, content_type: &str) -> bool {
+        self.patterns.iter().any(|pattern| match pattern.strip_suffix("/*") {
+            Some(top) => content_type.split('/').next() == Some(top),
+            None => pattern == content_type,
+        })
+    }
+}
+
+impl FileFilter for ContentTypeFilter {
+    fn check(&self, _file: &FileInfo, path: &Path) -> io::Result<Option<ExcludeReason>> {
+        Ok(self.sniff(path)?
+            .filter(|content_type| self.matches(content_type))
+            .map(|content_type| ExcludeReason::ContentType {
+                content_type: content_type.to_string(),
+            }))
+    }
+}
+
+/// 絞り込み条件を順に適用するパイプライン
+#[derive(Default)]
+pub struct FilterPipeline {
+    filters: Vec<Box<dyn FileFilter>>,
+}
+
+impl FilterPipeline {
+    /// スキャンの設定から作成（ファイルを読まない条件を先に判定する）
+    pub fn from_filters(filters: &ScanFilters, now: DateTime<Utc>) -> Self {
+        let mut pipeline = Self::default();
+        if let Some(limit) = filters.max_file_size {
+            pipeline.push(MaxSizeFilter::new(limit));
+        }
+        if let Some(days) = filters.max_age_days {
+            pipeline.push(MaxAgeFilter::new(days, now));
+        }
+        if !filters.exclude_content_types.is_empty() {
+            pipeline.push(ContentTypeFilter::new(&filters.exclude_content_types));
+        }
+        pipeline
+    }
+
+    /// 条件を末尾に追加
+    pub fn push(&mut self, filter: impl FileFilter + 'static) {
+        self.filters.push(Box::new(filter));
+    }
+
+    /// 最初に一致した条件の除外理由
+    pub fn check(&self, file: &FileInfo, path: &Path) -> io::Result<Option<ExcludeReason>> {
+        for filter in &self.filters {
+            if let Some(reason) = filter.check(file, path)? {
+                return Ok(Some(reason));
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// ISO 9660 / UDF（セクタ16のボリューム記述子）
+fn is_iso(buf: &[u8]) -> bool {
+    buf.get(0x8001..0x8006).is_some_and(|id| id == b"CD001" || id == b"BEA01")
+}
+
+/// QEMU Copy-On-Write
+fn is_qcow(buf: &[u8]) -> bool {
+    buf.starts_with(b"QFI\xfb")
+}
+
+/// VMware（スパースエクステントまたは記述子ファイル）
+fn is_vmdk(buf: &[u8]) -> bool {
+    buf.starts_with(b"KDMV") || buf.starts_with(b"# Disk DescriptorFile")
+}
+
+/// Virtual PC（可変長ディスクは先頭にフッタの複製を持つ）
+fn is_vhd(buf: &[u8]) -> bool {
+    buf.starts_with(b"conectix")
+}
+
+/// Hyper-V
+fn is_vhdx(buf: &[u8]) -> bool {
+    buf.starts_with(b"vhdxfile")
+}
+
+/// VirtualBox
+fn is_vdi(buf: &[u8]) -> bool {
+    buf.get(0x40..0x44).is_some_and(|magic| magic == [0x7f, 0x10, 0xda, 0xbe])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_sniff_content_type() {
+        let temp = TempDir::new().unwrap();
+        let mut iso = vec![0u8; 0x9000];
+        iso[0x8001..0x8006].copy_from_slice(b"CD001");
+        let files: [(&str, Vec<u8>); 4] = [
+            ("disk.img", iso),
+            ("vm.bin", b"QFI\xfb\x00\x00\x00\x03".to_vec()),
+            ("photo.dat", b"\x89PNG\r\n\x1a\n\x00\x00\x00\x0dIHDR".to_vec()),
+            ("notes.iso", b"plain text".to_vec()),
+        ];
+        for (name, data) in &files {
+            fs::write(temp.path().join(name), data).unwrap();
+        }
+
+        // 拡張子ではなく内容で判定する
+        let filter = ContentTypeFilter::new(&[]);
+        let sniff = |name: &str| filter.sniff(&temp.path().join(name)).unwrap();
+        assert_eq!(sniff("disk.img"), Some("application/x-iso9660-image"));
+        assert_eq!(sniff("vm.bin"), Some("application/x-qemu-disk"));
+        assert_eq!(sniff("photo.dat"), Some("image/png"));
+        assert_eq!(sniff("notes.iso"), None);
+
+        let patterns: Vec<String> = DISK_IMAGE_CONTENT_TYPES.iter()
+            .map(|t| t.to_string())
+            .chain(["Image/*".to_string()])
+            .collect();
+        let filter = ContentTypeFilter::new(&patterns);
+        let excluded: Vec<_> = files.iter()
+            .filter(|(name, _)| {
+                let path = temp.path().join(name);
+                let info = FileInfo::from_path(temp.path(), &path).unwrap();
+                filter.check(&info, &path).unwrap().is_some()
+            })
+            .map(|(name, _)| *name)
+            .collect();
+        assert_eq!(excluded, vec!["disk.img", "vm.bin", "photo.dat"]);
+    }
+
+    #[test]
+    fn test_pipeline_order() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("vm.qcow2");
+        fs::write(&path, b"QFI\xfb\x00\x00\x00\x03").unwrap();
+        let info = FileInfo::from_path(temp.path(), &path).unwrap();
+
+        let filters = ScanFilters {
+            max_file_size: Some(4),
+            exclude_content_types: vec!["application/x-qemu-disk".to_string()],
+            ..ScanFilters::default()
+        };
+        // 複数の条件に一致する場合は先に判定した条件を理由とする
+        let reason = FilterPipeline::from_filters(&filters, Utc::now()).check(&info, &path).unwrap();
+        assert_eq!(reason, Some(ExcludeReason::TooLarge { size: 8, limit: 4 }));
+
+        let pipeline = FilterPipeline::from_filters(&ScanFilters::default(), Utc::now());
+        assert_eq!(pipeline.check(&info, &path).unwrap(), None);
+    }
+}
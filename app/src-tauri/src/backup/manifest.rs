@@ -1,10 +1,28 @@
 //! バックアップマニフェスト - バックアップの状態を記録
 
-use super::{ScanResult, BackupConfig};
+use super::{is_valid_chunk_id, ScanResult, ScanFilters, BackupConfig, SnapshotMeta, RestoreError, KEYFILE_NAME};
+use crate::crypto::Encryptor;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::Path;
 use chrono::{DateTime, Utc};
 
+/// 最新マニフェストのファイル名
+pub const MANIFEST_FILE: &str = "manifest.json";
+
+/// ファイル単位の保存先ディレクトリ
+const DATA_DIR: &str = "data";
+
+/// 最新マニフェストの暗号化コンテキスト（関連データとして認証する）
+pub const MANIFEST_CONTEXT: &str = "manifest";
+
+/// スナップショットの暗号化コンテキスト
+pub fn snapshot_context(id: &str) -> String {
+    format!("snapshot:{}", id)
+}
+
 /// マニフェストエントリ（ファイルごとの情報）
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ManifestEntry {
@@ -28,6 +46,54 @@ pub struct ManifestEntry {
 
     /// 圧縮されているか
     pub compressed: bool,
+
+    /// チャンクID一覧（ファイル内の順序、data/ にファイル単位で保存した場合はNone）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub chunks: Option<Vec<String>>,
+
+    /// 保存先（リポジトリからの相対パス、v0.1.x 形式やチャンク分割時はNone）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub blob: Option<String>,
+}
+
+impl ManifestEntry {
+    /// ファイル単位で保存した場合の保存先（リポジトリからの相対パス）
+    ///
+    /// v0.1.x 形式では `data/<相対パス>`（暗号化時は `.enc` を付加）に保存されている。
+    /// `data/` 以下を指さない保存先（絶対パスや `..` を含むもの）はエラーとする。
+    pub fn blob_path(&self) -> Result<String, RestoreError> {
+        let blob = match &self.blob {
+            Some(blob) => blob.clone(),
+            None if self.encrypted => format!("{}/{}.enc", DATA_DIR, self.path),
+            None => format!("{}/{}", DATA_DIR, self.path),
+        };
+        if !is_safe_blob_path(&blob) {
+            return Err(RestoreError::InvalidEntry(format!("{}（保存先: {}）", self.path, blob)));
+        }
+        Ok(blob)
+    }
+
+    /// 保存先を組み立てる前にエントリの形式を確認
+    ///
+    /// 平文のマニフェストは認証されないため、別の場所から持ち込んだリポジトリでも安全に扱えるようにする。
+    pub fn validate(&self) -> Result<(), RestoreError> {
+        match &self.chunks {
+            Some(chunks) => match chunks.iter().find(|id| !is_valid_chunk_id(id)) {
+                Some(id) => Err(RestoreError::InvalidEntry(format!("{}（チャンクID: {}）", self.path, id))),
+                None => Ok(()),
+            },
+            None => self.blob_path().map(|_| ()),
+        }
+    }
+}
+
+/// 保存先が `data/` 以下を指す相対パスか（区切りは `/` のみ、空・`.`・`..` の要素を含まない）
+fn is_safe_blob_path(blob: &str) -> bool {
+    let mut parts = blob.split('/');
+    parts.next() == Some(DATA_DIR)
+        && !blob.contains(['\\', '\0'])
+        && blob.len() > DATA_DIR.len() + 1
+        && parts.all(|part| !matches!(part, "" | "." | ".."))
 }
 
 /// バックアップマニフェスト
@@ -53,6 +119,10 @@ pub struct BackupManifest {
 
     /// 統計情報
     pub stats: ManifestStats,
+
+    /// スナップショット情報（v0.1.x で作成したマニフェストはNone）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub snapshot: Option<SnapshotMeta>,
 }
 
 /// マニフェストに保存する設定
@@ -61,6 +131,14 @@ pub struct ManifestConfig {
     pub encrypt: bool,
     pub compress: bool,
     pub incremental: bool,
+
+    /// ファイルのハッシュがリポジトリ鍵による鍵付き BLAKE3 か（旧形式は平文の BLAKE3）
+    #[serde(default)]
+    pub keyed_hashes: bool,
+
+    /// このジョブの絞り込み条件（次回のバックアップで指定を省略した場合に使う）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub filters: Option<ScanFilters>,
 }
 
 /// 統計情報
@@ -97,6 +175,8 @@ impl BackupManifest {
                     modified: info.modified,
                     encrypted: config.encrypt,
                     compressed: config.compress,
+                    chunks: None,
+                    blob: None,
                 };
                 (path.clone(), entry)
             })
@@ -111,6 +191,8 @@ impl BackupManifest {
                 encrypt: config.encrypt,
                 compress: config.compress,
                 incremental: config.incremental,
+                keyed_hashes: false, // 鍵付きで計算した場合は保存時に設定
+                filters: Some(config.filters.clone()),
             },
             files,
             stats: ManifestStats {
@@ -120,6 +202,16 @@ impl BackupManifest {
                 last_backup: now,
                 backup_count: 1,
             },
+            snapshot: None,
+        }
+    }
+
+    /// 記録されたハッシュの検証に使う計算器
+    pub fn content_hasher(&self, encryptor: Option<&Encryptor>) -> blake3::Hasher {
+        if self.config.keyed_hashes {
+            content_hasher(encryptor)
+        } else {
+            blake3::Hasher::new()
         }
     }
 
@@ -145,6 +237,8 @@ impl BackupManifest {
                     modified: info.modified,
                     encrypted: self.config.encrypt,
                     compressed: self.config.compress,
+                    chunks: None,
+                    blob: None,
                 });
         }
 
@@ -159,6 +253,61 @@ impl BackupManifest {
     }
 }
 
+/// 内容ID（ファイルのハッシュ・チャンクID）の計算器
+///
+/// マスター鍵を持つ暗号化リポジトリではリポジトリ鍵による鍵付き BLAKE3 を使い、
+/// 既知のファイルのハッシュとの照合で中身を推測されないようにする。
+pub fn content_hasher(encryptor: Option<&Encryptor>) -> blake3::Hasher {
+    encryptor.and_then(Encryptor::content_hasher).unwrap_or_default()
+}
+
+/// マニフェストをファイル形式に変換（暗号化エンジンがあれば暗号化する）
+///
+/// 暗号化したマニフェストはファイル名・サイズ・ハッシュなどを漏らさず、改ざんも検出できる。
+/// `context` は保存先（`MANIFEST_CONTEXT` または `snapshot_context`）で、別の保存先への差し替えを検出する。
+pub fn encode_manifest(
+    manifest: &BackupManifest,
+    context: &str,
+    encryptor: Option<&Encryptor>,
+) -> io::Result<Vec<u8>> {
+    let data = serde_json::to_vec_pretty(manifest)?;
+    match encryptor {
+        Some(encryptor) => encryptor.seal_metadata(&data, context)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e)),
+        None => Ok(data),
+    }
+}
+
+/// マニフェストファイルを読み込み
+///
+/// 暗号化リポジトリ（キーファイルあり）、およびマニフェストを暗号化できる鍵を指定した場合は、
+/// キーファイルの有無にかかわらず平文のマニフェストを差し替えられたものとみなして拒否する。
+/// キーファイル導入前のパスワードのみの鍵ではマニフェストを暗号化しないため、平文を受け付ける。
+pub fn read_manifest_file(
+    repo_dir: &Path,
+    path: &Path,
+    context: &str,
+    encryptor: Option<&Encryptor>,
+) -> Result<BackupManifest, RestoreError> {
+    let data = fs::read(path)?;
+
+    let data = if Encryptor::is_sealed_metadata(&data) {
+        let encryptor = encryptor.ok_or(RestoreError::ManifestEncrypted)?;
+        encryptor.open_metadata(&data, context)
+            .map_err(|_| RestoreError::ManifestTampered(path.to_path_buf()))?
+    } else if encryptor.is_some_and(Encryptor::can_open_metadata) || repo_dir.join(KEYFILE_NAME).exists() {
+        return Err(RestoreError::ManifestTampered(path.to_path_buf()));
+    } else {
+        data
+    };
+
+    let manifest: BackupManifest = serde_json::from_slice(&data)?;
+    for entry in manifest.files.values() {
+        entry.validate()?;
+    }
+    Ok(manifest)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -182,6 +331,8 @@ mod tests {
             files,
             total_files: 1,
             total_size: 1000,
+            ignore_files: vec![],
+            excluded: vec![],
         };
 
         let config = BackupConfig::default();
@@ -190,4 +341,127 @@ mod tests {
         assert_eq!(manifest.stats.total_files, 1);
         assert!(manifest.files.contains_key("test.txt"));
     }
+
+    #[test]
+    fn test_rejects_blob_paths_outside_data() {
+        let mut entry = ManifestEntry {
+            path: "a.txt".to_string(),
+            original_size: 1,
+            backed_up_size: 1,
+            hash: String::new(),
+            modified: Utc::now(),
+            encrypted: false,
+            compressed: false,
+            chunks: None,
+            blob: None,
+        };
+        assert_eq!(entry.blob_path().unwrap(), "data/a.txt");
+
+        for blob in ["/etc/passwd", "../secret", "data/../../secret", "data/", "data//x", "data\\..\\x", "keys.json", "C:/x"] {
+            entry.blob = Some(blob.to_string());
+            assert!(matches!(entry.blob_path(), Err(RestoreError::InvalidEntry(_))), "{}", blob);
+            assert!(entry.validate().is_err());
+        }
+
+        // v0.1.x 形式でもパスに `..` を含めば拒否する
+        entry.blob = None;
+        entry.path = "../outside.txt".to_string();
+        assert!(entry.validate().is_err());
+    }
+
+    #[test]
+    fn test_rejects_invalid_chunk_ids() {
+        let repo = tempfile::TempDir::new().unwrap();
+        let path = repo.path().join(MANIFEST_FILE);
+        let mut manifest = BackupManifest::from_scan(&ScanResult {
+            source_dir: PathBuf::from("/test"),
+            scanned_at: Utc::now(),
+            files: HashMap::new(),
+            total_files: 0,
+            total_size: 0,
+            ignore_files: vec![],
+            excluded: vec![],
+        }, &BackupConfig::default());
+
+        for id in ["../../x".to_string(), "aé".to_string(), "A".repeat(64), "0".repeat(63)] {
+            manifest.files.insert("a.txt".to_string(), ManifestEntry {
+                path: "a.txt".to_string(),
+                original_size: 1,
+                backed_up_size: 1,
+                hash: String::new(),
+                modified: Utc::now(),
+                encrypted: false,
+                compressed: false,
+                chunks: Some(vec![id]),
+                blob: None,
+            });
+            fs::write(&path, encode_manifest(&manifest, MANIFEST_CONTEXT, None).unwrap()).unwrap();
+            assert!(matches!(
+                read_manifest_file(repo.path(), &path, MANIFEST_CONTEXT, None),
+                Err(RestoreError::InvalidEntry(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_sealed_manifest_detects_tampering() {
+        use crate::backup::{open_or_init_encryptor, MANIFEST_FILE};
+        use crate::crypto::KdfParams;
+
+        let repo = tempfile::TempDir::new().unwrap();
+        let kdf = KdfParams::for_tests();
+        let (encryptor, _) = open_or_init_encryptor(repo.path(), "password_one", kdf, None).unwrap();
+
+        let scan = ScanResult {
+            source_dir: PathBuf::from("/secret-project"),
+            scanned_at: Utc::now(),
+            files: HashMap::new(),
+            total_files: 0,
+            total_size: 0,
+            ignore_files: vec![],
+            excluded: vec![],
+        };
+        let manifest = BackupManifest::from_scan(&scan, &BackupConfig::default());
+        let path = repo.path().join(MANIFEST_FILE);
+
+        // 暗号化したマニフェストはディレクトリ名などを漏らさない
+        let sealed = encode_manifest(&manifest, MANIFEST_CONTEXT, Some(&encryptor)).unwrap();
+        assert!(!String::from_utf8_lossy(&sealed).contains("secret-project"));
+        fs::write(&path, &sealed).unwrap();
+        assert!(read_manifest_file(repo.path(), &path, MANIFEST_CONTEXT, Some(&encryptor)).is_ok());
+        assert!(matches!(read_manifest_file(repo.path(), &path, MANIFEST_CONTEXT, None), Err(RestoreError::ManifestEncrypted)));
+
+        // 書き換え・平文への差し替えは検出する
+        let mut tampered = sealed.clone();
+        *tampered.last_mut().unwrap() ^= 0x01;
+        fs::write(&path, &tampered).unwrap();
+        assert!(matches!(
+            read_manifest_file(repo.path(), &path, MANIFEST_CONTEXT, Some(&encryptor)),
+            Err(RestoreError::ManifestTampered(_))
+        ));
+
+        // スナップショットを manifest.json として持ち込んでも認証に失敗する
+        fs::write(&path, encode_manifest(&manifest, &snapshot_context("old"), Some(&encryptor)).unwrap()).unwrap();
+        assert!(matches!(
+            read_manifest_file(repo.path(), &path, MANIFEST_CONTEXT, Some(&encryptor)),
+            Err(RestoreError::ManifestTampered(_))
+        ));
+
+        fs::write(&path, encode_manifest(&manifest, MANIFEST_CONTEXT, None).unwrap()).unwrap();
+        assert!(matches!(
+            read_manifest_file(repo.path(), &path, MANIFEST_CONTEXT, Some(&encryptor)),
+            Err(RestoreError::ManifestTampered(_))
+        ));
+
+        // キーファイルを削除しても、鍵を指定していれば平文への差し替えを検出する
+        fs::remove_file(repo.path().join(KEYFILE_NAME)).unwrap();
+        assert!(matches!(
+            read_manifest_file(repo.path(), &path, MANIFEST_CONTEXT, Some(&encryptor)),
+            Err(RestoreError::ManifestTampered(_))
+        ));
+
+        // キーファイル導入前のパスワードのみの鍵は平文のマニフェストを読める
+        let legacy = Encryptor::new("password_one");
+        assert!(read_manifest_file(repo.path(), &path, MANIFEST_CONTEXT, Some(&legacy)).is_ok());
+    }
 }
@@ -2,11 +2,29 @@
 //! 差分検出、ファイルコピー、バックアップ管理、復元を担当
 
 mod scanner;
+mod rules;
+mod filter;
 mod executor;
 mod manifest;
 mod restore;
+mod repository;
+mod chunks;
+mod snapshot;
+mod retention;
+mod prune;
+mod verify;
+mod signature;
 
 pub use scanner::*;
+pub use rules::*;
+pub use filter::*;
 pub use executor::*;
 pub use manifest::*;
 pub use restore::*;
+pub use repository::*;
+pub use chunks::*;
+pub use snapshot::*;
+pub use retention::*;
+pub use prune::*;
+pub use verify::*;
+pub use signature::*;
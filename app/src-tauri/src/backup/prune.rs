@@ -0,0 +1,304 @@
+//! 整理（prune） - 保持ポリシー外のスナップショットの削除と不要データの回収
+//!
+//! スナップショットを削除した後、残りのスナップショットと `manifest.json` の
+//! いずれからも参照されないチャンク・ファイルを `chunks/` と `data/` から削除する。
+//! バックアップ実行中のリポジトリに対しては実行しないこと（書き込み途中のチャンクを回収してしまう）。
+
+use super::{
+    list_snapshots, load_backup_manifest, load_snapshot, signature_path, BackupManifest, ChunkStore,
+    RestoreError, RetentionDecision, RetentionPolicy, CHUNKS_DIR, SNAPSHOTS_DIR,
+};
+use crate::crypto::Encryptor;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+use walkdir::WalkDir;
+
+/// 整理エラー
+#[derive(Error, Debug)]
+pub enum PruneError {
+    #[error("IOエラー: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("スナップショットの読み込みに失敗しました: {0}")]
+    Snapshot(#[from] RestoreError),
+}
+
+/// 整理結果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PruneReport {
+    /// 試行のみ（何も削除していない）か
+    pub dry_run: bool,
+
+    /// スナップショットごとの判定（新しい順）
+    pub decisions: Vec<RetentionDecision>,
+
+    /// 削除した（dry_run 時は削除対象の）スナップショットID
+    pub forgotten_snapshots: Vec<String>,
+
+    /// 削除したチャンク数
+    pub removed_chunks: usize,
+
+    /// 削除したファイル単位のバックアップ数
+    pub removed_blobs: usize,
+
+    /// 回収したバイト数
+    pub reclaimed_bytes: u64,
+}
+
+/// 保持ポリシーに従ってスナップショットを削除し、不要なデータを回収
+///
+/// `dry_run` が true の場合は削除対象の集計のみ行う。
+/// 暗号化リポジトリではマニフェストの復号のため `encryptor` が必要。
+pub fn prune(
+    repo_dir: &Path,
+    policy: &RetentionPolicy,
+    dry_run: bool,
+    encryptor: Option<&Encryptor>,
+) -> Result<PruneReport, PruneError> {
+    // manifest.json のないディレクトリはリポジトリとして扱わない（全データを削除しないため）
+    let latest = load_backup_manifest(&repo_dir.to_path_buf(), encryptor)?;
+
+    let snapshots = list_snapshots(repo_dir, encryptor)?;
+    let decisions = policy.apply(&snapshots);
+
+    // 残すデータを収集（読み込めないスナップショットがあれば何も削除しない）
+    let store = ChunkStore::new(repo_dir, false, None);
+    let mut referenced = HashSet::new();
+    collect_references(repo_dir, &store, &latest, &mut referenced)?;
+    for decision in decisions.iter().filter(|d| d.keep) {
+        let manifest = load_snapshot(repo_dir, &decision.snapshot.id, encryptor)?;
+        collect_references(repo_dir, &store, &manifest, &mut referenced)?;
+    }
+
+    let forgotten_snapshots: Vec<String> = decisions.iter()
+        .filter(|d| !d.keep)
+        .map(|d| d.snapshot.id.clone())
+        .collect();
+    if !dry_run {
+        for id in &forgotten_snapshots {
+            let path = repo_dir.join(SNAPSHOTS_DIR).join(format!("{}.json", id));
+            fs::remove_file(&path)?;
+            let sig_path = signature_path(&path);
+            if sig_path.exists() {
+                fs::remove_file(sig_path)?;
+            }
+        }
+    }
+
+    let (removed_chunks, chunk_bytes) = sweep(&repo_dir.join(CHUNKS_DIR), &referenced, dry_run)?;
+    let (removed_blobs, blob_bytes) = sweep(&repo_dir.join("data"), &referenced, dry_run)?;
+
+    Ok(PruneReport {
+        dry_run,
+        decisions,
+        forgotten_snapshots,
+        removed_chunks,
+        removed_blobs,
+        reclaimed_bytes: chunk_bytes + blob_bytes,
+    })
+}
+
+/// マニフェストが参照する保存先を収集
+pub(super) fn collect_references(
+    repo_dir: &Path,
+    store: &ChunkStore,
+    manifest: &BackupManifest,
+    referenced: &mut HashSet<PathBuf>,
+) -> Result<(), RestoreError> {
+    for entry in manifest.files.values() {
+        match &entry.chunks {
+            Some(chunks) => {
+                for id in chunks {
+                    referenced.insert(store.chunk_path(id)?);
+                }
+            }
+            None => {
+                referenced.insert(repo_dir.join(entry.blob_path()?));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// 参照されていないファイルを列挙（書き込み途中の一時ファイルは対象外）
+///
+/// # Returns
+/// ファイルパスとサイズの一覧
+pub(super) fn unreferenced_files(dir: &Path, referenced: &HashSet<PathBuf>) -> std::io::Result<Vec<(PathBuf, u64)>> {
+    let mut files = Vec::new();
+    if !dir.exists() {
+        return Ok(files);
+    }
+
+    for entry in WalkDir::new(dir).min_depth(1) {
+        let entry = entry?;
+        if !entry.file_type().is_file() || referenced.contains(entry.path()) {
+            continue;
+        }
+        if entry.path().extension().is_some_and(|e| e == "tmp") {
+            continue;
+        }
+        files.push((entry.path().to_path_buf(), entry.metadata()?.len()));
+    }
+
+    Ok(files)
+}
+
+/// 参照されていないファイルを削除し、空になったディレクトリを片付ける
+///
+/// # Returns
+/// 削除したファイル数とバイト数
+fn sweep(dir: &Path, referenced: &HashSet<PathBuf>, dry_run: bool) -> Result<(usize, u64), PruneError> {
+    let files = unreferenced_files(dir, referenced)?;
+    let bytes = files.iter().map(|(_, size)| size).sum();
+
+    if !dry_run {
+        for (path, _) in &files {
+            fs::remove_file(path)?;
+        }
+
+        // 空でないディレクトリの削除は失敗するため、エラーは無視する
+        for entry in WalkDir::new(dir).min_depth(1).contents_first(true).into_iter().flatten() {
+            if entry.file_type().is_dir() {
+                let _ = fs::remove_dir(entry.path());
+            }
+        }
+    }
+
+    Ok((files.len(), bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backup::{BackupConfig, BackupExecutor, RestoreConfig, RestoreExecutor};
+    use tempfile::TempDir;
+
+    fn backup(source: &Path, dest: &Path, chunking: bool) -> String {
+        let config = BackupConfig {
+            source_dir: source.to_path_buf(),
+            dest_dir: dest.to_path_buf(),
+            chunking,
+            ..BackupConfig::default()
+        };
+        BackupExecutor::new(config).execute().unwrap().snapshot_id
+    }
+
+    fn count_files(dir: &Path) -> usize {
+        WalkDir::new(dir).into_iter().flatten().filter(|e| e.file_type().is_file()).count()
+    }
+
+    #[test]
+    fn test_prune_keep_last() {
+        for chunking in [true, false] {
+            let source = TempDir::new().unwrap();
+            let dest = TempDir::new().unwrap();
+
+            let mut ids = Vec::new();
+            for version in 0..3 {
+                fs::write(source.path().join("file.txt"), format!("version {}", version)).unwrap();
+                ids.push(backup(source.path(), dest.path(), chunking));
+            }
+            let stored_dir = dest.path().join(if chunking { CHUNKS_DIR } else { "data" });
+            assert_eq!(count_files(&stored_dir), 3);
+
+            let policy = RetentionPolicy {
+                keep_last: Some(1),
+                ..RetentionPolicy::default()
+            };
+
+            // 試行では何も削除しない
+            let report = prune(dest.path(), &policy, true, None).unwrap();
+            assert_eq!(report.forgotten_snapshots.len(), 2);
+            assert_eq!(report.removed_chunks + report.removed_blobs, 2);
+            assert_eq!(count_files(&stored_dir), 3);
+
+            let report = prune(dest.path(), &policy, false, None).unwrap();
+            assert!(!report.dry_run);
+            assert_eq!(count_files(&stored_dir), 1);
+            assert_eq!(list_snapshots(dest.path(), None).unwrap().len(), 1);
+
+            // 残したスナップショットは復元できる
+            let restore = TempDir::new().unwrap();
+            let result = RestoreExecutor::new(RestoreConfig {
+                backup_dir: dest.path().to_path_buf(),
+                restore_dir: restore.path().to_path_buf(),
+                files: vec![],
+                overwrite: true,
+                snapshot: Some(ids[2].clone()),
+                strict: false,
+                trusted_signers: Vec::new(),
+            }).execute().unwrap();
+            assert!(result.success);
+            assert_eq!(fs::read_to_string(restore.path().join("file.txt")).unwrap(), "version 2");
+        }
+    }
+
+    #[test]
+    fn test_prune_keeps_shared_chunks() {
+        let source = TempDir::new().unwrap();
+        let dest = TempDir::new().unwrap();
+
+        // 1回目と2回目のスナップショットは shared.txt のチャンクを共有し、最新のスナップショットは参照しない
+        fs::write(source.path().join("shared.txt"), "shared content").unwrap();
+        let mut ids = Vec::new();
+        for version in 0..3 {
+            if version == 2 {
+                fs::remove_file(source.path().join("shared.txt")).unwrap();
+            }
+            fs::write(source.path().join("file.txt"), format!("version {}", version)).unwrap();
+            ids.push(backup(source.path(), dest.path(), true));
+        }
+
+        let policy = RetentionPolicy {
+            keep_last: Some(2),
+            ..RetentionPolicy::default()
+        };
+        let report = prune(dest.path(), &policy, false, None).unwrap();
+        assert_eq!(report.forgotten_snapshots, vec![ids[0].clone()]);
+        // 回収するのは削除したスナップショットだけが参照していた version 0 のチャンクのみ
+        assert_eq!(report.removed_chunks, 1);
+
+        let restore = TempDir::new().unwrap();
+        let result = RestoreExecutor::new(RestoreConfig {
+            backup_dir: dest.path().to_path_buf(),
+            restore_dir: restore.path().to_path_buf(),
+            files: vec![],
+            overwrite: true,
+            snapshot: Some(ids[1].clone()),
+            strict: true,
+            trusted_signers: Vec::new(),
+        }).execute().unwrap();
+        assert!(result.success);
+        assert_eq!(fs::read_to_string(restore.path().join("shared.txt")).unwrap(), "shared content");
+    }
+
+    #[test]
+    fn test_prune_aborts_on_unreadable_snapshot() {
+        let source = TempDir::new().unwrap();
+        let dest = TempDir::new().unwrap();
+
+        let mut ids = Vec::new();
+        for version in 0..3 {
+            fs::write(source.path().join("file.txt"), format!("version {}", version)).unwrap();
+            ids.push(backup(source.path(), dest.path(), true));
+        }
+        let snapshot_path = dest.path().join(SNAPSHOTS_DIR).join(format!("{}.json", ids[1]));
+        fs::write(&snapshot_path, b"{ broken").unwrap();
+
+        let before = count_files(dest.path());
+        let policy = RetentionPolicy {
+            keep_last: Some(1),
+            ..RetentionPolicy::default()
+        };
+        assert!(matches!(prune(dest.path(), &policy, false, None), Err(PruneError::Snapshot(_))));
+
+        // スナップショットもチャンクも削除しない
+        assert_eq!(count_files(dest.path()), before);
+        assert_eq!(count_files(&dest.path().join(CHUNKS_DIR)), 3);
+        assert!(snapshot_path.exists());
+    }
+}
@@ -0,0 +1,330 @@
+//! リポジトリ - バックアップ先に保存される鍵情報の管理
+
+use super::{read_manifest_file, MANIFEST_CONTEXT, MANIFEST_FILE};
+use crate::crypto::{
+    CipherSuite, ContentKey, CryptoError, Encryptor, Identity, KdfParams, KeyFile, MasterKey, Recipient, RecoveryKey,
+};
+use secrecy::{ExposeSecret, SecretString};
+use std::fs::{self, File};
+use std::io::{self, BufReader, Read};
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+/// キーファイル名
+pub const KEYFILE_NAME: &str = "keys.json";
+
+/// リポジトリエラー
+#[derive(Error, Debug)]
+pub enum RepositoryError {
+    #[error("IOエラー: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("暗号化エラー: {0}")]
+    Crypto(#[from] CryptoError),
+
+    #[error("キーファイルの解析に失敗しました: {0}")]
+    Parse(#[from] serde_json::Error),
+
+    #[error("キーファイルが見つかりません: {0}")]
+    KeyFileNotFound(PathBuf),
+
+    #[error("リポジトリの暗号スイート（{repository}）と指定された暗号スイート（{requested}）が異なります")]
+    CipherMismatch {
+        repository: CipherSuite,
+        requested: CipherSuite,
+    },
+
+    #[error("リポジトリに記録された受信者と指定された受信者が一致しません")]
+    RecipientMismatch,
+}
+
+/// キーファイルを読み込み（存在しなければNone）
+pub fn load_keyfile(repo_dir: &Path) -> Result<Option<KeyFile>, RepositoryError> {
+    let path = repo_dir.join(KEYFILE_NAME);
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    let data = fs::read_to_string(&path)?;
+    Ok(Some(serde_json::from_str(&data)?))
+}
+
+/// キーファイルを保存
+///
+/// 書き込み途中の中断でキーファイルが壊れないよう、一時ファイル経由で置き換える。
+pub fn save_keyfile(repo_dir: &Path, keyfile: &KeyFile) -> Result<(), RepositoryError> {
+    let path = repo_dir.join(KEYFILE_NAME);
+    let tmp_path = repo_dir.join(format!("{}.tmp", KEYFILE_NAME));
+
+    fs::write(&tmp_path, serde_json::to_string_pretty(keyfile)?)?;
+    fs::rename(&tmp_path, &path)?;
+    Ok(())
+}
+
+/// キーファイルを読み込み、存在しなければエラー
+pub fn require_keyfile(repo_dir: &Path) -> Result<KeyFile, RepositoryError> {
+    load_keyfile(repo_dir)?
+        .ok_or_else(|| RepositoryError::KeyFileNotFound(repo_dir.join(KEYFILE_NAME)))
+}
+
+/// バックアップ用の暗号化エンジンを取得
+///
+/// キーファイルがあればパスワードでマスター鍵を取り出し、なければ新しいマスター鍵を
+/// 生成し、回復キーとともにキーファイルを作成する。
+/// キーファイル導入前のバックアップがあるリポジトリでは、作成前にパスワードで既存のファイルを復号できるか確認する。
+/// 暗号スイートはリポジトリ作成時に決まり、`cipher` を指定した場合は既存の設定と一致する必要がある。
+///
+/// # Returns
+/// 暗号化エンジンと、リポジトリを新規作成した場合は回復キー（利用者に一度だけ表示する）
+pub fn open_or_init_encryptor(
+    repo_dir: &Path,
+    password: &str,
+    kdf: KdfParams,
+    cipher: Option<CipherSuite>,
+) -> Result<(Encryptor, Option<RecoveryKey>), RepositoryError> {
+    let (master, repository_cipher, recovery) = match load_keyfile(repo_dir)? {
+        Some(keyfile) => {
+            check_cipher(&keyfile, cipher)?;
+            (keyfile.unlock(password)?, keyfile.cipher, None)
+        }
+        None => {
+            check_legacy_password(repo_dir, password)?;
+            let (mut keyfile, master) = KeyFile::create(password, kdf, cipher.unwrap_or_default())?;
+            let recovery = keyfile.create_recovery(&master)?;
+            save_keyfile(repo_dir, &keyfile)?;
+            (master, keyfile.cipher, Some(recovery))
+        }
+    };
+
+    Ok((Encryptor::from_master_key(&master).with_cipher(repository_cipher), recovery))
+}
+
+/// キーファイル導入前のリポジトリで、パスワードが既存の暗号化ファイルを復号できるか確認
+///
+/// 旧形式のファイルはキーファイルを作成したパスワードで復号するため、誤ったパスワードで作成すると
+/// 以降は復元できなくなる。暗号化したファイルがなければ確認しない。
+fn check_legacy_password(repo_dir: &Path, password: &str) -> Result<(), RepositoryError> {
+    let manifest_path = repo_dir.join(MANIFEST_FILE);
+    if !manifest_path.exists() {
+        return Ok(());
+    }
+
+    let manifest = read_manifest_file(repo_dir, &manifest_path, MANIFEST_CONTEXT, None)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    let mut blobs: Vec<String> = manifest.files.values()
+        .filter(|entry| entry.encrypted && entry.chunks.is_none())
+        .filter_map(|entry| entry.blob_path().ok())
+        .filter(|blob| repo_dir.join(blob).is_file())
+        .collect();
+    blobs.sort();
+    let Some(blob) = blobs.first() else {
+        return Ok(());
+    };
+
+    // 先頭の1バイトを読めば認証まで行われる（ストリーム形式は最初のセグメント、それ以外は全体）
+    let file = BufReader::new(File::open(repo_dir.join(blob))?);
+    Encryptor::new(password)
+        .decrypt_reader(file, &format!("blob:{}", blob))
+        .and_then(|mut reader| reader.read(&mut [0u8; 1]))
+        .map_err(|_| CryptoError::WrongPassword)?;
+    Ok(())
+}
+
+/// 受信者方式のバックアップ用の暗号化エンジンを取得（書き込み専用）
+///
+/// キーファイルがなければ受信者を記録して作成する。既存のリポジトリでは、
+/// 記録された受信者と同じ受信者を指定する必要がある（パスワード方式のリポジトリには使用できない）。
+/// `content_key` を指定した場合は受信者宛てに暗号化してキーファイルに記録し（記録済みなら一致を確認）、
+/// 内容IDを鍵付きで計算する。
+pub fn open_or_init_recipient_encryptor(
+    repo_dir: &Path,
+    recipients: &[Recipient],
+    cipher: Option<CipherSuite>,
+    content_key: Option<&ContentKey>,
+) -> Result<Encryptor, RepositoryError> {
+    let (mut keyfile, mut changed) = match load_keyfile(repo_dir)? {
+        Some(keyfile) => {
+            check_cipher(&keyfile, cipher)?;
+            let mut recorded = keyfile.recipients()?;
+            let mut requested = recipients.to_vec();
+            recorded.sort_by_key(|r| r.to_string());
+            requested.sort_by_key(|r| r.to_string());
+            if recorded.is_empty() || recorded != requested {
+                return Err(RepositoryError::RecipientMismatch);
+            }
+            (keyfile, false)
+        }
+        None => (KeyFile::for_recipients(recipients, cipher.unwrap_or_default()), true),
+    };
+
+    if let Some(key) = content_key {
+        if !keyfile.check_content_key(key)? {
+            keyfile.set_content_key(key)?;
+            changed = true;
+        }
+    }
+    if changed {
+        save_keyfile(repo_dir, &keyfile)?;
+    }
+
+    let encryptor = Encryptor::for_recipients(recipients.to_vec()).with_cipher(keyfile.cipher);
+    Ok(match content_key {
+        Some(key) => encryptor.with_content_key(key),
+        None => encryptor,
+    })
+}
+
+/// 受信者方式のリポジトリをアイデンティティ（秘密鍵）で開く（復元用）
+pub fn open_identity_encryptor(repo_dir: &Path, identity: Identity) -> Result<Encryptor, RepositoryError> {
+    let keyfile = require_keyfile(repo_dir)?;
+    if !keyfile.recipients()?.contains(&identity.recipient()) {
+        return Err(CryptoError::UnknownIdentity.into());
+    }
+
+    let content_key = keyfile.open_content_key(&identity)?;
+    let encryptor = Encryptor::from_identity(identity).with_cipher(keyfile.cipher);
+    Ok(match content_key {
+        Some(key) => encryptor.with_content_key(&key),
+        None => encryptor,
+    })
+}
+
+/// 鍵の断片から復元したマスター鍵でリポジトリを開く（復元用）
+pub fn open_master_key_encryptor(repo_dir: &Path, master: &MasterKey) -> Result<Encryptor, RepositoryError> {
+    let keyfile = require_keyfile(repo_dir)?;
+    Ok(Encryptor::from_master_key(master).with_cipher(keyfile.cipher))
+}
+
+/// 指定された暗号スイートがリポジトリの設定と一致するか確認
+fn check_cipher(keyfile: &KeyFile, cipher: Option<CipherSuite>) -> Result<(), RepositoryError> {
+    match cipher.filter(|c| *c != keyfile.cipher) {
+        Some(requested) => Err(RepositoryError::CipherMismatch {
+            repository: keyfile.cipher,
+            requested,
+        }),
+        None => Ok(()),
+    }
+}
+
+/// 復元用の暗号化エンジンを取得
+///
+/// `password` には回復フレーズも指定できる。
+/// キーファイルがない（マスター鍵導入前の）リポジトリはパスワードのみで復号する。
+pub fn open_encryptor(repo_dir: &Path, password: &str) -> Result<Encryptor, RepositoryError> {
+    match load_keyfile(repo_dir)? {
+        Some(keyfile) => {
+            let master = keyfile.unlock(password)?;
+            Ok(Encryptor::from_master_key(&master)
+                .with_password(password)
+                .with_cipher(keyfile.cipher))
+        }
+        None => Ok(Encryptor::new(password)),
+    }
+}
+
+/// 復元・検証でリポジトリを開く鍵
+pub enum RepositoryKey {
+    /// パスワードまたは回復フレーズ
+    Password(SecretString),
+
+    /// 受信者方式のリポジトリの秘密鍵
+    Identity(Identity),
+
+    /// 鍵の断片から復元したマスター鍵
+    MasterKey(MasterKey),
+}
+
+/// 指定された鍵でリポジトリを開く（復元・検証用）
+///
+/// 鍵を指定しない場合はNoneとし、暗号化されたファイルは読み込む時点でファイルごとにエラーとなる。
+/// アイデンティティ・マスター鍵はキーファイルが必要で、キーファイルが削除されていればエラーとする
+/// （鍵なしとして開くと、差し替えられた平文のマニフェストを受け付けてしまうため）。
+pub fn open_repository_key(repo_dir: &Path, key: Option<&RepositoryKey>) -> Result<Option<Encryptor>, RepositoryError> {
+    key.map(|key| match key {
+        RepositoryKey::Password(password) => open_encryptor(repo_dir, password.expose_secret()),
+        RepositoryKey::Identity(identity) => open_identity_encryptor(repo_dir, identity.clone()),
+        RepositoryKey::MasterKey(master) => open_master_key_encryptor(repo_dir, master),
+    })
+    .transpose()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_init_and_reopen() {
+        let repo = TempDir::new().unwrap();
+        let kdf = KdfParams::for_tests();
+
+        let (encryptor, recovery) = open_or_init_encryptor(repo.path(), "password_one", kdf, None).unwrap();
+        assert!(repo.path().join(KEYFILE_NAME).exists());
+        let recovery = recovery.unwrap().phrase();
+        let encrypted = encryptor.encrypt(b"data", "test").unwrap();
+
+        // パスワード変更後も同じデータを復号できる
+        let mut keyfile = require_keyfile(repo.path()).unwrap();
+        keyfile.change_password("password_one", "password_two", kdf).unwrap();
+        save_keyfile(repo.path(), &keyfile).unwrap();
+
+        let encryptor = open_encryptor(repo.path(), "password_two").unwrap();
+        assert_eq!(encryptor.decrypt(&encrypted, "test").unwrap(), b"data");
+        assert!(open_encryptor(repo.path(), "password_one").is_err());
+
+        // 回復フレーズはパスワードの代わりに使え、既存リポジトリでは再発行されない
+        let encryptor = open_encryptor(repo.path(), &recovery).unwrap();
+        assert_eq!(encryptor.decrypt(&encrypted, "test").unwrap(), b"data");
+        let (_, recovery) = open_or_init_encryptor(repo.path(), "password_two", kdf, None).unwrap();
+        assert!(recovery.is_none());
+    }
+
+    #[test]
+    fn test_upgrade_legacy_repository_checks_password() {
+        use crate::backup::{BackupConfig, BackupManifest, FileInfo, ScanResult};
+        use chrono::Utc;
+        use std::collections::HashMap;
+
+        // キーファイル導入前の形式: 平文のマニフェストと、パスワードで暗号化した data/<パス>.enc
+        let repo = TempDir::new().unwrap();
+        let mut files = HashMap::new();
+        files.insert("a.txt".to_string(), FileInfo {
+            relative_path: "a.txt".to_string(),
+            size: 4,
+            modified: Utc::now(),
+            hash: None,
+        });
+        let scan = ScanResult {
+            source_dir: PathBuf::from("/src"),
+            scanned_at: Utc::now(),
+            files,
+            total_files: 1,
+            total_size: 4,
+            ignore_files: vec![],
+            excluded: vec![],
+        };
+        let config = BackupConfig {
+            encrypt: true,
+            compress: false,
+            ..BackupConfig::default()
+        };
+        let manifest = BackupManifest::from_scan(&scan, &config);
+        fs::write(repo.path().join(MANIFEST_FILE), serde_json::to_vec(&manifest).unwrap()).unwrap();
+        let legacy = Encryptor::with_params("legacy_password", KdfParams::for_tests());
+        fs::create_dir_all(repo.path().join("data")).unwrap();
+        fs::write(
+            repo.path().join("data/a.txt.enc"),
+            legacy.encrypt(b"data", "blob:data/a.txt.enc").unwrap(),
+        ).unwrap();
+
+        // 誤ったパスワードではキーファイルを作成しない
+        let result = open_or_init_encryptor(repo.path(), "legacy_passwrod", KdfParams::for_tests(), None);
+        assert!(matches!(result, Err(RepositoryError::Crypto(CryptoError::WrongPassword))));
+        assert!(!repo.path().join(KEYFILE_NAME).exists());
+
+        open_or_init_encryptor(repo.path(), "legacy_password", KdfParams::for_tests(), None).unwrap();
+        let encryptor = open_encryptor(repo.path(), "legacy_password").unwrap();
+        let data = fs::read(repo.path().join("data/a.txt.enc")).unwrap();
+        assert_eq!(encryptor.decrypt(&data, "blob:data/a.txt.enc").unwrap(), b"data");
+    }
+}
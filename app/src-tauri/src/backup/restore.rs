@@ -2,12 +2,17 @@
 //!
 //! 暗号化・圧縮されたバックアップファイルを元の形式に復元する機能を提供。
 
-use super::{BackupManifest, ManifestEntry};
-use crate::crypto::{CryptoError, Encryptor};
+use super::{
+    load_snapshot, open_repository_key, read_manifest_file, require_trusted_signature, BackupManifest, ChunkStore,
+    ManifestEntry, RepositoryError, RepositoryKey, ScanFilters, SignatureStatus,
+    MANIFEST_CONTEXT, MANIFEST_FILE,
+};
+use crate::crypto::{CryptoError, Encryptor, Identity, MasterKey, SignerKey};
+use secrecy::SecretString;
 use serde::{Deserialize, Serialize};
 use std::fs::{self, File};
-use std::io::{Read, Write};
-use std::path::PathBuf;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
 use chrono::{DateTime, Utc};
 use thiserror::Error;
 
@@ -34,6 +39,60 @@ pub enum RestoreError {
 
     #[error("パスワードが正しくありません")]
     WrongPassword,
+
+    #[error("暗号化されたファイルの復号には鍵が必要です: {0}")]
+    KeyRequired(String),
+
+    #[error("バックアップデータが改ざんされているか破損しています: {0}")]
+    BlobCorrupted(String),
+
+    #[error("スナップショットが見つかりません: {0}")]
+    SnapshotNotFound(String),
+
+    #[error("復元したファイルのハッシュが一致しません: {0}")]
+    HashMismatch(String),
+
+    #[error("ハッシュが記録されていないため検証できません: {0}")]
+    HashMissing(String),
+
+    #[error("復元先の外を指すパスです: {0}")]
+    UnsafePath(String),
+
+    #[error("マニフェストが暗号化されています（パスワードが必要です）")]
+    ManifestEncrypted,
+
+    #[error("マニフェストが改ざんされているか破損しています: {0}")]
+    ManifestTampered(PathBuf),
+
+    #[error("スナップショットの署名が正しくありません（改ざんの可能性があります）: {0}")]
+    SignatureInvalid(String),
+
+    #[error("信頼する鍵で署名されていないスナップショットです: {0}")]
+    UntrustedSnapshot(String),
+
+    #[error("キーファイルの解析に失敗しました: {0}")]
+    KeyFile(serde_json::Error),
+
+    #[error("マニフェストのエントリが不正です: {0}")]
+    InvalidEntry(String),
+
+    #[error("リポジトリを開けません: {0}")]
+    Repository(RepositoryError),
+}
+
+impl From<RepositoryError> for RestoreError {
+    /// 鍵が合わない場合のみ WrongPassword とし、IOエラーやキーファイルの破損はそのまま伝える
+    fn from(e: RepositoryError) -> Self {
+        match e {
+            RepositoryError::Io(e) => Self::Io(e),
+            RepositoryError::Parse(e) => Self::KeyFile(e),
+            RepositoryError::Crypto(
+                CryptoError::WrongPassword | CryptoError::UnknownIdentity | CryptoError::DecryptionFailed,
+            ) => Self::WrongPassword,
+            RepositoryError::Crypto(e) => Self::Crypto(e),
+            e => Self::Repository(e),
+        }
+    }
 }
 
 /// 復元設定
@@ -50,6 +109,18 @@ pub struct RestoreConfig {
 
     /// 既存ファイルを上書きするか
     pub overwrite: bool,
+
+    /// 復元するスナップショットID（Noneの場合は最新）
+    #[serde(default)]
+    pub snapshot: Option<String>,
+
+    /// ハッシュ不一致のファイルがあれば直ちに復元を中止するか
+    #[serde(default)]
+    pub strict: bool,
+
+    /// 信頼する署名者（空でなければ、これらの鍵で署名されたスナップショットのみ復元する）
+    #[serde(default)]
+    pub trusted_signers: Vec<SignerKey>,
 }
 
 /// 復元進捗
@@ -113,6 +184,16 @@ pub struct RestoreResult {
     /// エラーが発生したファイル
     pub failed_files: Vec<String>,
 
+    /// ハッシュが一致しなかったファイル数（failed_files にも含む）
+    pub hash_mismatches: usize,
+
+    /// ハッシュが記録されておらず検証できなかったファイル（restored_files にも含む）
+    #[serde(default)]
+    pub unverified_files: Vec<String>,
+
+    /// 復元したスナップショットの署名
+    pub signature: SignatureStatus,
+
     /// 成功したか
     pub success: bool,
 }
@@ -120,7 +201,7 @@ pub struct RestoreResult {
 /// 復元実行エンジン
 pub struct RestoreExecutor {
     config: RestoreConfig,
-    encryptor: Option<Encryptor>,
+    key: Option<RepositoryKey>,
     progress_callback: Option<Box<dyn Fn(RestoreProgress) + Send + Sync>>,
 }
 
@@ -129,17 +210,32 @@ impl RestoreExecutor {
     pub fn new(config: RestoreConfig) -> Self {
         Self {
             config,
-            encryptor: None,
+            key: None,
             progress_callback: None,
         }
     }
 
-    /// 復号化用のパスワードを設定
-    pub fn with_password(mut self, password: &str) -> Self {
-        self.encryptor = Some(Encryptor::new(password));
+    /// 復号化用の鍵を設定（後から設定した鍵で置き換える）
+    pub fn with_key(mut self, key: RepositoryKey) -> Self {
+        self.key = Some(key);
         self
     }
 
+    /// 復号化用のパスワード（または回復フレーズ）を設定
+    pub fn with_password(self, password: impl Into<SecretString>) -> Self {
+        self.with_key(RepositoryKey::Password(password.into()))
+    }
+
+    /// 復号化用のアイデンティティ（受信者方式のリポジトリの秘密鍵）を設定
+    pub fn with_identity(self, identity: Identity) -> Self {
+        self.with_key(RepositoryKey::Identity(identity))
+    }
+
+    /// 復号化用のマスター鍵（鍵の断片から復元したもの）を設定
+    pub fn with_master_key(self, master_key: MasterKey) -> Self {
+        self.with_key(RepositoryKey::MasterKey(master_key))
+    }
+
     /// 進捗コールバックを設定
     pub fn with_progress_callback<F>(mut self, callback: F) -> Self
     where
@@ -164,8 +260,16 @@ impl RestoreExecutor {
             error: None,
         });
 
-        // マニフェストを読み込み
-        let manifest = self.load_manifest()?;
+        // 暗号化エンジンを準備（鍵のないリポジトリの暗号化ファイルはファイルごとにエラーとする）
+        let encryptor = open_repository_key(&self.config.backup_dir, self.key.as_ref())?;
+
+        // 署名を確認してからマニフェストを読み込み
+        let signature = require_trusted_signature(
+            &self.config.backup_dir,
+            self.config.snapshot.as_deref(),
+            &self.config.trusted_signers,
+        )?;
+        let manifest = self.load_manifest(encryptor.as_ref())?;
 
         // 復元対象ファイルを決定
         let files_to_restore = if self.config.files.is_empty() {
@@ -190,6 +294,8 @@ impl RestoreExecutor {
         let mut restored_bytes = 0u64;
         let mut skipped_files = 0usize;
         let mut failed_files = Vec::new();
+        let mut hash_mismatches = 0usize;
+        let mut unverified_files = Vec::new();
 
         for (idx, entry) in files_to_restore.iter().enumerate() {
             self.report_progress(RestoreProgress {
@@ -202,14 +308,27 @@ impl RestoreExecutor {
                 error: None,
             });
 
-            match self.restore_file(entry, &manifest) {
+            match self.restore_file(entry, &manifest, encryptor.as_ref()) {
                 Ok(RestoreFileResult::Restored(size)) => {
                     restored_files += 1;
                     restored_bytes += size;
                 }
+                Ok(RestoreFileResult::Unverified(size)) => {
+                    restored_files += 1;
+                    restored_bytes += size;
+                    unverified_files.push(entry.path.clone());
+                }
                 Ok(RestoreFileResult::Skipped) => {
                     skipped_files += 1;
                 }
+                Err(e @ RestoreError::HashMissing(_)) => return Err(e),
+                Err(e @ RestoreError::HashMismatch(_)) => {
+                    if self.config.strict {
+                        return Err(e);
+                    }
+                    hash_mismatches += 1;
+                    failed_files.push(format!("{}: {}", entry.path, e));
+                }
                 Err(e) => {
                     failed_files.push(format!("{}: {}", entry.path, e));
                 }
@@ -245,72 +364,56 @@ impl RestoreExecutor {
             restored_bytes,
             skipped_files,
             failed_files,
+            hash_mismatches,
+            unverified_files,
+            signature,
             success,
         })
     }
 
     /// マニフェストを読み込み
-    fn load_manifest(&self) -> Result<BackupManifest, RestoreError> {
-        let manifest_path = self.config.backup_dir.join("manifest.json");
+    fn load_manifest(&self, encryptor: Option<&Encryptor>) -> Result<BackupManifest, RestoreError> {
+        let result = match &self.config.snapshot {
+            Some(id) => load_snapshot(&self.config.backup_dir, id, encryptor),
+            None => load_backup_manifest(&self.config.backup_dir, encryptor),
+        };
 
-        if !manifest_path.exists() {
-            return Err(RestoreError::ManifestNotFound(manifest_path));
+        // 鍵を指定したのに復号できない場合は鍵違い
+        match result {
+            Err(RestoreError::ManifestEncrypted) if self.key.is_some() => {
+                Err(RestoreError::WrongPassword)
+            }
+            result => result,
         }
-
-        let manifest_data = fs::read_to_string(&manifest_path)?;
-        let manifest: BackupManifest = serde_json::from_str(&manifest_data)?;
-
-        Ok(manifest)
     }
 
     /// 単一ファイルを復元
-    fn restore_file(&self, entry: &ManifestEntry, manifest: &BackupManifest) -> Result<RestoreFileResult, RestoreError> {
-        let restore_path = self.config.restore_dir.join(&entry.path);
+    fn restore_file(
+        &self,
+        entry: &ManifestEntry,
+        manifest: &BackupManifest,
+        encryptor: Option<&Encryptor>,
+    ) -> Result<RestoreFileResult, RestoreError> {
+        let restore_path = safe_restore_path(&self.config.restore_dir, &entry.path)?;
 
         // 上書きチェック
         if restore_path.exists() && !self.config.overwrite {
             return Ok(RestoreFileResult::Skipped);
         }
 
-        // バックアップファイルのパスを構築
-        let backup_file_path = if entry.encrypted {
-            // 暗号化されている場合は.enc拡張子
-            let mut path = self.config.backup_dir.join("data").join(&entry.path);
-            let extension = path.extension()
-                .map(|e| format!("{}.enc", e.to_string_lossy()))
-                .unwrap_or_else(|| "enc".to_string());
-            path.set_extension(extension);
-            path
-        } else {
-            self.config.backup_dir.join("data").join(&entry.path)
-        };
-
-        if !backup_file_path.exists() {
-            return Err(RestoreError::BackupFileNotFound(backup_file_path));
+        // ハッシュのない（古い・編集された）エントリは検証できない。厳格モードでは復元しない
+        let verified = !entry.hash.is_empty();
+        if !verified && self.config.strict {
+            return Err(RestoreError::HashMissing(entry.path.clone()));
         }
 
-        // ファイルを読み込み
-        let mut data = Vec::new();
-        File::open(&backup_file_path)?.read_to_end(&mut data)?;
-
-        // 復号化
-        let data = if entry.encrypted {
-            if let Some(ref encryptor) = self.encryptor {
-                encryptor.decrypt(&data)
-                    .map_err(|_| RestoreError::WrongPassword)?
-            } else {
-                return Err(RestoreError::WrongPassword);
-            }
-        } else {
-            data
-        };
-
-        // 解凍
-        let data = if entry.compressed || manifest.config.compress {
-            zstd::decode_all(data.as_slice())
-                .map_err(|_| RestoreError::Decompression)?
+        // チャンク分割されたファイルはチャンクストアから再構成する
+        let chunk_store;
+        let (mut reader, compressed): (Box<dyn Read + '_>, bool) = if let Some(chunks) = &entry.chunks {
+            chunk_store = ChunkStore::new(&self.config.backup_dir, entry.compressed, encryptor);
+            (Box::new(chunk_store.reader(chunks)), entry.compressed)
         } else {
-            data
+            open_file_blob(&self.config.backup_dir, entry, manifest, encryptor)?
         };
 
         // 親ディレクトリを作成
@@ -318,11 +421,33 @@ impl RestoreExecutor {
             fs::create_dir_all(parent)?;
         }
 
-        // ファイルを書き込み
-        let mut file = File::create(&restore_path)?;
-        file.write_all(&data)?;
+        // 一時ファイルに書き込み、完了してから置き換える（途中で失敗しても既存ファイルを壊さない）
+        let tmp_path = temporary_path(&restore_path);
+        let result = File::create(&tmp_path)
+            .map_err(RestoreError::from)
+            .and_then(|file| {
+                // 書き込みながらハッシュを計算し、マニフェストの記録と照合する
+                let mut writer = HashingWriter::new(BufWriter::new(file), manifest.content_hasher(encryptor));
+                copy_restored(&mut reader, &mut writer, compressed, &entry.path)?;
+                if verified && writer.hash().to_hex().as_str() != entry.hash {
+                    return Err(RestoreError::HashMismatch(entry.path.clone()));
+                }
+                Ok(())
+            });
+
+        match result {
+            Ok(_) => fs::rename(&tmp_path, &restore_path)?,
+            Err(e) => {
+                let _ = fs::remove_file(&tmp_path);
+                return Err(e);
+            }
+        }
 
-        Ok(RestoreFileResult::Restored(entry.original_size))
+        Ok(if verified {
+            RestoreFileResult::Restored(entry.original_size)
+        } else {
+            RestoreFileResult::Unverified(entry.original_size)
+        })
     }
 
     /// 進捗を報告
@@ -333,26 +458,178 @@ impl RestoreExecutor {
     }
 }
 
+/// data/ にファイル単位で保存されたバックアップを開く
+///
+/// # Returns
+/// 復号・解凍済みのリーダーと、圧縮されていたか
+pub(super) fn open_file_blob(
+    backup_dir: &Path,
+    entry: &ManifestEntry,
+    manifest: &BackupManifest,
+    encryptor: Option<&Encryptor>,
+) -> Result<(Box<dyn Read>, bool), RestoreError> {
+    let blob = entry.blob_path()?;
+    let backup_file_path = backup_dir.join(&blob);
+
+    if !backup_file_path.exists() {
+        return Err(RestoreError::BackupFileNotFound(backup_file_path));
+    }
+
+    let file = BufReader::new(File::open(&backup_file_path)?);
+
+    // 復号化（鍵はリポジトリを開く時点で確認済みのため、ここでの失敗は改ざん・破損）
+    let reader: Box<dyn Read> = if entry.encrypted {
+        if let Some(encryptor) = encryptor {
+            encryptor.decrypt_reader(file, &format!("blob:{}", blob))
+                .map_err(|_| RestoreError::BlobCorrupted(entry.path.clone()))?
+        } else {
+            return Err(RestoreError::KeyRequired(entry.path.clone()));
+        }
+    } else {
+        Box::new(file)
+    };
+
+    // 解凍
+    let compressed = entry.compressed || manifest.config.compress;
+    let reader: Box<dyn Read> = if compressed {
+        Box::new(zstd::stream::read::Decoder::new(reader)
+            .map_err(|_| RestoreError::Decompression)?)
+    } else {
+        reader
+    };
+
+    Ok((reader, compressed))
+}
+
+/// 書き込んだ内容の BLAKE3 ハッシュを計算するライター
+struct HashingWriter<W: Write> {
+    inner: W,
+    hasher: blake3::Hasher,
+}
+
+impl<W: Write> HashingWriter<W> {
+    fn new(inner: W, hasher: blake3::Hasher) -> Self {
+        Self { inner, hasher }
+    }
+
+    /// ここまでに書き込んだ内容のハッシュ
+    fn hash(&self) -> blake3::Hash {
+        self.hasher.finalize()
+    }
+}
+
+impl<W: Write> Write for HashingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        self.hasher.update(&buf[..n]);
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// マニフェストのパスを検証し、復元先のパスを返す
+///
+/// 絶対パス・ドライブ指定・`..` を含むパスは復元先の外に書き込めるため拒否する。
+/// 他のOSで作成したバックアップに備え、区切り文字は `/` と `\` の両方を受け付ける。
+fn safe_restore_path(restore_dir: &Path, entry_path: &str) -> Result<PathBuf, RestoreError> {
+    let unsafe_path = || RestoreError::UnsafePath(entry_path.to_string());
+
+    if entry_path.starts_with(['/', '\\']) || entry_path.contains('\0') {
+        return Err(unsafe_path());
+    }
+
+    let mut path = restore_dir.to_path_buf();
+    let mut depth = 0usize;
+    for (i, part) in entry_path.split(['/', '\\']).enumerate() {
+        match part {
+            "" | "." => continue,
+            ".." => return Err(unsafe_path()),
+            // Windows のドライブ指定（C:\ や C:foo）
+            _ if i == 0 && part.len() >= 2 && part.as_bytes()[1] == b':'
+                && part.as_bytes()[0].is_ascii_alphabetic() => return Err(unsafe_path()),
+            _ => {
+                path.push(part);
+                depth += 1;
+            }
+        }
+    }
+
+    if depth == 0 {
+        return Err(unsafe_path());
+    }
+    Ok(path)
+}
+
+/// 復元中の一時ファイルパス
+fn temporary_path(path: &Path) -> PathBuf {
+    let name = path.file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    path.with_file_name(format!(".{}.sb-restore", name))
+}
+
+/// 復号・解凍しながら書き込み
+///
+/// 読み込み側のエラーは `path` の改ざん・破損（認証失敗）または解凍失敗として、
+/// 書き込み側のエラーはIOエラーとして扱う。
+pub(super) fn copy_restored(
+    reader: &mut dyn Read,
+    mut writer: impl Write,
+    compressed: bool,
+    path: &str,
+) -> Result<u64, RestoreError> {
+    let mut buf = vec![0u8; 64 * 1024];
+    let mut total = 0u64;
+
+    loop {
+        let n = match reader.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) => n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => {
+                let is_crypto = e.get_ref()
+                    .is_some_and(|inner| inner.downcast_ref::<CryptoError>().is_some());
+                return Err(if is_crypto {
+                    RestoreError::BlobCorrupted(path.to_string())
+                } else if compressed {
+                    RestoreError::Decompression
+                } else {
+                    RestoreError::Io(e)
+                });
+            }
+        };
+        writer.write_all(&buf[..n])?;
+        total += n as u64;
+    }
+
+    writer.flush()?;
+    Ok(total)
+}
+
 /// 復元ファイル結果
 enum RestoreFileResult {
     /// 復元成功（バイト数）
     Restored(u64),
+    /// 復元したがハッシュがなく検証していない（バイト数）
+    Unverified(u64),
     /// スキップ（既存ファイルあり）
     Skipped,
 }
 
 /// バックアップマニフェストを読み込み
-pub fn load_backup_manifest(backup_dir: &PathBuf) -> Result<BackupManifest, RestoreError> {
-    let manifest_path = backup_dir.join("manifest.json");
+///
+/// 暗号化リポジトリのマニフェストは `encryptor` で復号・検証する。
+pub fn load_backup_manifest(backup_dir: &PathBuf, encryptor: Option<&Encryptor>) -> Result<BackupManifest, RestoreError> {
+    let manifest_path = backup_dir.join(MANIFEST_FILE);
 
     if !manifest_path.exists() {
         return Err(RestoreError::ManifestNotFound(manifest_path));
     }
 
-    let manifest_data = fs::read_to_string(&manifest_path)?;
-    let manifest: BackupManifest = serde_json::from_str(&manifest_data)?;
-
-    Ok(manifest)
+    read_manifest_file(backup_dir, &manifest_path, MANIFEST_CONTEXT, encryptor)
 }
 
 /// バックアップ情報
@@ -387,6 +664,14 @@ pub struct BackupInfo {
 
     /// バックアップ回数
     pub backup_count: u32,
+
+    /// 最新スナップショットの署名（確認していない場合はNone）
+    #[serde(default)]
+    pub signature: Option<SignatureStatus>,
+
+    /// このジョブの絞り込み条件（v0.1.x など記録のないマニフェストはNone）
+    #[serde(default)]
+    pub filters: Option<ScanFilters>,
 }
 
 impl From<&BackupManifest> for BackupInfo {
@@ -402,6 +687,8 @@ impl From<&BackupManifest> for BackupInfo {
             encrypted: manifest.config.encrypt,
             compressed: manifest.config.compress,
             backup_count: manifest.stats.backup_count,
+            signature: None,
+            filters: manifest.config.filters.clone(),
         }
     }
 }
@@ -411,7 +698,8 @@ mod tests {
     use super::*;
     use tempfile::TempDir;
     use std::io::Write as IoWrite;
-    use crate::backup::{BackupConfig, BackupExecutor};
+    use crate::backup::{BackupConfig, BackupExecutor, CHUNKS_DIR};
+    use crate::crypto::{ContentKey, SnapshotSigner};
 
     #[test]
     fn test_restore_unencrypted() {
@@ -432,7 +720,7 @@ mod tests {
             encrypt: false,
             compress: true,
             incremental: false,
-            exclude_patterns: vec![],
+            ..BackupConfig::default()
         };
 
         let executor = BackupExecutor::new(backup_config);
@@ -445,6 +733,9 @@ mod tests {
             restore_dir: restore.path().to_path_buf(),
             files: vec![],
             overwrite: true,
+            snapshot: None,
+            strict: false,
+            trusted_signers: Vec::new(),
         };
 
         let restore_executor = RestoreExecutor::new(restore_config);
@@ -480,7 +771,7 @@ mod tests {
             encrypt: true,
             compress: true,
             incremental: false,
-            exclude_patterns: vec![],
+            ..BackupConfig::default()
         };
 
         let executor = BackupExecutor::new(backup_config)
@@ -494,6 +785,9 @@ mod tests {
             restore_dir: restore.path().to_path_buf(),
             files: vec![],
             overwrite: true,
+            snapshot: None,
+            strict: false,
+            trusted_signers: Vec::new(),
         };
 
         let restore_executor = RestoreExecutor::new(restore_config)
@@ -530,7 +824,7 @@ mod tests {
             encrypt: true,
             compress: true,
             incremental: false,
-            exclude_patterns: vec![],
+            ..BackupConfig::default()
         };
 
         let executor = BackupExecutor::new(backup_config)
@@ -544,14 +838,607 @@ mod tests {
             restore_dir: restore.path().to_path_buf(),
             files: vec![],
             overwrite: true,
+            snapshot: None,
+            strict: false,
+            trusted_signers: Vec::new(),
         };
 
         let restore_executor = RestoreExecutor::new(restore_config)
             .with_password("wrong_password");
-        let restore_result = restore_executor.execute().unwrap();
+        let restore_result = restore_executor.execute();
+
+        // パスワードが間違っているので暗号化されたマニフェストも開けない
+        assert!(matches!(restore_result, Err(RestoreError::WrongPassword)));
+        assert!(!restore.path().join("secret.txt").exists());
+    }
+
+    #[test]
+    fn test_restore_reports_corrupt_keyfile() {
+        let source = TempDir::new().unwrap();
+        let backup = TempDir::new().unwrap();
+        let restore = TempDir::new().unwrap();
+
+        fs::write(source.path().join("secret.txt"), "Secret Data!").unwrap();
+
+        let backup_config = BackupConfig {
+            source_dir: source.path().to_path_buf(),
+            dest_dir: backup.path().to_path_buf(),
+            encrypt: true,
+            ..BackupConfig::default()
+        };
+        let result = BackupExecutor::new(backup_config)
+            .with_encryption("correct_password")
+            .execute()
+            .unwrap();
+        assert!(result.success);
+
+        // キーファイルが壊れている場合はパスワード違いとして扱わない
+        fs::write(backup.path().join(crate::backup::KEYFILE_NAME), "{ broken").unwrap();
+
+        let restore_config = RestoreConfig {
+            backup_dir: backup.path().to_path_buf(),
+            restore_dir: restore.path().to_path_buf(),
+            files: vec![],
+            overwrite: true,
+            snapshot: None,
+            strict: false,
+            trusted_signers: Vec::new(),
+        };
+        let result = RestoreExecutor::new(restore_config)
+            .with_password("correct_password")
+            .execute();
+        assert!(matches!(result, Err(RestoreError::KeyFile(_))));
+    }
+
+    #[test]
+    fn test_restore_after_incremental() {
+        let source = TempDir::new().unwrap();
+        let backup = TempDir::new().unwrap();
+        let restore = TempDir::new().unwrap();
+
+        fs::write(source.path().join("a.txt"), "first version").unwrap();
+        fs::write(source.path().join("b.txt"), "unchanged").unwrap();
+
+        let backup_config = BackupConfig {
+            source_dir: source.path().to_path_buf(),
+            dest_dir: backup.path().to_path_buf(),
+            ..BackupConfig::default()
+        };
+        assert!(BackupExecutor::new(backup_config.clone()).execute().unwrap().success);
+
+        // 1ファイルだけ変更して差分バックアップ
+        fs::write(source.path().join("a.txt"), "second version").unwrap();
+        let result = BackupExecutor::new(backup_config).execute().unwrap();
+        assert_eq!(result.backed_up_files, 1);
+        assert_eq!(result.skipped_files, 1);
+
+        let restore_config = RestoreConfig {
+            backup_dir: backup.path().to_path_buf(),
+            restore_dir: restore.path().to_path_buf(),
+            files: vec![],
+            overwrite: true,
+            snapshot: None,
+            strict: false,
+            trusted_signers: Vec::new(),
+        };
+        let restore_result = RestoreExecutor::new(restore_config).execute().unwrap();
+        assert!(restore_result.success);
+        assert_eq!(fs::read_to_string(restore.path().join("a.txt")).unwrap(), "second version");
+        assert_eq!(fs::read_to_string(restore.path().join("b.txt")).unwrap(), "unchanged");
+    }
+
+    #[test]
+    fn test_restore_file_mode_encrypted() {
+        let source = TempDir::new().unwrap();
+        let backup = TempDir::new().unwrap();
+        let restore = TempDir::new().unwrap();
+
+        fs::write(source.path().join("secret.txt"), "Secret Data!").unwrap();
+
+        // チャンク分割を無効にして data/ にファイル単位で保存
+        let backup_config = BackupConfig {
+            source_dir: source.path().to_path_buf(),
+            dest_dir: backup.path().to_path_buf(),
+            chunking: false,
+            ..BackupConfig::default()
+        };
+        let executor = BackupExecutor::new(backup_config)
+            .with_encryption("test_password_123");
+        let snapshot_id = executor.execute().unwrap().snapshot_id;
+        assert!(backup.path().join("data").join(&snapshot_id).join("secret.txt.enc").exists());
+
+        let restore_config = RestoreConfig {
+            backup_dir: backup.path().to_path_buf(),
+            restore_dir: restore.path().to_path_buf(),
+            files: vec![],
+            overwrite: true,
+            snapshot: None,
+            strict: false,
+            trusted_signers: Vec::new(),
+        };
+        let restore_result = RestoreExecutor::new(restore_config)
+            .with_password("test_password_123")
+            .execute()
+            .unwrap();
+        assert!(restore_result.success);
+        assert_eq!(fs::read_to_string(restore.path().join("secret.txt")).unwrap(), "Secret Data!");
+    }
+
+    #[test]
+    fn test_restore_detects_swapped_blobs() {
+        let source = TempDir::new().unwrap();
+        let backup = TempDir::new().unwrap();
+        let restore = TempDir::new().unwrap();
+
+        fs::write(source.path().join("a.txt"), "alpha").unwrap();
+        fs::write(source.path().join("b.txt"), "bravo").unwrap();
 
-        // パスワードが間違っているのでエラーになるはず
+        let backup_config = BackupConfig {
+            source_dir: source.path().to_path_buf(),
+            dest_dir: backup.path().to_path_buf(),
+            chunking: false,
+            ..BackupConfig::default()
+        };
+        let snapshot_id = BackupExecutor::new(backup_config)
+            .with_encryption("test_password_123")
+            .execute()
+            .unwrap()
+            .snapshot_id;
+
+        // 同じ鍵で暗号化された2つのファイルを入れ替える
+        let data_dir = backup.path().join("data").join(&snapshot_id);
+        let a = fs::read(data_dir.join("a.txt.enc")).unwrap();
+        let b = fs::read(data_dir.join("b.txt.enc")).unwrap();
+        fs::write(data_dir.join("a.txt.enc"), b).unwrap();
+        fs::write(data_dir.join("b.txt.enc"), a).unwrap();
+
+        let restore_config = RestoreConfig {
+            backup_dir: backup.path().to_path_buf(),
+            restore_dir: restore.path().to_path_buf(),
+            files: vec![],
+            overwrite: true,
+            snapshot: None,
+            strict: false,
+            trusted_signers: Vec::new(),
+        };
+        let restore_result = RestoreExecutor::new(restore_config)
+            .with_password("test_password_123")
+            .execute()
+            .unwrap();
         assert!(!restore_result.success);
-        assert!(!restore_result.failed_files.is_empty());
+        assert_eq!(restore_result.restored_files, 0);
+        assert_eq!(restore_result.failed_files.len(), 2);
+        assert!(restore_result.failed_files.iter().all(|f| f.contains("改ざん")));
+    }
+
+    #[test]
+    fn test_restore_rejects_plaintext_manifest_without_keyfile() {
+        use crate::backup::{require_keyfile, KEYFILE_NAME};
+
+        let source = TempDir::new().unwrap();
+        let backup = TempDir::new().unwrap();
+        let restore = TempDir::new().unwrap();
+
+        fs::write(source.path().join("secret.txt"), "Secret Data!").unwrap();
+        let backup_config = BackupConfig {
+            source_dir: source.path().to_path_buf(),
+            dest_dir: backup.path().to_path_buf(),
+            ..BackupConfig::default()
+        };
+        BackupExecutor::new(backup_config)
+            .with_encryption("test_password_123")
+            .execute()
+            .unwrap();
+        let master = require_keyfile(backup.path()).unwrap().unlock("test_password_123").unwrap();
+
+        // キーファイルを削除し、平文のマニフェストに差し替える
+        let forged = BackupManifest::from_scan(&crate::backup::ScanResult {
+            source_dir: PathBuf::from("/forged"),
+            scanned_at: Utc::now(),
+            files: std::collections::HashMap::new(),
+            total_files: 0,
+            total_size: 0,
+            ignore_files: vec![],
+            excluded: vec![],
+        }, &BackupConfig::default());
+        fs::remove_file(backup.path().join(KEYFILE_NAME)).unwrap();
+        fs::write(backup.path().join(MANIFEST_FILE), serde_json::to_vec(&forged).unwrap()).unwrap();
+
+        let restore_config = RestoreConfig {
+            backup_dir: backup.path().to_path_buf(),
+            restore_dir: restore.path().to_path_buf(),
+            files: vec![],
+            overwrite: true,
+            snapshot: None,
+            strict: false,
+            trusted_signers: Vec::new(),
+        };
+
+        // マスター鍵はキーファイルなしでは使えず、鍵なしとして平文を読むこともしない
+        let result = RestoreExecutor::new(restore_config).with_master_key(master).execute();
+        assert!(matches!(result, Err(RestoreError::Repository(RepositoryError::KeyFileNotFound(_)))));
+    }
+
+    #[test]
+    fn test_restore_reports_corrupted_chunk() {
+        let source = TempDir::new().unwrap();
+        let backup = TempDir::new().unwrap();
+        let restore = TempDir::new().unwrap();
+
+        fs::write(source.path().join("secret.txt"), "Secret Data!").unwrap();
+        let backup_config = BackupConfig {
+            source_dir: source.path().to_path_buf(),
+            dest_dir: backup.path().to_path_buf(),
+            ..BackupConfig::default()
+        };
+        BackupExecutor::new(backup_config)
+            .with_encryption("test_password_123")
+            .execute()
+            .unwrap();
+
+        // 正しいパスワードでの認証失敗は、パスワード違いではなくデータの改ざん・破損として報告する
+        let chunk = walkdir::WalkDir::new(backup.path().join(CHUNKS_DIR))
+            .into_iter()
+            .flatten()
+            .find(|e| e.file_type().is_file())
+            .unwrap()
+            .into_path();
+        let mut data = fs::read(&chunk).unwrap();
+        *data.last_mut().unwrap() ^= 0x01;
+        fs::write(&chunk, data).unwrap();
+
+        let restore_config = RestoreConfig {
+            backup_dir: backup.path().to_path_buf(),
+            restore_dir: restore.path().to_path_buf(),
+            files: vec![],
+            overwrite: true,
+            snapshot: None,
+            strict: false,
+            trusted_signers: Vec::new(),
+        };
+        let result = RestoreExecutor::new(restore_config)
+            .with_password("test_password_123")
+            .execute()
+            .unwrap();
+        let expected = RestoreError::BlobCorrupted("secret.txt".to_string());
+        assert_eq!(result.failed_files, vec![format!("secret.txt: {}", expected)]);
+        assert!(!restore.path().join("secret.txt").exists());
+    }
+
+    #[test]
+    fn test_restore_with_identity() {
+        let source = TempDir::new().unwrap();
+        let backup = TempDir::new().unwrap();
+        let identity = Identity::generate();
+        let content_key = ContentKey::generate();
+
+        fs::write(source.path().join("secret.txt"), "Secret Data!").unwrap();
+
+        // バックアップ側は公開鍵と内容ID用の鍵のみを持つ
+        for chunking in [true, false] {
+            let backup_config = BackupConfig {
+                source_dir: source.path().to_path_buf(),
+                dest_dir: backup.path().to_path_buf(),
+                chunking,
+                ..BackupConfig::default()
+            };
+            let result = BackupExecutor::new(backup_config)
+                .with_recipients(vec![identity.recipient()])
+                .with_content_key(content_key.clone())
+                .execute()
+                .unwrap();
+            assert!(result.success);
+        }
+
+        let restore_config = |dir: &Path| RestoreConfig {
+            backup_dir: backup.path().to_path_buf(),
+            restore_dir: dir.to_path_buf(),
+            files: vec![],
+            overwrite: true,
+            snapshot: None,
+            strict: false,
+            trusted_signers: Vec::new(),
+        };
+
+        // 別のアイデンティティでは復号できない
+        let restore = TempDir::new().unwrap();
+        let result = RestoreExecutor::new(restore_config(restore.path()))
+            .with_identity(Identity::generate())
+            .execute();
+        assert!(matches!(result, Err(RestoreError::WrongPassword)));
+
+        let result = RestoreExecutor::new(restore_config(restore.path()))
+            .with_identity(identity)
+            .execute()
+            .unwrap();
+        assert!(result.success);
+        assert_eq!(fs::read_to_string(restore.path().join("secret.txt")).unwrap(), "Secret Data!");
+    }
+
+    #[test]
+    fn test_restore_previous_snapshot() {
+        let source = TempDir::new().unwrap();
+        let backup = TempDir::new().unwrap();
+        let restore = TempDir::new().unwrap();
+
+        for chunking in [true, false] {
+            let backup_dir = backup.path().join(format!("chunking_{}", chunking));
+            let backup_config = BackupConfig {
+                source_dir: source.path().to_path_buf(),
+                dest_dir: backup_dir.clone(),
+                chunking,
+                ..BackupConfig::default()
+            };
+
+            fs::write(source.path().join("doc.txt"), "original").unwrap();
+            let first = BackupExecutor::new(backup_config.clone()).execute().unwrap();
+            fs::write(source.path().join("doc.txt"), "overwritten by ransomware").unwrap();
+            let second = BackupExecutor::new(backup_config).execute().unwrap();
+
+            let snapshots = crate::backup::list_snapshots(&backup_dir, None).unwrap();
+            assert_eq!(snapshots.len(), 2);
+            assert_eq!(snapshots[1].parent.as_deref(), Some(first.snapshot_id.as_str()));
+            assert_eq!(snapshots[1].id, second.snapshot_id);
+
+            // 古いスナップショットから元の内容を復元できる
+            let restore_config = RestoreConfig {
+                backup_dir,
+                restore_dir: restore.path().to_path_buf(),
+                files: vec![],
+                overwrite: true,
+                snapshot: Some(first.snapshot_id),
+                strict: false,
+                trusted_signers: Vec::new(),
+            };
+            assert!(RestoreExecutor::new(restore_config).execute().unwrap().success);
+            assert_eq!(fs::read_to_string(restore.path().join("doc.txt")).unwrap(), "original");
+        }
+    }
+
+    #[test]
+    fn test_restore_detects_hash_mismatch() {
+        let source = TempDir::new().unwrap();
+        let backup = TempDir::new().unwrap();
+        let restore = TempDir::new().unwrap();
+
+        fs::write(source.path().join("good.txt"), "good data").unwrap();
+        fs::write(source.path().join("bad.txt"), "bad data").unwrap();
+
+        // 暗号化・圧縮なしでは破損を復号や解凍で検出できない
+        let backup_config = BackupConfig {
+            source_dir: source.path().to_path_buf(),
+            dest_dir: backup.path().to_path_buf(),
+            compress: false,
+            chunking: false,
+            ..BackupConfig::default()
+        };
+        let snapshot_id = BackupExecutor::new(backup_config).execute().unwrap().snapshot_id;
+        fs::write(backup.path().join("data").join(&snapshot_id).join("bad.txt"), "bad dat4").unwrap();
+
+        let mut restore_config = RestoreConfig {
+            backup_dir: backup.path().to_path_buf(),
+            restore_dir: restore.path().to_path_buf(),
+            files: vec![],
+            overwrite: true,
+            snapshot: None,
+            strict: false,
+            trusted_signers: Vec::new(),
+        };
+        let result = RestoreExecutor::new(restore_config.clone()).execute().unwrap();
+        assert!(!result.success);
+        assert_eq!(result.restored_files, 1);
+        assert_eq!(result.hash_mismatches, 1);
+        assert!(!restore.path().join("bad.txt").exists());
+
+        // 厳格モードでは最初の不一致で中止する
+        restore_config.strict = true;
+        let result = RestoreExecutor::new(restore_config).execute();
+        assert!(matches!(result, Err(RestoreError::HashMismatch(path)) if path == "bad.txt"));
+    }
+
+    #[test]
+    fn test_restore_reports_missing_hash() {
+        let source = TempDir::new().unwrap();
+        let backup = TempDir::new().unwrap();
+        let restore = TempDir::new().unwrap();
+
+        fs::write(source.path().join("doc.txt"), "data").unwrap();
+        let backup_config = BackupConfig {
+            source_dir: source.path().to_path_buf(),
+            dest_dir: backup.path().to_path_buf(),
+            ..BackupConfig::default()
+        };
+        BackupExecutor::new(backup_config).execute().unwrap();
+
+        // マニフェストからハッシュを消しても、検証済みとしては扱わない
+        let manifest_path = backup.path().join(MANIFEST_FILE);
+        let mut manifest: serde_json::Value = serde_json::from_slice(&fs::read(&manifest_path).unwrap()).unwrap();
+        manifest["files"]["doc.txt"]["hash"] = "".into();
+        fs::write(&manifest_path, serde_json::to_vec(&manifest).unwrap()).unwrap();
+
+        let mut restore_config = RestoreConfig {
+            backup_dir: backup.path().to_path_buf(),
+            restore_dir: restore.path().to_path_buf(),
+            files: vec![],
+            overwrite: true,
+            snapshot: None,
+            strict: false,
+            trusted_signers: Vec::new(),
+        };
+        let result = RestoreExecutor::new(restore_config.clone()).execute().unwrap();
+        assert_eq!(result.restored_files, 1);
+        assert_eq!(result.unverified_files, vec!["doc.txt".to_string()]);
+
+        // 厳格モードでは検証できないファイルを復元しない
+        fs::remove_file(restore.path().join("doc.txt")).unwrap();
+        restore_config.strict = true;
+        let result = RestoreExecutor::new(restore_config).execute();
+        assert!(matches!(result, Err(RestoreError::HashMissing(path)) if path == "doc.txt"));
+        assert!(!restore.path().join("doc.txt").exists());
+    }
+
+    #[test]
+    fn test_safe_restore_path() {
+        let root = Path::new("/restore");
+
+        assert_eq!(safe_restore_path(root, "a/b.txt").unwrap(), root.join("a").join("b.txt"));
+        assert_eq!(safe_restore_path(root, "./a//b.txt").unwrap(), root.join("a").join("b.txt"));
+        assert_eq!(safe_restore_path(root, "a\\b.txt").unwrap(), root.join("a").join("b.txt"));
+
+        for path in [
+            "../../.bashrc",
+            "a/../../b",
+            "..\\..\\evil.txt",
+            "/etc/passwd",
+            "\\\\server\\share\\x",
+            "C:\\Windows\\evil.dll",
+            "C:evil.dll",
+            "",
+            ".",
+        ] {
+            assert!(
+                matches!(safe_restore_path(root, path), Err(RestoreError::UnsafePath(_))),
+                "{} は拒否されるべき",
+                path,
+            );
+        }
+    }
+
+    #[test]
+    fn test_restore_rejects_tampered_manifest() {
+        let source = TempDir::new().unwrap();
+        let backup = TempDir::new().unwrap();
+        let restore = TempDir::new().unwrap();
+
+        fs::write(source.path().join("innocent.txt"), "payload").unwrap();
+        let backup_config = BackupConfig {
+            source_dir: source.path().to_path_buf(),
+            dest_dir: backup.path().to_path_buf(),
+            ..BackupConfig::default()
+        };
+        BackupExecutor::new(backup_config).execute().unwrap();
+
+        // マニフェストのパスを書き換えて復元先の外を指す
+        let manifest_path = backup.path().join("manifest.json");
+        let mut manifest: BackupManifest =
+            serde_json::from_str(&fs::read_to_string(&manifest_path).unwrap()).unwrap();
+        let mut entry = manifest.files.remove("innocent.txt").unwrap();
+        entry.path = "../escaped.txt".to_string();
+        manifest.files.insert(entry.path.clone(), entry);
+        fs::write(&manifest_path, serde_json::to_string(&manifest).unwrap()).unwrap();
+
+        let restore_dir = restore.path().join("target");
+        let restore_config = RestoreConfig {
+            backup_dir: backup.path().to_path_buf(),
+            restore_dir: restore_dir.clone(),
+            files: vec![],
+            overwrite: true,
+            snapshot: None,
+            strict: false,
+            trusted_signers: Vec::new(),
+        };
+        let result = RestoreExecutor::new(restore_config).execute().unwrap();
+
+        assert!(!result.success);
+        assert_eq!(result.restored_files, 0);
+        assert!(result.failed_files[0].contains("復元先の外"));
+        assert!(!restore.path().join("escaped.txt").exists());
+    }
+
+    #[test]
+    fn test_restore_opaque_names() {
+        let source = TempDir::new().unwrap();
+        let backup = TempDir::new().unwrap();
+        let restore = TempDir::new().unwrap();
+
+        fs::create_dir_all(source.path().join("tax-returns")).unwrap();
+        fs::write(source.path().join("tax-returns").join("2026.pdf"), "Secret Data!").unwrap();
+
+        let backup_config = BackupConfig {
+            source_dir: source.path().to_path_buf(),
+            dest_dir: backup.path().to_path_buf(),
+            chunking: false,
+            opaque_names: true,
+            ..BackupConfig::default()
+        };
+        BackupExecutor::new(backup_config)
+            .with_encryption("test_password_123")
+            .execute()
+            .unwrap();
+
+        // 保存先から元のファイル名・ディレクトリ名が分からない
+        for entry in walkdir::WalkDir::new(backup.path().join("data")) {
+            let name = entry.unwrap().file_name().to_string_lossy().to_string();
+            assert!(!name.contains("tax-returns") && !name.contains("2026.pdf"), "{}", name);
+        }
+
+        let restore_config = RestoreConfig {
+            backup_dir: backup.path().to_path_buf(),
+            restore_dir: restore.path().to_path_buf(),
+            files: vec![],
+            overwrite: true,
+            snapshot: None,
+            strict: false,
+            trusted_signers: Vec::new(),
+        };
+        let restore_result = RestoreExecutor::new(restore_config)
+            .with_password("test_password_123")
+            .execute()
+            .unwrap();
+        assert!(restore_result.success);
+        assert_eq!(
+            fs::read_to_string(restore.path().join("tax-returns").join("2026.pdf")).unwrap(),
+            "Secret Data!"
+        );
+    }
+
+    #[test]
+    fn test_restore_checks_signature() {
+        let source = TempDir::new().unwrap();
+        let backup = TempDir::new().unwrap();
+        let restore = TempDir::new().unwrap();
+
+        fs::write(source.path().join("signed.txt"), "payload").unwrap();
+        let signer = SnapshotSigner::generate();
+        let backup_config = BackupConfig {
+            source_dir: source.path().to_path_buf(),
+            dest_dir: backup.path().to_path_buf(),
+            ..BackupConfig::default()
+        };
+        let backup_result = BackupExecutor::new(backup_config)
+            .with_signer(signer.clone())
+            .execute()
+            .unwrap();
+
+        let restore_with = |snapshot: Option<String>, trusted_signers: Vec<SignerKey>| {
+            RestoreExecutor::new(RestoreConfig {
+                backup_dir: backup.path().to_path_buf(),
+                restore_dir: restore.path().to_path_buf(),
+                files: vec![],
+                overwrite: true,
+                snapshot,
+                strict: false,
+                trusted_signers,
+            })
+            .execute()
+        };
+
+        // 信頼する鍵で署名されていれば復元できる（最新・スナップショット指定とも）
+        let result = restore_with(None, vec![signer.signer_key()]).unwrap();
+        assert!(result.success);
+        assert!(result.signature.is_trusted());
+        let result = restore_with(Some(backup_result.snapshot_id.clone()), vec![signer.signer_key()]).unwrap();
+        assert!(result.signature.is_trusted());
+
+        // 別の鍵のみを信頼する場合は拒否する
+        let other = SnapshotSigner::generate().signer_key();
+        assert!(matches!(restore_with(None, vec![other]), Err(RestoreError::UntrustedSnapshot(_))));
+
+        // 署名後に manifest.json を書き換えると、信頼する鍵の指定がなくても拒否する
+        let manifest_path = backup.path().join(MANIFEST_FILE);
+        let mut data = fs::read(&manifest_path).unwrap();
+        data.push(b'\n');
+        fs::write(&manifest_path, data).unwrap();
+        assert!(matches!(restore_with(None, vec![]), Err(RestoreError::SignatureInvalid(_))));
     }
 }
@@ -0,0 +1,229 @@
+//! 保持ポリシー - 残すスナップショットの選択
+
+use super::SnapshotInfo;
+use chrono::{DateTime, Datelike, Duration, Timelike, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+/// 保持ポリシー（未設定の規則は適用しない）
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RetentionPolicy {
+    /// 最新から指定数を残す
+    #[serde(default)]
+    pub keep_last: Option<u32>,
+
+    /// 1時間ごとに最新の1つを、指定時間数分残す
+    #[serde(default)]
+    pub keep_hourly: Option<u32>,
+
+    /// 1日ごとに最新の1つを、指定日数分残す
+    #[serde(default)]
+    pub keep_daily: Option<u32>,
+
+    /// 1週間ごとに最新の1つを、指定週数分残す
+    #[serde(default)]
+    pub keep_weekly: Option<u32>,
+
+    /// 1か月ごとに最新の1つを、指定月数分残す
+    #[serde(default)]
+    pub keep_monthly: Option<u32>,
+
+    /// 1年ごとに最新の1つを、指定年数分残す
+    #[serde(default)]
+    pub keep_yearly: Option<u32>,
+
+    /// 最新スナップショットから指定時間以内のものをすべて残す
+    #[serde(default)]
+    pub keep_within_hours: Option<u32>,
+}
+
+/// 保持判定の結果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RetentionDecision {
+    /// 対象スナップショット
+    pub snapshot: SnapshotInfo,
+
+    /// 残すか
+    pub keep: bool,
+
+    /// 残す理由（該当した規則）
+    pub reasons: Vec<String>,
+}
+
+/// 期間単位の規則
+#[derive(Debug, Clone, Copy)]
+enum Bucket {
+    Hourly,
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+}
+
+impl Bucket {
+    /// 規則名
+    fn name(self) -> &'static str {
+        match self {
+            Self::Hourly => "hourly",
+            Self::Daily => "daily",
+            Self::Weekly => "weekly",
+            Self::Monthly => "monthly",
+            Self::Yearly => "yearly",
+        }
+    }
+
+    /// 日時が属する期間のキー
+    fn key(self, t: DateTime<Utc>) -> (i32, u32, u32, u32) {
+        match self {
+            Self::Hourly => (t.year(), t.month(), t.day(), t.hour()),
+            Self::Daily => (t.year(), t.month(), t.day(), 0),
+            Self::Weekly => {
+                let week = t.iso_week();
+                (week.year(), week.week(), 0, 0)
+            }
+            Self::Monthly => (t.year(), t.month(), 0, 0),
+            Self::Yearly => (t.year(), 0, 0, 0),
+        }
+    }
+}
+
+impl RetentionPolicy {
+    /// いずれかの規則が設定されているか
+    pub fn is_enabled(&self) -> bool {
+        self.keep_last.is_some()
+            || self.keep_hourly.is_some()
+            || self.keep_daily.is_some()
+            || self.keep_weekly.is_some()
+            || self.keep_monthly.is_some()
+            || self.keep_yearly.is_some()
+            || self.keep_within_hours.is_some()
+    }
+
+    /// 各スナップショットを残すか判定（新しい順に返す）
+    ///
+    /// 規則が1つも設定されていない場合はすべて残す。最新のスナップショットは常に残す。
+    pub fn apply(&self, snapshots: &[SnapshotInfo]) -> Vec<RetentionDecision> {
+        let mut sorted: Vec<&SnapshotInfo> = snapshots.iter().collect();
+        sorted.sort_by(|a, b| b.timestamp.cmp(&a.timestamp).then_with(|| b.id.cmp(&a.id)));
+
+        let mut reasons: Vec<Vec<String>> = vec![Vec::new(); sorted.len()];
+
+        if !self.is_enabled() {
+            reasons.iter_mut().for_each(|r| r.push("no policy".to_string()));
+        }
+
+        // 最新は常に残す（manifest.json が参照しているため）
+        if let Some(first) = reasons.first_mut() {
+            first.push("latest".to_string());
+        }
+
+        if let Some(n) = self.keep_last {
+            for r in reasons.iter_mut().take(n as usize) {
+                r.push("last".to_string());
+            }
+        }
+
+        let buckets = [
+            (Bucket::Hourly, self.keep_hourly),
+            (Bucket::Daily, self.keep_daily),
+            (Bucket::Weekly, self.keep_weekly),
+            (Bucket::Monthly, self.keep_monthly),
+            (Bucket::Yearly, self.keep_yearly),
+        ];
+        for (bucket, count) in buckets {
+            let Some(count) = count else { continue };
+            let mut seen = HashSet::new();
+            for (i, snapshot) in sorted.iter().enumerate() {
+                if seen.len() >= count as usize {
+                    break;
+                }
+                // 期間ごとに最も新しいものだけを残す
+                let key = bucket.key(snapshot.timestamp);
+                if seen.insert(key) {
+                    reasons[i].push(bucket.name().to_string());
+                }
+            }
+        }
+
+        if let (Some(hours), Some(newest)) = (self.keep_within_hours, sorted.first()) {
+            let threshold = newest.timestamp - Duration::hours(hours as i64);
+            for (i, snapshot) in sorted.iter().enumerate() {
+                if snapshot.timestamp >= threshold {
+                    reasons[i].push("within".to_string());
+                }
+            }
+        }
+
+        sorted.into_iter()
+            .zip(reasons)
+            .map(|(snapshot, reasons)| RetentionDecision {
+                snapshot: snapshot.clone(),
+                keep: !reasons.is_empty(),
+                reasons,
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn snapshot(id: &str, t: DateTime<Utc>) -> SnapshotInfo {
+        SnapshotInfo {
+            id: id.to_string(),
+            parent: None,
+            timestamp: t,
+            source_dir: "/test".to_string(),
+            total_files: 0,
+            total_original_size: 0,
+        }
+    }
+
+    /// 1日2回（0時・12時）、10日分のスナップショット
+    fn ten_days() -> Vec<SnapshotInfo> {
+        (0..20)
+            .map(|i| {
+                let t = Utc.with_ymd_and_hms(2026, 3, 1, 0, 0, 0).unwrap() + Duration::hours(12 * i);
+                snapshot(&format!("s{:02}", i), t)
+            })
+            .collect()
+    }
+
+    fn kept(decisions: &[RetentionDecision]) -> Vec<String> {
+        decisions.iter()
+            .filter(|d| d.keep)
+            .map(|d| d.snapshot.id.clone())
+            .collect()
+    }
+
+    #[test]
+    fn test_no_policy_keeps_everything() {
+        let decisions = RetentionPolicy::default().apply(&ten_days());
+        assert!(decisions.iter().all(|d| d.keep));
+    }
+
+    #[test]
+    fn test_keep_last_and_daily() {
+        let policy = RetentionPolicy {
+            keep_last: Some(2),
+            keep_daily: Some(3),
+            ..RetentionPolicy::default()
+        };
+        let decisions = policy.apply(&ten_days());
+
+        // 最新2つ + 直近3日の各日で最新（12時）のもの
+        assert_eq!(kept(&decisions), vec!["s19", "s18", "s17", "s15"]);
+    }
+
+    #[test]
+    fn test_keep_within() {
+        let policy = RetentionPolicy {
+            keep_within_hours: Some(36),
+            ..RetentionPolicy::default()
+        };
+        let decisions = policy.apply(&ten_days());
+        assert_eq!(kept(&decisions), vec!["s19", "s18", "s17", "s16"]);
+    }
+}
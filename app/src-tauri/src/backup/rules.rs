@@ -0,0 +1,261 @@
+//! 除外ルール - gitignore 形式のパターンによる除外・再包含
+//!
+//! パターンは .gitignore と同じ書式で解釈する。
+//! - `*.tmp` のように `/` を含まないパターンは任意の階層の名前に一致する
+//! - `/build` のように先頭（または途中）に `/` を含むパターンはソースディレクトリからの相対パスに固定される
+//! - `**` は任意の階層（0個以上のディレクトリ）に一致する
+//! - 末尾に `/` を付けたパターンはディレクトリにのみ一致する
+//! - `!keep.log` のように `!` で始まるパターンは除外を打ち消す（後に書いたルールが優先される）
+//!
+//! gitignore と同様、除外したディレクトリの中のファイルは再包含できない（ディレクトリごと走査しないため）。
+//!
+//! 走査中に見つけた `.backupignore`（有効にした場合は `.gitignore` も）はそのディレクトリ以下に適用する。
+
+use super::{ExcludeReason, ScanError};
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use ignore::Match;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// ディレクトリごとの除外ファイル名
+pub const BACKUP_IGNORE_FILE: &str = ".backupignore";
+
+/// Git の除外ファイル名（有効にした場合のみ参照する）
+pub const GIT_IGNORE_FILE: &str = ".gitignore";
+
+/// 除外・再包含ルール
+#[derive(Debug, Clone)]
+pub struct ExcludeRules {
+    exclude: Gitignore,
+    include: Gitignore,
+}
+
+impl ExcludeRules {
+    /// ルールを作成
+    ///
+    /// # Arguments
+    /// * `root` - 固定パターン（`/build` など）の基準となるディレクトリ
+    /// * `exclude` - 除外パターン
+    /// * `include` - 再包含パターン（除外パターンやディレクトリごとの除外ファイルより優先）
+    pub fn new(root: &Path, exclude: &[String], include: &[String]) -> Result<Self, ScanError> {
+        let build = |patterns: &[String]| -> Result<Gitignore, ScanError> {
+            let mut builder = GitignoreBuilder::new(root);
+            for pattern in patterns {
+                builder.add_line(None, pattern)?;
+            }
+            Ok(builder.build()?)
+        };
+
+        Ok(Self {
+            exclude: build(exclude)?,
+            include: build(include)?,
+        })
+    }
+
+    /// 再包含パターンに一致するかチェック
+    ///
+    /// # Arguments
+    /// * `relative` - ソースディレクトリからの相対パス
+    /// * `is_dir` - ディレクトリか
+    pub fn is_included(&self, relative: &Path, is_dir: bool) -> bool {
+        matches!(self.include.matched(relative, is_dir), Match::Ignore(_))
+    }
+
+    /// 除外対象かチェック
+    ///
+    /// # Arguments
+    /// * `relative` - ソースディレクトリからの相対パス
+    /// * `is_dir` - ディレクトリか
+    pub fn is_excluded(&self, relative: &Path, is_dir: bool) -> bool {
+        self.excluded_by(relative, is_dir).is_some()
+    }
+
+    /// 除外対象であれば一致した除外パターン
+    pub fn excluded_by(&self, relative: &Path, is_dir: bool) -> Option<String> {
+        if self.is_included(relative, is_dir) {
+            return None;
+        }
+        match self.exclude.matched(relative, is_dir) {
+            Match::Ignore(glob) => Some(glob.original().to_string()),
+            _ => None,
+        }
+    }
+}
+
+/// 除外ファイルごとの集計
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IgnoreFileReport {
+    /// 除外ファイルのパス（ソースディレクトリからの相対パス）
+    pub path: String,
+
+    /// このファイルのルールで除外したエントリ数（除外したディレクトリは中身を含めて1件と数える）
+    pub excluded: usize,
+}
+
+/// 除外ファイルによる判定結果
+pub(super) enum IgnoreMatch {
+    /// どのルールにも一致しない
+    None,
+    /// 除外する
+    Excluded(ExcludeReason),
+    /// `!` で打ち消された
+    Whitelisted,
+}
+
+/// 走査中のディレクトリ階層で有効な除外ファイル
+///
+/// 除外ファイルのルールはそのディレクトリ以下に適用し、深い階層のファイルほど優先する。
+/// 同じディレクトリでは `names` の後のファイルほど優先する。
+pub(super) struct IgnoreStack {
+    names: Vec<&'static str>,
+    frames: Vec<IgnoreFrame>,
+    reports: Vec<IgnoreFileReport>,
+}
+
+struct IgnoreFrame {
+    /// 除外ファイルのあるディレクトリの深さ
+    depth: usize,
+    matcher: Gitignore,
+    /// `reports` の添字
+    report: usize,
+}
+
+impl IgnoreStack {
+    pub(super) fn new(names: Vec<&'static str>) -> Self {
+        Self {
+            names,
+            frames: Vec::new(),
+            reports: Vec::new(),
+        }
+    }
+
+    /// 深さ `depth` のエントリに進む前に、祖先でないディレクトリの除外ファイルを外す
+    pub(super) fn leave(&mut self, depth: usize) {
+        while self.frames.last().is_some_and(|f| f.depth >= depth) {
+            self.frames.pop();
+        }
+    }
+
+    /// ディレクトリに入る際に、そのディレクトリの除外ファイルを読み込む
+    ///
+    /// 書式の誤った行は git と同様に無視し、残りの行を適用する。
+    pub(super) fn enter(&mut self, source: &Path, dir: &Path, depth: usize) {
+        for name in &self.names {
+            let path = dir.join(name);
+            if !path.is_file() {
+                continue;
+            }
+
+            let mut builder = GitignoreBuilder::new(dir);
+            let _ = builder.add(&path);
+            let Ok(matcher) = builder.build() else {
+                continue;
+            };
+
+            self.reports.push(IgnoreFileReport {
+                path: path.strip_prefix(source).unwrap_or(&path).to_string_lossy().replace('\\', "/"),
+                excluded: 0,
+            });
+            self.frames.push(IgnoreFrame {
+                depth,
+                matcher,
+                report: self.reports.len() - 1,
+            });
+        }
+    }
+
+    /// 除外ファイルのルールで判定し、除外した場合はそのファイルの件数に加算
+    pub(super) fn check(&mut self, path: &Path, is_dir: bool) -> IgnoreMatch {
+        for frame in self.frames.iter().rev() {
+            match frame.matcher.matched(path, is_dir) {
+                Match::None => continue,
+                Match::Ignore(glob) => {
+                    let report = &mut self.reports[frame.report];
+                    report.excluded += 1;
+                    return IgnoreMatch::Excluded(ExcludeReason::IgnoreFile {
+                        file: report.path.clone(),
+                        pattern: glob.original().to_string(),
+                    });
+                }
+                Match::Whitelist(_) => return IgnoreMatch::Whitelisted,
+            }
+        }
+        IgnoreMatch::None
+    }
+
+    /// 読み込んだ除外ファイルの集計（パス順）
+    pub(super) fn into_reports(mut self) -> Vec<IgnoreFileReport> {
+        self.reports.sort_by(|a, b| a.path.cmp(&b.path));
+        self.reports
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules(exclude: &[&str], include: &[&str]) -> ExcludeRules {
+        let to_vec = |p: &[&str]| p.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        ExcludeRules::new(Path::new("/src"), &to_vec(exclude), &to_vec(include)).unwrap()
+    }
+
+    #[test]
+    fn test_name_is_not_substring() {
+        let rules = rules(&["target"], &[]);
+        assert!(rules.is_excluded(Path::new("target"), true));
+        assert!(rules.is_excluded(Path::new("crates/app/target"), true));
+        // 名前の一部に含むだけでは除外しない
+        assert!(!rules.is_excluded(Path::new("targets.xlsx"), false));
+        assert!(!rules.is_excluded(Path::new("my_target_notes"), true));
+    }
+
+    #[test]
+    fn test_wildcards() {
+        let rules = rules(&["*.tmp", "logs/**/*.log", "cache?"], &[]);
+        assert!(rules.is_excluded(Path::new("a.tmp"), false));
+        assert!(rules.is_excluded(Path::new("deep/dir/b.tmp"), false));
+        assert!(!rules.is_excluded(Path::new("a.tmp.txt"), false));
+        assert!(rules.is_excluded(Path::new("logs/app.log"), false));
+        assert!(rules.is_excluded(Path::new("logs/2026/10/app.log"), false));
+        assert!(!rules.is_excluded(Path::new("other/logs/app.log"), false));
+        assert!(rules.is_excluded(Path::new("cache1"), true));
+        assert!(!rules.is_excluded(Path::new("cache10"), true));
+    }
+
+    #[test]
+    fn test_anchored_and_dir_only() {
+        let rules = rules(&["/build", "tmp/"], &[]);
+        assert!(rules.is_excluded(Path::new("build"), true));
+        assert!(!rules.is_excluded(Path::new("src/build"), true));
+        // 末尾の `/` はディレクトリのみ
+        assert!(rules.is_excluded(Path::new("src/tmp"), true));
+        assert!(!rules.is_excluded(Path::new("src/tmp"), false));
+    }
+
+    #[test]
+    fn test_negation() {
+        // 後に書いたルールが優先される
+        let keep = rules(&["*.log", "!keep.log"], &[]);
+        assert!(keep.is_excluded(Path::new("debug.log"), false));
+        assert!(!keep.is_excluded(Path::new("keep.log"), false));
+        assert!(!keep.is_excluded(Path::new("sub/keep.log"), false));
+
+        let overridden = rules(&["!keep.log", "*.log"], &[]);
+        assert!(overridden.is_excluded(Path::new("keep.log"), false));
+    }
+
+    #[test]
+    fn test_include_takes_precedence() {
+        // 再包含パターンは除外パターンの順序によらず優先される
+        let rules = rules(&["*.psd", "important.psd"], &["important.psd"]);
+        assert!(rules.is_excluded(Path::new("draft.psd"), false));
+        assert!(!rules.is_excluded(Path::new("important.psd"), false));
+        assert!(!rules.is_excluded(Path::new("design/important.psd"), false));
+    }
+
+    #[test]
+    fn test_invalid_pattern() {
+        let result = ExcludeRules::new(Path::new("/src"), &["[z-a]".to_string()], &[]);
+        assert!(matches!(result, Err(ScanError::InvalidPattern(_))));
+    }
+}
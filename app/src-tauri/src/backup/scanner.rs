@@ -1,8 +1,10 @@
 //! ファイルスキャナー - ディレクトリ走査と差分検出
 
+use super::{ExcludeRules, FilterPipeline, IgnoreFileReport, IgnoreMatch, IgnoreStack, BACKUP_IGNORE_FILE, GIT_IGNORE_FILE};
+use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::fs;
+use std::fs::{self, File};
 use std::path::{Path, PathBuf};
 use walkdir::WalkDir;
 use chrono::{DateTime, Utc};
@@ -19,6 +21,12 @@ pub enum ScanError {
 
     #[error("ファイル走査エラー: {0}")]
     WalkDir(#[from] walkdir::Error),
+
+    #[error("除外パターンが正しくありません: {0}")]
+    InvalidPattern(#[from] ignore::Error),
+
+    #[error("ワーカースレッドを起動できません: {0}")]
+    ThreadPool(#[from] rayon::ThreadPoolBuildError),
 }
 
 /// ファイル情報
@@ -59,10 +67,18 @@ impl FileInfo {
 
     /// ハッシュを計算して設定
     pub fn compute_hash(&mut self, base: &Path) -> Result<(), ScanError> {
+        self.compute_hash_with(base, &blake3::Hasher::new())
+    }
+
+    /// 指定した計算器（鍵付き BLAKE3 など）でハッシュを計算して設定
+    ///
+    /// ファイル全体をメモリに読み込まず、固定長のバッファで読みながら計算する。
+    /// メモリマップは使わない（走査中に元のファイルが切り詰められるとプロセスごと異常終了するため）。
+    pub fn compute_hash_with(&mut self, base: &Path, hasher: &blake3::Hasher) -> Result<(), ScanError> {
         let full_path = base.join(&self.relative_path);
-        let data = fs::read(&full_path)?;
-        let hash = blake3::hash(&data);
-        self.hash = Some(hash.to_hex().to_string());
+        let mut hasher = hasher.clone();
+        hasher.update_reader(File::open(&full_path)?)?;
+        self.hash = Some(hasher.finalize().to_hex().to_string());
         Ok(())
     }
 }
@@ -84,6 +100,109 @@ pub struct ScanResult {
 
     /// 合計サイズ（バイト）
     pub total_size: u64,
+
+    /// 走査中に見つけた除外ファイルと、それぞれが除外したエントリ数
+    #[serde(default)]
+    pub ignore_files: Vec<IgnoreFileReport>,
+
+    /// 除外したエントリと理由（パス順）
+    #[serde(default)]
+    pub excluded: Vec<ExcludedEntry>,
+}
+
+impl ScanResult {
+    /// 全ファイルのハッシュをワーカースレッドで計算して設定
+    ///
+    /// # Arguments
+    /// * `hasher` - ハッシュ計算器（鍵付き BLAKE3 など）
+    /// * `threads` - スレッド数（0 の場合は論理CPU数）
+    pub fn compute_hashes(&mut self, hasher: &blake3::Hasher, threads: usize) -> Result<(), ScanError> {
+        let source = &self.source_dir;
+        let errors: Vec<_> = worker_pool(threads)?.install(|| {
+            self.files.par_iter_mut()
+                .filter_map(|(path, file)| file.compute_hash_with(source, hasher).err().map(|e| (path.clone(), e)))
+                .collect()
+        });
+
+        // スレッド数や処理順によらず同じエラーを返すよう、パスの最も小さいものを選ぶ
+        match errors.into_iter().min_by(|a, b| a.0.cmp(&b.0)) {
+            Some((_, e)) => Err(e),
+            None => Ok(()),
+        }
+    }
+}
+
+/// 指定したスレッド数のワーカープールを作成（0 の場合は論理CPU数）
+fn worker_pool(threads: usize) -> Result<rayon::ThreadPool, ScanError> {
+    Ok(rayon::ThreadPoolBuilder::new().num_threads(threads).build()?)
+}
+
+/// 既定の除外パターン
+pub const DEFAULT_EXCLUDE_PATTERNS: &[&str] = &[".git", "node_modules", "target", ".DS_Store", "Thumbs.db"];
+
+/// スキャン対象の絞り込み条件（バックアップ先のマニフェストにジョブの設定として記録する）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScanFilters {
+    /// 除外パターン（gitignore 形式、後に書いたものが優先）
+    pub exclude: Vec<String>,
+
+    /// 再包含パターン（除外パターンやディレクトリごとの除外ファイルより優先）
+    pub include: Vec<String>,
+
+    /// ディレクトリごとの .gitignore も適用するか（.backupignore は常に適用する）
+    pub use_gitignore: bool,
+
+    /// これより大きいファイルを除外（バイト）
+    pub max_file_size: Option<u64>,
+
+    /// 最終更新からこの日数を超えたファイルを除外
+    pub max_age_days: Option<u32>,
+
+    /// 内容から判定した種類がこれらに一致するファイルを除外（MIME タイプ、`video/*` のような指定も可）
+    pub exclude_content_types: Vec<String>,
+}
+
+impl Default for ScanFilters {
+    fn default() -> Self {
+        Self {
+            exclude: DEFAULT_EXCLUDE_PATTERNS.iter().map(|p| p.to_string()).collect(),
+            include: Vec::new(),
+            use_gitignore: false,
+            max_file_size: None,
+            max_age_days: None,
+            exclude_content_types: Vec::new(),
+        }
+    }
+}
+
+/// 除外理由
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ExcludeReason {
+    /// 除外パターンに一致
+    Pattern { pattern: String },
+    /// ディレクトリごとの除外ファイル（ソースディレクトリからの相対パス）のパターンに一致
+    IgnoreFile { file: String, pattern: String },
+    /// サイズが上限を超える
+    TooLarge { size: u64, limit: u64 },
+    /// 最終更新が古い
+    TooOld { modified: DateTime<Utc>, max_age_days: u32 },
+    /// 内容から判定した種類（MIME タイプ）が除外対象
+    ContentType { content_type: String },
+}
+
+/// 除外したエントリ
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExcludedEntry {
+    /// 相対パス
+    pub path: String,
+
+    /// ディレクトリか（除外したディレクトリの中身は走査しない）
+    pub is_dir: bool,
+
+    /// 除外理由
+    pub reason: ExcludeReason,
 }
 
 /// ディレクトリスキャナー
@@ -91,26 +210,24 @@ pub struct DirectoryScanner {
     /// スキャン対象ディレクトリ
     source: PathBuf,
 
-    /// 除外パターン
-    exclude_patterns: Vec<String>,
+    /// 絞り込み条件
+    filters: ScanFilters,
 
     /// ハッシュ計算を行うか
     compute_hash: bool,
+
+    /// ファイル単位の条件判定・ハッシュ計算に使うスレッド数（0 の場合は論理CPU数）
+    threads: usize,
 }
 
 impl DirectoryScanner {
-    /// 新しいスキャナーを作成
+    /// 新しいスキャナーを作成（既定の除外パターンを適用する）
     pub fn new(source: impl Into<PathBuf>) -> Self {
         Self {
             source: source.into(),
-            exclude_patterns: vec![
-                ".git".to_string(),
-                "node_modules".to_string(),
-                "target".to_string(),
-                ".DS_Store".to_string(),
-                "Thumbs.db".to_string(),
-            ],
+            filters: ScanFilters::default(),
             compute_hash: false,
+            threads: 0,
         }
     }
 
@@ -120,58 +237,155 @@ impl DirectoryScanner {
         self
     }
 
+    /// ファイル単位の条件判定・ハッシュ計算に使うスレッド数を設定（0 の場合は論理CPU数）
+    ///
+    /// スレッド数によらず、スキャン結果は同じになる。
+    pub fn with_threads(mut self, threads: usize) -> Self {
+        self.threads = threads;
+        self
+    }
+
+    /// 絞り込み条件を設定（既定の除外パターンも置き換える）
+    pub fn with_filters(mut self, filters: ScanFilters) -> Self {
+        self.filters = filters;
+        self
+    }
+
     /// 除外パターンを追加
+    ///
+    /// .gitignore と同じ書式（`*.tmp`、`/build`、`logs/**`、`cache/`、`!keep.log` など）で、後に追加したものが優先される。
     pub fn exclude(mut self, pattern: impl Into<String>) -> Self {
-        self.exclude_patterns.push(pattern.into());
+        self.filters.exclude.push(pattern.into());
+        self
+    }
+
+    /// 再包含パターンを追加（一致するパスは除外パターンや除外ファイルに一致しても対象とする）
+    pub fn include(mut self, pattern: impl Into<String>) -> Self {
+        self.filters.include.push(pattern.into());
+        self
+    }
+
+    /// .backupignore に加えて .gitignore も適用する（同じディレクトリでは .backupignore を優先）
+    pub fn with_gitignore(mut self) -> Self {
+        self.filters.use_gitignore = true;
         self
     }
 
     /// ディレクトリをスキャン
+    ///
+    /// 再包含パターン、ディレクトリごとの除外ファイル（深い階層ほど優先）、除外パターンの順に判定し、
+    /// 残ったファイルにサイズ・更新日時・内容の種類の条件（[`FilterPipeline`]）を適用する。
+    /// ディレクトリの走査は順に行い、ファイル単位の条件判定とハッシュ計算はワーカースレッドで並列に行う。
     pub fn scan(&self) -> Result<ScanResult, ScanError> {
         if !self.source.exists() {
             return Err(ScanError::DirectoryNotFound(self.source.clone()));
         }
 
-        let mut files = HashMap::new();
-        let mut total_size = 0u64;
+        let rules = ExcludeRules::new(&self.source, &self.filters.exclude, &self.filters.include)?;
+        let mut ignores = IgnoreStack::new(if self.filters.use_gitignore {
+            vec![GIT_IGNORE_FILE, BACKUP_IGNORE_FILE]
+        } else {
+            vec![BACKUP_IGNORE_FILE]
+        });
+        let scanned_at = Utc::now();
+        let pipeline = FilterPipeline::from_filters(&self.filters, scanned_at);
+        // パターンで除外したエントリと、ファイル単位の条件を判定するファイル（走査順）
+        let mut pruned = Vec::new();
+        let mut candidates = Vec::new();
 
         for entry in WalkDir::new(&self.source)
             .follow_links(false)
             .into_iter()
-            .filter_entry(|e| !self.is_excluded(e.path()))
+            .filter_entry(|e| {
+                let is_dir = e.file_type().is_dir();
+                ignores.leave(e.depth());
+
+                // ソースディレクトリ自体は除外しない
+                if e.depth() > 0 {
+                    let relative = e.path().strip_prefix(&self.source).unwrap_or(e.path());
+                    let reason = if rules.is_included(relative, is_dir) {
+                        None
+                    } else {
+                        match ignores.check(e.path(), is_dir) {
+                            IgnoreMatch::Excluded(reason) => Some(reason),
+                            IgnoreMatch::Whitelisted => None,
+                            IgnoreMatch::None => rules.excluded_by(relative, is_dir)
+                                .map(|pattern| ExcludeReason::Pattern { pattern }),
+                        }
+                    };
+                    if let Some(reason) = reason {
+                        pruned.push(ExcludedEntry {
+                            path: relative.to_string_lossy().replace('\\', "/"),
+                            is_dir,
+                            reason,
+                        });
+                        return false;
+                    }
+                }
+
+                if is_dir {
+                    ignores.enter(&self.source, e.path(), e.depth());
+                }
+                true
+            })
         {
             let entry = entry?;
             if entry.file_type().is_file() {
-                let mut file_info = FileInfo::from_path(&self.source, entry.path())?;
+                candidates.push(FileInfo::from_path(&self.source, entry.path())?);
+            }
+        }
 
-                if self.compute_hash {
-                    file_info.compute_hash(&self.source)?;
-                }
+        let checked: Vec<_> = worker_pool(self.threads)?.install(|| {
+            candidates.into_par_iter()
+                .map(|file_info| self.check_file(&pipeline, file_info))
+                .collect()
+        });
 
-                total_size += file_info.size;
-                files.insert(file_info.relative_path.clone(), file_info);
+        // 結果は走査順に並んでいるため、エラーも走査順で最初のものを返す
+        let mut files = HashMap::new();
+        let mut total_size = 0u64;
+        let mut excluded = pruned;
+        for result in checked {
+            match result? {
+                Ok(file_info) => {
+                    total_size += file_info.size;
+                    files.insert(file_info.relative_path.clone(), file_info);
+                }
+                Err(entry) => excluded.push(entry),
             }
         }
+        excluded.sort_by(|a, b| a.path.cmp(&b.path));
 
         Ok(ScanResult {
             source_dir: self.source.clone(),
-            scanned_at: Utc::now(),
+            scanned_at,
             total_files: files.len(),
             total_size,
             files,
+            ignore_files: ignores.into_reports(),
+            excluded,
         })
     }
 
-    /// パスが除外対象かチェック
-    fn is_excluded(&self, path: &Path) -> bool {
-        path.components().any(|c| {
-            if let std::path::Component::Normal(name) = c {
-                let name_str = name.to_string_lossy();
-                self.exclude_patterns.iter().any(|p| name_str.contains(p))
-            } else {
-                false
-            }
-        })
+    /// ファイル単位の条件を判定し、残すファイルはハッシュを計算（除外する場合は `Err` に理由を入れる）
+    fn check_file(
+        &self,
+        pipeline: &FilterPipeline,
+        mut file_info: FileInfo,
+    ) -> Result<Result<FileInfo, ExcludedEntry>, ScanError> {
+        let path = self.source.join(&file_info.relative_path);
+        if let Some(reason) = pipeline.check(&file_info, &path)? {
+            return Ok(Err(ExcludedEntry {
+                path: file_info.relative_path,
+                is_dir: false,
+                reason,
+            }));
+        }
+
+        if self.compute_hash {
+            file_info.compute_hash(&self.source)?;
+        }
+        Ok(Ok(file_info))
     }
 }
 
@@ -267,6 +481,170 @@ mod tests {
         assert!(result.files.contains_key("test.txt"));
     }
 
+    #[test]
+    fn test_scan_exclude_rules() {
+        let temp = TempDir::new().unwrap();
+        for path in ["targets.xlsx", "target/debug/app", "build/out.o", "src/build/gen.rs", "a.tmp", "logs/keep.log", "logs/debug.log"] {
+            let path = temp.path().join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, "x").unwrap();
+        }
+
+        let result = DirectoryScanner::new(temp.path())
+            .exclude("/build")
+            .exclude("*.tmp")
+            .exclude("logs/*.log")
+            .include("keep.log")
+            .scan()
+            .unwrap();
+
+        let mut files: Vec<_> = result.files.keys().map(String::as_str).collect();
+        files.sort();
+        assert_eq!(files, vec!["logs/keep.log", "src/build/gen.rs", "targets.xlsx"]);
+    }
+
+    #[test]
+    fn test_scan_backupignore_hierarchy() {
+        let temp = TempDir::new().unwrap();
+        let files = [
+            ".backupignore", "a.log", "keep.log", "cache/x.bin",
+            "project/.backupignore", "project/.gitignore", "project/b.log", "project/dist/app.js", "project/src/main.rs",
+            "other/dist/app.js",
+        ];
+        for path in files {
+            let path = temp.path().join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, "x").unwrap();
+        }
+        fs::write(temp.path().join(".backupignore"), "*.log\n!keep.log\ncache/\n").unwrap();
+        // サブディレクトリのルールはその配下にのみ適用し、親のルールより優先する
+        fs::write(temp.path().join("project/.backupignore"), "!b.log\n").unwrap();
+        fs::write(temp.path().join("project/.gitignore"), "dist/\n").unwrap();
+
+        let scan = |scanner: DirectoryScanner| {
+            let result = scanner.scan().unwrap();
+            let mut files: Vec<_> = result.files.keys().cloned().collect();
+            files.sort();
+            (files, result.ignore_files)
+        };
+
+        let (files, reports) = scan(DirectoryScanner::new(temp.path()));
+        assert_eq!(files, vec![
+            ".backupignore", "keep.log", "other/dist/app.js", "project/.backupignore", "project/.gitignore",
+            "project/b.log", "project/dist/app.js", "project/src/main.rs",
+        ]);
+        let counts: Vec<_> = reports.iter().map(|r| (r.path.as_str(), r.excluded)).collect();
+        assert_eq!(counts, vec![(".backupignore", 2), ("project/.backupignore", 0)]);
+
+        // .gitignore は有効にした場合のみ適用する
+        let (files, reports) = scan(DirectoryScanner::new(temp.path()).with_gitignore());
+        assert!(!files.contains(&"project/dist/app.js".to_string()));
+        assert!(files.contains(&"other/dist/app.js".to_string()));
+        assert_eq!(reports.iter().find(|r| r.path == "project/.gitignore").unwrap().excluded, 1);
+
+        // 再包含パターンは除外ファイルより優先する
+        let (files, _) = scan(DirectoryScanner::new(temp.path()).include("a.log"));
+        assert!(files.contains(&"a.log".to_string()));
+    }
+
+    #[test]
+    fn test_scan_size_and_age_filters() {
+        let temp = TempDir::new().unwrap();
+        fs::write(temp.path().join("small.txt"), "x").unwrap();
+        fs::write(temp.path().join("large.bin"), vec![0u8; 2048]).unwrap();
+        let old = File::create(temp.path().join("old.txt")).unwrap();
+        old.set_modified(std::time::SystemTime::now() - std::time::Duration::from_secs(100 * 24 * 3600)).unwrap();
+        fs::create_dir(temp.path().join("target")).unwrap();
+
+        let result = DirectoryScanner::new(temp.path())
+            .with_filters(ScanFilters {
+                max_file_size: Some(1024),
+                max_age_days: Some(90),
+                ..ScanFilters::default()
+            })
+            .scan()
+            .unwrap();
+
+        assert_eq!(result.files.keys().collect::<Vec<_>>(), vec!["small.txt"]);
+        let reasons: Vec<_> = result.excluded.iter().map(|e| (e.path.as_str(), &e.reason)).collect();
+        assert_eq!(reasons.len(), 3);
+        assert_eq!(reasons[0], ("large.bin", &ExcludeReason::TooLarge { size: 2048, limit: 1024 }));
+        assert!(matches!(reasons[1], ("old.txt", ExcludeReason::TooOld { max_age_days: 90, .. })));
+        assert_eq!(reasons[2], ("target", &ExcludeReason::Pattern { pattern: "target".to_string() }));
+
+        // 既定の除外パターンも置き換えられる
+        let result = DirectoryScanner::new(temp.path())
+            .with_filters(ScanFilters { exclude: vec![], ..ScanFilters::default() })
+            .scan()
+            .unwrap();
+        assert!(result.excluded.is_empty());
+        assert_eq!(result.total_files, 3);
+    }
+
+    #[test]
+    fn test_scan_content_type_filter() {
+        let temp = TempDir::new().unwrap();
+        fs::write(temp.path().join("notes.txt"), "x").unwrap();
+        fs::write(temp.path().join("vm.bin"), b"QFI\xfb\x00\x00\x00\x03").unwrap();
+
+        let result = DirectoryScanner::new(temp.path())
+            .with_hash()
+            .with_filters(ScanFilters {
+                exclude_content_types: crate::backup::DISK_IMAGE_CONTENT_TYPES.iter().map(|t| t.to_string()).collect(),
+                ..ScanFilters::default()
+            })
+            .scan()
+            .unwrap();
+
+        assert_eq!(result.files.keys().collect::<Vec<_>>(), vec!["notes.txt"]);
+        assert_eq!(result.excluded.len(), 1);
+        assert_eq!(result.excluded[0].reason, ExcludeReason::ContentType {
+            content_type: "application/x-qemu-disk".to_string(),
+        });
+    }
+
+    #[test]
+    fn test_parallel_scan_is_deterministic() {
+        let temp = TempDir::new().unwrap();
+        for i in 0..50 {
+            let path = temp.path().join(format!("dir{}/file{}.txt", i % 5, i));
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, format!("content {}", i)).unwrap();
+        }
+        // 読み込みバッファより大きいファイル
+        let large: Vec<u8> = (0..1_000_000u32).map(|i| (i % 251) as u8).collect();
+        fs::write(temp.path().join("large.bin"), &large).unwrap();
+        fs::write(temp.path().join("skip.tmp"), "x").unwrap();
+
+        let scan = |threads| {
+            DirectoryScanner::new(temp.path())
+                .exclude("*.tmp")
+                .with_hash()
+                .with_threads(threads)
+                .scan()
+                .unwrap()
+        };
+        let single = scan(1);
+        assert_eq!(single.total_files, 51);
+        assert_eq!(single.files["large.bin"].hash, Some(blake3::hash(&large).to_hex().to_string()));
+
+        for threads in [2, 8] {
+            let parallel = scan(threads);
+            assert_eq!(parallel.total_size, single.total_size);
+            assert_eq!(parallel.excluded.len(), single.excluded.len());
+            for (path, info) in &single.files {
+                assert_eq!(parallel.files[path].hash, info.hash);
+            }
+        }
+
+        // 後からまとめて計算しても同じハッシュになる
+        let mut unhashed = DirectoryScanner::new(temp.path()).exclude("*.tmp").scan().unwrap();
+        unhashed.compute_hashes(&blake3::Hasher::new(), 4).unwrap();
+        for (path, info) in &single.files {
+            assert_eq!(unhashed.files[path].hash, info.hash);
+        }
+    }
+
     #[test]
     fn test_compute_diff() {
         let mut old_files = HashMap::new();
@@ -289,6 +667,8 @@ mod tests {
             files: old_files,
             total_files: 2,
             total_size: 300,
+            ignore_files: vec![],
+            excluded: vec![],
         };
 
         let mut new_files = HashMap::new();
@@ -311,6 +691,8 @@ mod tests {
             files: new_files,
             total_files: 2,
             total_size: 400,
+            ignore_files: vec![],
+            excluded: vec![],
         };
 
         let diff = compute_diff(&old, &new);
@@ -0,0 +1,176 @@
+//! スナップショットの署名ファイル
+//!
+//! `snapshots/<ID>.json` の署名は `snapshots/<ID>.sig`、`manifest.json` の署名は `manifest.sig` に保存する。
+//! 署名は保存したバイト列と保存先（暗号化コンテキストと同じ文字列）に対して行う。
+//! 信頼する公開鍵の一覧はリポジトリの外（利用者の設定）で管理する。
+
+use super::{is_valid_snapshot_id, snapshot_context, RestoreError, MANIFEST_CONTEXT, MANIFEST_FILE, SNAPSHOTS_DIR};
+use crate::crypto::{SignerKey, SnapshotSigner};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// 署名ファイルの拡張子
+pub const SIGNATURE_EXTENSION: &str = "sig";
+
+/// 署名ファイル
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignatureFile {
+    /// 署名者（公開鍵、`sbs1...`）
+    pub signer: String,
+
+    /// 署名（hex）
+    pub signature: String,
+
+    /// 署名日時
+    pub signed_at: DateTime<Utc>,
+}
+
+/// 署名の検証結果
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum SignatureStatus {
+    /// 信頼する鍵による有効な署名
+    Trusted { signer: String },
+    /// 有効な署名だが、署名者が信頼する鍵の一覧にない
+    Untrusted { signer: String },
+    /// 署名がない
+    Unsigned,
+    /// 署名が一致しない（署名後に改ざんされた）
+    Invalid,
+}
+
+impl SignatureStatus {
+    /// 信頼する鍵で署名されているか
+    pub fn is_trusted(&self) -> bool {
+        matches!(self, Self::Trusted { .. })
+    }
+}
+
+/// 署名対象のファイルに対応する署名ファイルのパス
+pub fn signature_path(path: &Path) -> PathBuf {
+    path.with_extension(SIGNATURE_EXTENSION)
+}
+
+/// 保存したデータの署名ファイルを書き込み
+///
+/// 署名鍵がない場合は、以前の署名が残って不一致と判定されないよう既存の署名ファイルを削除する。
+pub fn write_signature(path: &Path, data: &[u8], context: &str, signer: Option<&SnapshotSigner>) -> io::Result<()> {
+    let sig_path = signature_path(path);
+    let Some(signer) = signer else {
+        return match fs::remove_file(&sig_path) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        };
+    };
+
+    let file = SignatureFile {
+        signer: signer.signer_key().to_string(),
+        signature: signer.sign(context, data),
+        signed_at: Utc::now(),
+    };
+    fs::write(sig_path, serde_json::to_vec_pretty(&file)?)
+}
+
+/// 署名を検証
+///
+/// 署名者が `trusted` に含まれていれば `Trusted`、含まれていなければ `Untrusted` となる。
+pub fn check_signature(path: &Path, context: &str, trusted: &[SignerKey]) -> Result<SignatureStatus, RestoreError> {
+    let sig_path = signature_path(path);
+    if !sig_path.exists() {
+        return Ok(SignatureStatus::Unsigned);
+    }
+
+    let file: SignatureFile = match serde_json::from_slice(&fs::read(&sig_path)?) {
+        Ok(file) => file,
+        Err(_) => return Ok(SignatureStatus::Invalid),
+    };
+    let Ok(signer) = SignerKey::parse(&file.signer) else {
+        return Ok(SignatureStatus::Invalid);
+    };
+    if signer.verify(context, &fs::read(path)?, &file.signature).is_err() {
+        return Ok(SignatureStatus::Invalid);
+    }
+
+    let signer_name = signer.to_string();
+    Ok(if trusted.contains(&signer) {
+        SignatureStatus::Trusted { signer: signer_name }
+    } else {
+        SignatureStatus::Untrusted { signer: signer_name }
+    })
+}
+
+/// スナップショット（省略時は最新の manifest.json）の署名を検証
+pub fn check_snapshot_signature(
+    repo_dir: &Path,
+    snapshot: Option<&str>,
+    trusted: &[SignerKey],
+) -> Result<SignatureStatus, RestoreError> {
+    match snapshot {
+        Some(id) if !is_valid_snapshot_id(id) => Err(RestoreError::SnapshotNotFound(id.to_string())),
+        Some(id) => check_signature(
+            &repo_dir.join(SNAPSHOTS_DIR).join(format!("{}.json", id)),
+            &snapshot_context(id),
+            trusted,
+        ),
+        None => check_signature(&repo_dir.join(MANIFEST_FILE), MANIFEST_CONTEXT, trusted),
+    }
+}
+
+/// 信頼する鍵の一覧に照らして、スナップショットを使用してよいか確認
+///
+/// 署名が一致しない場合は常に拒否し、`trusted` を指定した場合は信頼する鍵による署名を必須とする。
+pub fn require_trusted_signature(
+    repo_dir: &Path,
+    snapshot: Option<&str>,
+    trusted: &[SignerKey],
+) -> Result<SignatureStatus, RestoreError> {
+    let name = snapshot.unwrap_or(MANIFEST_FILE).to_string();
+    match check_snapshot_signature(repo_dir, snapshot, trusted)? {
+        SignatureStatus::Invalid => Err(RestoreError::SignatureInvalid(name)),
+        status if !trusted.is_empty() && !status.is_trusted() => Err(RestoreError::UntrustedSnapshot(name)),
+        status => Ok(status),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_signature_status() {
+        let repo = TempDir::new().unwrap();
+        let path = repo.path().join(MANIFEST_FILE);
+        let signer = SnapshotSigner::generate();
+        let trusted = [signer.signer_key()];
+
+        fs::write(&path, b"manifest").unwrap();
+        assert_eq!(check_snapshot_signature(repo.path(), None, &trusted).unwrap(), SignatureStatus::Unsigned);
+
+        write_signature(&path, b"manifest", MANIFEST_CONTEXT, Some(&signer)).unwrap();
+        assert!(check_snapshot_signature(repo.path(), None, &trusted).unwrap().is_trusted());
+        assert!(matches!(
+            check_snapshot_signature(repo.path(), None, &[]).unwrap(),
+            SignatureStatus::Untrusted { .. }
+        ));
+
+        // 署名後の書き換えは検出する
+        fs::write(&path, b"manifest!").unwrap();
+        assert_eq!(check_snapshot_signature(repo.path(), None, &trusted).unwrap(), SignatureStatus::Invalid);
+        assert!(matches!(
+            require_trusted_signature(repo.path(), None, &[]),
+            Err(RestoreError::SignatureInvalid(_))
+        ));
+
+        // 署名なしで上書きした場合は古い署名を残さない
+        write_signature(&path, b"manifest!", MANIFEST_CONTEXT, None).unwrap();
+        assert_eq!(require_trusted_signature(repo.path(), None, &[]).unwrap(), SignatureStatus::Unsigned);
+        assert!(matches!(
+            require_trusted_signature(repo.path(), None, &trusted),
+            Err(RestoreError::UntrustedSnapshot(_))
+        ));
+    }
+}
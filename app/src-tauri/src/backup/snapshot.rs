@@ -0,0 +1,155 @@
+//! スナップショット - バックアップ実行ごとの不変な記録
+//!
+//! 各バックアップは `snapshots/<ID>.json` に一度だけ書き込まれ、以後変更されない。
+//! `manifest.json` は最新スナップショットの写しとして差分計算や情報表示に使う。
+
+use super::{encode_manifest, read_manifest_file, snapshot_context, write_signature, BackupManifest, RestoreError};
+use crate::crypto::{Encryptor, SnapshotSigner};
+use chrono::{DateTime, Utc};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+
+/// スナップショットのディレクトリ名
+pub const SNAPSHOTS_DIR: &str = "snapshots";
+
+/// マニフェストに記録するスナップショット情報
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnapshotMeta {
+    /// スナップショットID
+    pub id: String,
+
+    /// 親スナップショットID（初回はNone）
+    pub parent: Option<String>,
+
+    /// 作成日時
+    pub timestamp: DateTime<Utc>,
+}
+
+/// スナップショット一覧の項目
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnapshotInfo {
+    /// スナップショットID
+    pub id: String,
+
+    /// 親スナップショットID
+    pub parent: Option<String>,
+
+    /// 作成日時
+    pub timestamp: DateTime<Utc>,
+
+    /// ソースディレクトリ
+    pub source_dir: String,
+
+    /// 総ファイル数
+    pub total_files: usize,
+
+    /// オリジナル合計サイズ
+    pub total_original_size: u64,
+}
+
+/// 新しいスナップショットIDを生成
+///
+/// 日時順に並ぶよう `YYYYMMDDTHHMMSSZ-<ランダム8桁>` の形式とする。
+pub fn new_snapshot_id(timestamp: DateTime<Utc>) -> String {
+    let suffix: u32 = rand::thread_rng().gen();
+    format!("{}-{:08x}", timestamp.format("%Y%m%dT%H%M%SZ"), suffix)
+}
+
+/// スナップショットIDとして妥当かチェック（パス区切りなどを含まない）
+pub(super) fn is_valid_snapshot_id(id: &str) -> bool {
+    !id.is_empty() && id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+}
+
+/// スナップショットを書き込み（既存のスナップショットは上書きしない）
+///
+/// 暗号化エンジンがあればマニフェストと同様に暗号化し、署名鍵があれば `<ID>.sig` に署名を書き込む。
+pub fn write_snapshot(
+    repo_dir: &Path,
+    manifest: &BackupManifest,
+    encryptor: Option<&Encryptor>,
+    signer: Option<&SnapshotSigner>,
+) -> std::io::Result<()> {
+    let meta = manifest.snapshot.as_ref().ok_or_else(|| {
+        std::io::Error::new(std::io::ErrorKind::InvalidInput, "スナップショット情報がありません")
+    })?;
+
+    let dir = repo_dir.join(SNAPSHOTS_DIR);
+    fs::create_dir_all(&dir)?;
+
+    let context = snapshot_context(&meta.id);
+    let data = encode_manifest(manifest, &context, encryptor)?;
+    let path = dir.join(format!("{}.json", meta.id));
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&path)?;
+    file.write_all(&data)?;
+    file.sync_all()?;
+    write_signature(&path, &data, &context, signer)
+}
+
+/// スナップショットを読み込み
+pub fn load_snapshot(repo_dir: &Path, id: &str, encryptor: Option<&Encryptor>) -> Result<BackupManifest, RestoreError> {
+    if !is_valid_snapshot_id(id) {
+        return Err(RestoreError::SnapshotNotFound(id.to_string()));
+    }
+
+    let path = repo_dir.join(SNAPSHOTS_DIR).join(format!("{}.json", id));
+    if !path.exists() {
+        return Err(RestoreError::SnapshotNotFound(id.to_string()));
+    }
+
+    read_manifest_file(repo_dir, &path, &snapshot_context(id), encryptor)
+}
+
+/// スナップショット一覧を取得（古い順）
+///
+/// 暗号化リポジトリでは復号のため `encryptor` が必要。
+/// v0.1.x で作成したリポジトリにはスナップショットがないため空になる。
+pub fn list_snapshots(repo_dir: &Path, encryptor: Option<&Encryptor>) -> Result<Vec<SnapshotInfo>, RestoreError> {
+    let dir = repo_dir.join(SNAPSHOTS_DIR);
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut snapshots = Vec::new();
+    for entry in fs::read_dir(&dir)? {
+        let path = entry?.path();
+        if path.extension().and_then(|e| e.to_str()) != Some("json") {
+            continue;
+        }
+
+        // ファイル名のIDをコンテキストとするため、別のスナップショットへの差し替えは復号に失敗する
+        let id = path.file_stem().and_then(|s| s.to_str()).unwrap_or_default();
+        let manifest = read_manifest_file(repo_dir, &path, &snapshot_context(id), encryptor)?;
+        if let Some(meta) = manifest.snapshot {
+            snapshots.push(SnapshotInfo {
+                id: meta.id,
+                parent: meta.parent,
+                timestamp: meta.timestamp,
+                source_dir: manifest.source_dir,
+                total_files: manifest.stats.total_files,
+                total_original_size: manifest.stats.total_original_size,
+            });
+        }
+    }
+
+    snapshots.sort_by(|a, b| a.timestamp.cmp(&b.timestamp).then_with(|| a.id.cmp(&b.id)));
+    Ok(snapshots)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snapshot_id_format() {
+        let id = new_snapshot_id(Utc::now());
+        assert!(is_valid_snapshot_id(&id));
+        assert!(!is_valid_snapshot_id("../manifest"));
+        assert!(!is_valid_snapshot_id(""));
+    }
+}
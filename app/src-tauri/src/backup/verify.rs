@@ -0,0 +1,405 @@
+//! 検証 - バックアップデータの整合性チェック
+//!
+//! マニフェストの各エントリについて、保存先の存在・認証タグ・内容のハッシュを確認する。
+//! あわせて、どのスナップショットからも参照されないチャンク・ファイルを報告する。
+
+use super::{
+    collect_references, copy_restored, list_snapshots, load_backup_manifest, load_snapshot,
+    check_snapshot_signature, open_file_blob, open_repository_key, unreferenced_files, BackupManifest, ChunkStore,
+    ManifestEntry, RepositoryKey, RestoreError, SignatureStatus, CHUNKS_DIR,
+};
+use crate::crypto::{Encryptor, SignerKey};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::io::Read;
+use std::path::Path;
+
+/// 検証モード
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum VerifyMode {
+    /// 保存先の存在のみ確認（データは読まない）
+    #[default]
+    Quick,
+    /// 全データを読み、復号・解凍してハッシュを照合
+    Full,
+}
+
+/// 検証で見つかった問題
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VerifyIssue {
+    /// ファイルの相対パス（参照されていないデータはリポジトリからの相対パス）
+    pub path: String,
+
+    /// 詳細
+    pub detail: String,
+}
+
+/// 検証結果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VerifyReport {
+    /// 検証モード
+    pub mode: VerifyMode,
+
+    /// 検証したスナップショットID（v0.1.x 形式のマニフェストはNone）
+    pub snapshot: Option<String>,
+
+    /// 検証したファイル数
+    pub checked_files: usize,
+
+    /// 内容を照合したバイト数（Full モードのみ）
+    pub verified_bytes: u64,
+
+    /// 保存先が見つからないファイル
+    pub missing: Vec<VerifyIssue>,
+
+    /// 認証・解凍・ハッシュ照合に失敗したファイル
+    pub corrupt: Vec<VerifyIssue>,
+
+    /// ハッシュが記録されておらず内容を照合できなかったファイル（Full モードのみ）
+    #[serde(default)]
+    pub unverified: Vec<VerifyIssue>,
+
+    /// どのスナップショットからも参照されていないデータ
+    pub orphaned: Vec<VerifyIssue>,
+
+    /// スナップショットの署名
+    pub signature: SignatureStatus,
+
+    /// 信頼する鍵による署名を必須としたか（信頼する鍵を指定した場合）
+    pub signature_required: bool,
+}
+
+impl VerifyReport {
+    /// 問題がなかったか（参照されていないデータは復元に影響しないため含めない）
+    pub fn is_ok(&self) -> bool {
+        self.missing.is_empty()
+            && self.corrupt.is_empty()
+            && self.unverified.is_empty()
+            && self.signature != SignatureStatus::Invalid
+            && (!self.signature_required || self.signature.is_trusted())
+    }
+}
+
+/// バックアップを検証
+///
+/// `snapshot` を省略した場合は最新（manifest.json）を検証する。
+/// 暗号化リポジトリ、および Full モードで暗号化されたファイルを検証する場合は鍵が必要。
+/// 鍵には復元と同じく、パスワード（回復フレーズ）・アイデンティティ・マスター鍵を指定できる。
+/// `trusted_signers` を指定した場合は、それらの鍵で署名されていなければ問題として扱う。
+pub fn verify_backup(
+    repo_dir: &Path,
+    snapshot: Option<&str>,
+    mode: VerifyMode,
+    key: Option<&RepositoryKey>,
+    trusted_signers: &[SignerKey],
+) -> Result<VerifyReport, RestoreError> {
+    let encryptor = open_repository_key(repo_dir, key)?;
+    let encryptor = encryptor.as_ref();
+
+    let latest = load_backup_manifest(&repo_dir.to_path_buf(), encryptor)?;
+    let manifest = match snapshot {
+        Some(id) => load_snapshot(repo_dir, id, encryptor)?,
+        None => latest.clone(),
+    };
+
+    if mode == VerifyMode::Full && encryptor.is_none() {
+        if let Some(entry) = manifest.files.values().find(|e| e.encrypted) {
+            return Err(RestoreError::KeyRequired(entry.path.clone()));
+        }
+    }
+
+    let signature = check_snapshot_signature(repo_dir, snapshot, trusted_signers)?;
+
+    let store = ChunkStore::new(repo_dir, false, encryptor);
+    let mut report = VerifyReport {
+        mode,
+        snapshot: manifest.snapshot.as_ref().map(|s| s.id.clone()),
+        checked_files: 0,
+        verified_bytes: 0,
+        missing: Vec::new(),
+        corrupt: Vec::new(),
+        unverified: Vec::new(),
+        orphaned: Vec::new(),
+        signature,
+        signature_required: !trusted_signers.is_empty(),
+    };
+
+    let mut entries: Vec<&ManifestEntry> = manifest.files.values().collect();
+    entries.sort_by(|a, b| a.path.cmp(&b.path));
+
+    for entry in entries {
+        report.checked_files += 1;
+
+        if let Some(detail) = find_missing(repo_dir, &store, entry) {
+            report.missing.push(VerifyIssue {
+                path: entry.path.clone(),
+                detail,
+            });
+            continue;
+        }
+
+        if mode == VerifyMode::Full {
+            match verify_content(repo_dir, &store, entry, &manifest, encryptor) {
+                Ok(true) => report.verified_bytes += entry.original_size,
+                Ok(false) => report.unverified.push(VerifyIssue {
+                    path: entry.path.clone(),
+                    detail: "ハッシュが記録されていないため内容を照合できません".to_string(),
+                }),
+                Err(detail) => report.corrupt.push(VerifyIssue {
+                    path: entry.path.clone(),
+                    detail,
+                }),
+            }
+        }
+    }
+
+    // 参照されていないデータを検出（残っているすべてのスナップショットを対象とする）
+    let mut referenced = HashSet::new();
+    collect_references(repo_dir, &store, &latest, &mut referenced)?;
+    for info in list_snapshots(repo_dir, encryptor)? {
+        collect_references(repo_dir, &store, &load_snapshot(repo_dir, &info.id, encryptor)?, &mut referenced)?;
+    }
+    for dir in [CHUNKS_DIR, "data"] {
+        for (path, _) in unreferenced_files(&repo_dir.join(dir), &referenced)? {
+            report.orphaned.push(VerifyIssue {
+                path: path.strip_prefix(repo_dir).unwrap_or(&path).to_string_lossy().to_string(),
+                detail: "どのスナップショットからも参照されていません".to_string(),
+            });
+        }
+    }
+
+    Ok(report)
+}
+
+/// 保存先の存在を確認
+///
+/// # Returns
+/// 見つからない場合はその内容
+fn find_missing(repo_dir: &Path, store: &ChunkStore, entry: &ManifestEntry) -> Option<String> {
+    match &entry.chunks {
+        Some(chunks) => chunks.iter()
+            .find(|id| !store.chunk_path(id).is_ok_and(|path| path.is_file()))
+            .map(|id| format!("チャンクが見つかりません: {}", id)),
+        None => {
+            let blob = match entry.blob_path() {
+                Ok(blob) => blob,
+                Err(e) => return Some(e.to_string()),
+            };
+            (!repo_dir.join(&blob).is_file())
+                .then(|| format!("バックアップファイルが見つかりません: {}", blob))
+        }
+    }
+}
+
+/// 内容を復号・解凍してハッシュを照合
+///
+/// # Returns
+/// ハッシュを照合できたか（記録がなければfalse）。失敗した場合はその内容
+fn verify_content(
+    repo_dir: &Path,
+    store: &ChunkStore,
+    entry: &ManifestEntry,
+    manifest: &BackupManifest,
+    encryptor: Option<&Encryptor>,
+) -> Result<bool, String> {
+    let describe = |e: RestoreError| match e {
+        RestoreError::BlobCorrupted(_) => "認証に失敗しました（改ざんまたは破損）".to_string(),
+        e => e.to_string(),
+    };
+
+    let (mut reader, compressed): (Box<dyn Read + '_>, bool) = match &entry.chunks {
+        Some(chunks) => (Box::new(store.reader(chunks)), entry.compressed),
+        None => open_file_blob(repo_dir, entry, manifest, encryptor).map_err(describe)?,
+    };
+
+    let mut hasher = manifest.content_hasher(encryptor);
+    let size = copy_restored(&mut reader, &mut hasher, compressed, &entry.path).map_err(describe)?;
+
+    if size != entry.original_size {
+        return Err(format!("サイズが一致しません（記録: {}、実際: {}）", entry.original_size, size));
+    }
+    if entry.hash.is_empty() {
+        return Ok(false);
+    }
+    if hasher.finalize().to_hex().as_str() != entry.hash {
+        return Err("ハッシュが一致しません".to_string());
+    }
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::backup::{BackupConfig, BackupExecutor, MANIFEST_FILE};
+    use crate::crypto::SnapshotSigner;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn backup(source: &Path, dest: &Path, compress: bool, password: Option<&str>) {
+        let config = BackupConfig {
+            source_dir: source.to_path_buf(),
+            dest_dir: dest.to_path_buf(),
+            compress,
+            ..BackupConfig::default()
+        };
+        let mut executor = BackupExecutor::new(config);
+        if let Some(password) = password {
+            executor = executor.with_encryption(password);
+        }
+        assert!(executor.execute().unwrap().success);
+    }
+
+    /// 最初に見つかったチャンクファイルの末尾1バイトを書き換える
+    fn corrupt_first_chunk(dest: &Path) {
+        let path = walkdir::WalkDir::new(dest.join(CHUNKS_DIR))
+            .into_iter()
+            .flatten()
+            .find(|e| e.file_type().is_file())
+            .unwrap()
+            .into_path();
+        let mut data = fs::read(&path).unwrap();
+        *data.last_mut().unwrap() ^= 0x01;
+        fs::write(&path, data).unwrap();
+    }
+
+    #[test]
+    fn test_verify_quick_missing_and_orphaned() {
+        let source = TempDir::new().unwrap();
+        let dest = TempDir::new().unwrap();
+        fs::write(source.path().join("a.txt"), "alpha").unwrap();
+        fs::write(source.path().join("b.txt"), "bravo").unwrap();
+        backup(source.path(), dest.path(), true, None);
+
+        let report = verify_backup(dest.path(), None, VerifyMode::Quick, None, &[]).unwrap();
+        assert!(report.is_ok());
+        assert_eq!(report.checked_files, 2);
+        assert!(report.orphaned.is_empty());
+
+        // チャンクを1つ削除し、参照されないファイルを追加
+        let manifest = super::load_backup_manifest(&dest.path().to_path_buf(), None).unwrap();
+        let store = ChunkStore::new(dest.path(), false, None);
+        fs::remove_file(store.chunk_path(&manifest.files["a.txt"].chunks.as_ref().unwrap()[0]).unwrap()).unwrap();
+        fs::create_dir_all(dest.path().join("data")).unwrap();
+        fs::write(dest.path().join("data").join("stray"), "x").unwrap();
+
+        let report = verify_backup(dest.path(), None, VerifyMode::Quick, None, &[]).unwrap();
+        assert!(!report.is_ok());
+        assert_eq!(report.missing.len(), 1);
+        assert_eq!(report.missing[0].path, "a.txt");
+        assert_eq!(report.orphaned.len(), 1);
+    }
+
+    #[test]
+    fn test_verify_full_detects_corruption() {
+        // 暗号化なし・圧縮なしの破損は解凍でも検出されないため、ハッシュ照合で検出する
+        let source = TempDir::new().unwrap();
+        let dest = TempDir::new().unwrap();
+        fs::write(source.path().join("plain.txt"), "plain data").unwrap();
+        backup(source.path(), dest.path(), false, None);
+
+        assert!(verify_backup(dest.path(), None, VerifyMode::Full, None, &[]).unwrap().is_ok());
+        corrupt_first_chunk(dest.path());
+
+        let report = verify_backup(dest.path(), None, VerifyMode::Full, None, &[]).unwrap();
+        assert_eq!(report.corrupt.len(), 1);
+        assert!(report.corrupt[0].detail.contains("ハッシュ"));
+
+        // 暗号化されたデータの改ざんは認証タグで検出する
+        let source = TempDir::new().unwrap();
+        let dest = TempDir::new().unwrap();
+        fs::write(source.path().join("secret.txt"), "secret data").unwrap();
+        backup(source.path(), dest.path(), true, Some("test_password_123"));
+
+        let key = RepositoryKey::Password("test_password_123".into());
+        let password = Some(&key);
+        assert!(verify_backup(dest.path(), None, VerifyMode::Full, password, &[]).unwrap().is_ok());
+        corrupt_first_chunk(dest.path());
+
+        let report = verify_backup(dest.path(), None, VerifyMode::Full, password, &[]).unwrap();
+        assert_eq!(report.corrupt.len(), 1);
+        assert!(report.corrupt[0].detail.contains("認証"));
+    }
+
+    #[test]
+    fn test_verify_with_identity_and_master_key() {
+        use crate::backup::require_keyfile;
+        use crate::crypto::{ContentKey, Identity};
+
+        // 受信者方式のリポジトリはアイデンティティで検証できる
+        let source = TempDir::new().unwrap();
+        let dest = TempDir::new().unwrap();
+        fs::write(source.path().join("secret.txt"), "secret data").unwrap();
+        let identity = Identity::generate();
+        let config = BackupConfig {
+            source_dir: source.path().to_path_buf(),
+            dest_dir: dest.path().to_path_buf(),
+            ..BackupConfig::default()
+        };
+        BackupExecutor::new(config)
+            .with_recipients(vec![identity.recipient()])
+            .with_content_key(ContentKey::generate())
+            .execute()
+            .unwrap();
+
+        let key = RepositoryKey::Identity(identity);
+        assert!(verify_backup(dest.path(), None, VerifyMode::Full, Some(&key), &[]).unwrap().is_ok());
+        let other = RepositoryKey::Identity(Identity::generate());
+        assert!(matches!(
+            verify_backup(dest.path(), None, VerifyMode::Full, Some(&other), &[]),
+            Err(RestoreError::WrongPassword)
+        ));
+
+        // パスワード方式のリポジトリは鍵の断片から復元したマスター鍵でも検証できる
+        let dest = TempDir::new().unwrap();
+        backup(source.path(), dest.path(), true, Some("test_password_123"));
+        let master = require_keyfile(dest.path()).unwrap().unlock("test_password_123").unwrap();
+        let key = RepositoryKey::MasterKey(master);
+        assert!(verify_backup(dest.path(), None, VerifyMode::Full, Some(&key), &[]).unwrap().is_ok());
+    }
+
+    #[test]
+    fn test_verify_full_reports_missing_hash() {
+        let source = TempDir::new().unwrap();
+        let dest = TempDir::new().unwrap();
+        fs::write(source.path().join("a.txt"), "alpha").unwrap();
+        backup(source.path(), dest.path(), false, None);
+
+        // ハッシュのないエントリは照合済みとして扱わない
+        let manifest_path = dest.path().join(MANIFEST_FILE);
+        let mut manifest: serde_json::Value = serde_json::from_slice(&fs::read(&manifest_path).unwrap()).unwrap();
+        manifest["files"]["a.txt"]["hash"] = "".into();
+        fs::write(&manifest_path, serde_json::to_vec(&manifest).unwrap()).unwrap();
+
+        let report = verify_backup(dest.path(), None, VerifyMode::Full, None, &[]).unwrap();
+        assert!(!report.is_ok());
+        assert!(report.corrupt.is_empty());
+        assert_eq!(report.unverified.len(), 1);
+        assert_eq!(report.unverified[0].path, "a.txt");
+        assert_eq!(report.verified_bytes, 0);
+    }
+
+    #[test]
+    fn test_verify_signature() {
+        let source = TempDir::new().unwrap();
+        let dest = TempDir::new().unwrap();
+        fs::write(source.path().join("a.txt"), "alpha").unwrap();
+
+        // 署名なしのバックアップは、信頼する鍵を指定した場合のみ問題とする
+        backup(source.path(), dest.path(), false, None);
+        let signer = SnapshotSigner::generate();
+        let report = verify_backup(dest.path(), None, VerifyMode::Quick, None, &[]).unwrap();
+        assert_eq!(report.signature, SignatureStatus::Unsigned);
+        assert!(report.is_ok());
+        assert!(!verify_backup(dest.path(), None, VerifyMode::Quick, None, &[signer.signer_key()]).unwrap().is_ok());
+
+        let config = BackupConfig {
+            source_dir: source.path().to_path_buf(),
+            dest_dir: dest.path().to_path_buf(),
+            ..BackupConfig::default()
+        };
+        BackupExecutor::new(config).with_signer(signer.clone()).execute().unwrap();
+        let report = verify_backup(dest.path(), None, VerifyMode::Quick, None, &[signer.signer_key()]).unwrap();
+        assert!(report.signature.is_trusted());
+        assert!(report.is_ok());
+    }
+}
@@ -3,12 +3,22 @@
 use crate::backup::{
     BackupConfig, BackupExecutor, BackupProgress, DirectoryScanner, ScanResult,
     RestoreConfig, RestoreExecutor, RestoreProgress, load_backup_manifest, BackupInfo,
+    require_keyfile, save_keyfile, open_encryptor, list_snapshots as list_repository_snapshots, SnapshotInfo,
+    prune, PruneReport, RetentionPolicy, verify_backup as verify_repository, VerifyMode, VerifyReport,
+    check_snapshot_signature, ExcludedEntry, IgnoreFileReport, RepositoryKey, RestoreError, ScanFilters,
 };
-use crate::crypto::{Encryptor, PasswordStrength};
+use crate::crypto::{
+    combine_key_shares, estimate_password, split_master_key, CipherSuite, ContentKey, Encryptor, Identity, KdfParams,
+    KeyShare, PasswordStrength, Recipient, SignerKey, SnapshotSigner,
+};
+use secrecy::{ExposeSecret, SecretString};
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
 use tauri::State;
+use zeroize::Zeroizing;
 
 /// アプリケーション状態
 pub struct AppState {
@@ -37,6 +47,12 @@ impl Default for AppState {
 pub struct ScanRequest {
     pub path: String,
     pub compute_hash: bool,
+    /// 絞り込み条件（省略時は既定の除外パターンのみ）
+    #[serde(default)]
+    pub filters: Option<ScanFilters>,
+    /// 条件判定・ハッシュ計算のスレッド数（0 または省略時は論理CPU数）
+    #[serde(default)]
+    pub scan_threads: usize,
 }
 
 /// スキャンレスポンス
@@ -45,6 +61,8 @@ pub struct ScanResponse {
     pub success: bool,
     pub total_files: usize,
     pub total_size: u64,
+    /// 除外ファイルごとの除外件数
+    pub ignore_files: Vec<IgnoreFileReport>,
     pub error: Option<String>,
 }
 
@@ -54,22 +72,72 @@ pub struct BackupRequest {
     pub source_dir: String,
     pub dest_dir: String,
     pub encrypt: bool,
-    pub password: Option<String>,
+    pub password: Option<SecretString>,
     pub compress: bool,
     pub incremental: bool,
+    /// 鍵導出パラメータ（省略時は既定値）
+    #[serde(default)]
+    pub kdf: Option<KdfParams>,
+    /// 暗号スイート（新規リポジトリのみ、省略時は既存の設定または AES-256-GCM）
+    #[serde(default)]
+    pub cipher: Option<CipherSuite>,
+    /// 受信者の公開鍵（`sbr1...`、指定時はパスワードの代わりに使う）
+    #[serde(default)]
+    pub recipients: Vec<String>,
+    /// 内容ID用の鍵ファイルのパス（受信者方式でチャンク分割する場合は必須）
+    #[serde(default)]
+    pub content_key_file: Option<String>,
+    /// チャンク分割による重複排除（省略時は有効）
+    #[serde(default)]
+    pub chunking: Option<bool>,
+    /// 暗号化時にファイル単位の保存先の名前を秘匿するか（省略時は無効）
+    #[serde(default)]
+    pub opaque_names: Option<bool>,
+    /// 保持ポリシー（省略時は整理しない）
+    #[serde(default)]
+    pub retention: Option<RetentionPolicy>,
+    /// スナップショットの署名鍵ファイルのパス（省略時は署名しない）
+    #[serde(default)]
+    pub signing_key_file: Option<String>,
+    /// 絞り込み条件（省略時はバックアップ先に記録された前回の条件、初回は既定の除外パターンのみ。記録を読めなければエラー）
+    #[serde(default)]
+    pub filters: Option<ScanFilters>,
+    /// スキャン・ハッシュ計算のスレッド数（0 または省略時は論理CPU数）
+    #[serde(default)]
+    pub scan_threads: usize,
 }
 
 /// バックアップレスポンス
 #[derive(Debug, Serialize)]
 pub struct BackupResponse {
     pub success: bool,
+    pub snapshot_id: Option<String>,
     pub backed_up_files: usize,
     pub backed_up_bytes: u64,
     pub skipped_files: usize,
+    pub pruned_snapshots: usize,
     pub duration_secs: f64,
+    /// 暗号化リポジトリを新規作成した場合の回復フレーズ（一度だけ表示する）
+    pub recovery_phrase: Option<String>,
     pub error: Option<String>,
 }
 
+impl BackupResponse {
+    fn failed(error: String) -> Self {
+        Self {
+            success: false,
+            snapshot_id: None,
+            backed_up_files: 0,
+            backed_up_bytes: 0,
+            skipped_files: 0,
+            pruned_snapshots: 0,
+            duration_secs: 0.0,
+            recovery_phrase: None,
+            error: Some(error),
+        }
+    }
+}
+
 /// 進捗レスポンス
 #[derive(Debug, Serialize)]
 pub struct ProgressResponse {
@@ -87,7 +155,16 @@ pub struct ProgressResponse {
 #[derive(Debug, Serialize)]
 pub struct PasswordCheckResponse {
     pub strength: String,
+    /// 3段階の強度（1〜3）
     pub score: u8,
+    /// 推定推測回数の常用対数
+    pub guesses_log10: f64,
+    /// 解読に要する推定時間（秒）
+    pub crack_time_secs: f64,
+    /// 解読に要する推定時間（表示用）
+    pub crack_time: String,
+    /// 主な弱点
+    pub warning: Option<String>,
     pub suggestions: Vec<String>,
 }
 
@@ -99,7 +176,9 @@ pub async fn scan_directory(
 ) -> Result<ScanResponse, String> {
     let path = PathBuf::from(&request.path);
 
-    let mut scanner = DirectoryScanner::new(&path);
+    let mut scanner = DirectoryScanner::new(&path)
+        .with_filters(request.filters.unwrap_or_default())
+        .with_threads(request.scan_threads);
     if request.compute_hash {
         scanner = scanner.with_hash();
     }
@@ -110,6 +189,7 @@ pub async fn scan_directory(
                 success: true,
                 total_files: result.total_files,
                 total_size: result.total_size,
+                ignore_files: result.ignore_files.clone(),
                 error: None,
             };
 
@@ -122,50 +202,142 @@ pub async fn scan_directory(
             success: false,
             total_files: 0,
             total_size: 0,
+            ignore_files: vec![],
+            error: Some(e.to_string()),
+        }),
+    }
+}
+
+/// 除外プレビューレスポンス
+#[derive(Debug, Serialize)]
+pub struct ExclusionPreviewResponse {
+    pub success: bool,
+    /// バックアップ対象となるファイル数
+    pub total_files: usize,
+    pub total_size: u64,
+    /// 除外するエントリと理由
+    pub excluded: Vec<ExcludedEntry>,
+    /// 除外ファイルごとの除外件数
+    pub ignore_files: Vec<IgnoreFileReport>,
+    pub error: Option<String>,
+}
+
+/// 絞り込み条件で除外されるファイル・ディレクトリを一覧（ハッシュは計算しない）
+#[tauri::command]
+pub async fn preview_exclusions(request: ScanRequest) -> Result<ExclusionPreviewResponse, String> {
+    let result = DirectoryScanner::new(&request.path)
+        .with_filters(request.filters.unwrap_or_default())
+        .with_threads(request.scan_threads)
+        .scan();
+
+    match result {
+        Ok(result) => Ok(ExclusionPreviewResponse {
+            success: true,
+            total_files: result.total_files,
+            total_size: result.total_size,
+            excluded: result.excluded,
+            ignore_files: result.ignore_files,
+            error: None,
+        }),
+        Err(e) => Ok(ExclusionPreviewResponse {
+            success: false,
+            total_files: 0,
+            total_size: 0,
+            excluded: vec![],
+            ignore_files: vec![],
             error: Some(e.to_string()),
         }),
     }
 }
 
+/// バックアップ先に記録された前回の絞り込み条件（初回のバックアップはNone）
+///
+/// マニフェストがあるのに読めない場合（パスワード違い・受信者方式など）は、
+/// 既定の条件で黙ってバックアップしないようエラーとする。
+fn saved_filters(dest_dir: &Path, password: Option<&SecretString>) -> Result<Option<ScanFilters>, String> {
+    let unreadable = |e: String| format!("前回の絞り込み条件を読み込めません（絞り込み条件を指定してください）: {}", e);
+    let encryptor = open_repository(dest_dir, password).map_err(unreadable)?;
+    match load_backup_manifest(&dest_dir.to_path_buf(), encryptor.as_ref()) {
+        Ok(manifest) => Ok(manifest.config.filters),
+        Err(RestoreError::ManifestNotFound(_)) => Ok(None),
+        Err(e) => Err(unreadable(e.to_string())),
+    }
+}
+
 /// バックアップを実行
 #[tauri::command]
 pub async fn execute_backup(
     request: BackupRequest,
     state: State<'_, AppState>,
 ) -> Result<BackupResponse, String> {
+    let dest_dir = PathBuf::from(&request.dest_dir);
+    let filters = match request.filters {
+        Some(filters) => filters,
+        None => match saved_filters(&dest_dir, request.password.as_ref()) {
+            Ok(filters) => filters.unwrap_or_default(),
+            Err(e) => return Ok(BackupResponse::failed(e)),
+        },
+    };
+
     let config = BackupConfig {
         source_dir: PathBuf::from(&request.source_dir),
-        dest_dir: PathBuf::from(&request.dest_dir),
+        dest_dir,
         encrypt: request.encrypt,
         compress: request.compress,
         incremental: request.incremental,
-        exclude_patterns: vec![
-            ".git".to_string(),
-            "node_modules".to_string(),
-            "target".to_string(),
-        ],
+        filters,
+        kdf: request.kdf.unwrap_or_default(),
+        cipher: request.cipher,
+        chunking: request.chunking.unwrap_or(true),
+        opaque_names: request.opaque_names.unwrap_or(false),
+        retention: request.retention.unwrap_or_default(),
+        scan_threads: request.scan_threads,
     };
 
     let progress_state = state.progress.clone();
 
     let mut executor = BackupExecutor::new(config);
 
-    // 暗号化が有効な場合
+    // 暗号化が有効な場合（受信者の公開鍵が指定されていればパスワードより優先）
     if request.encrypt {
-        if let Some(password) = &request.password {
-            executor = executor.with_encryption(password);
+        if !request.recipients.is_empty() {
+            let recipients = match request.recipients.iter().map(|r| Recipient::parse(r)).collect() {
+                Ok(recipients) => recipients,
+                Err(e) => return Ok(BackupResponse::failed(e.to_string())),
+            };
+            executor = executor.with_recipients(recipients);
+
+            if let Some(content_key_file) = &request.content_key_file {
+                let content_key = match fs::read_to_string(content_key_file)
+                    .map(Zeroizing::new)
+                    .map_err(|e| e.to_string())
+                    .and_then(|content| ContentKey::parse(&content).map_err(|e| e.to_string()))
+                {
+                    Ok(content_key) => content_key,
+                    Err(e) => return Ok(BackupResponse::failed(e)),
+                };
+                executor = executor.with_content_key(content_key);
+            }
+        } else if let Some(password) = &request.password {
+            executor = executor.with_encryption(password.clone());
         } else {
-            return Ok(BackupResponse {
-                success: false,
-                backed_up_files: 0,
-                backed_up_bytes: 0,
-                skipped_files: 0,
-                duration_secs: 0.0,
-                error: Some("暗号化にはパスワードが必要です".to_string()),
-            });
+            return Ok(BackupResponse::failed("暗号化にはパスワードが必要です".to_string()));
         }
     }
 
+    // 署名鍵が指定されている場合
+    if let Some(signing_key_file) = &request.signing_key_file {
+        let signer = match fs::read_to_string(signing_key_file)
+            .map(Zeroizing::new)
+            .map_err(|e| e.to_string())
+            .and_then(|content| SnapshotSigner::parse(&content).map_err(|e| e.to_string()))
+        {
+            Ok(signer) => signer,
+            Err(e) => return Ok(BackupResponse::failed(e)),
+        };
+        executor = executor.with_signer(signer);
+    }
+
     // 進捗コールバックを設定
     executor = executor.with_progress_callback(move |progress| {
         *progress_state.lock().unwrap() = Some(progress);
@@ -182,10 +354,13 @@ pub async fn execute_backup(
 
             Ok(BackupResponse {
                 success: result.success,
+                snapshot_id: Some(result.snapshot_id),
                 backed_up_files: result.backed_up_files,
                 backed_up_bytes: result.backed_up_bytes,
                 skipped_files: result.skipped_files,
+                pruned_snapshots: result.pruned_snapshots,
                 duration_secs: duration,
+                recovery_phrase: result.recovery_phrase,
                 error: if result.failed_files.is_empty() {
                     None
                 } else {
@@ -198,10 +373,13 @@ pub async fn execute_backup(
 
             Ok(BackupResponse {
                 success: false,
+                snapshot_id: None,
                 backed_up_files: 0,
                 backed_up_bytes: 0,
                 skipped_files: 0,
+                pruned_snapshots: 0,
                 duration_secs: start.elapsed().as_secs_f64(),
+                recovery_phrase: None,
                 error: Some(e.to_string()),
             })
         }
@@ -246,41 +424,26 @@ pub fn get_progress(state: State<'_, AppState>) -> ProgressResponse {
 }
 
 /// パスワード強度をチェック
+///
+/// 辞書の単語・キーボードの並び・繰り返し・日付などのパターンから推測回数と解読時間を見積もる。
 #[tauri::command]
-pub fn check_password(password: String) -> PasswordCheckResponse {
-    let strength = Encryptor::check_password_strength(&password);
+pub fn check_password(password: SecretString) -> PasswordCheckResponse {
+    let estimate = estimate_password(password.expose_secret());
 
-    let (strength_str, score) = match strength {
+    let (strength_str, score) = match estimate.strength() {
         PasswordStrength::Weak => ("弱い", 1),
         PasswordStrength::Medium => ("中程度", 2),
         PasswordStrength::Strong => ("強い", 3),
     };
 
-    let mut suggestions = Vec::new();
-
-    if password.len() < 8 {
-        suggestions.push("8文字以上にしてください".to_string());
-    }
-    if password.len() < 12 {
-        suggestions.push("12文字以上を推奨".to_string());
-    }
-    if !password.chars().any(|c| c.is_uppercase()) {
-        suggestions.push("大文字を含めてください".to_string());
-    }
-    if !password.chars().any(|c| c.is_lowercase()) {
-        suggestions.push("小文字を含めてください".to_string());
-    }
-    if !password.chars().any(|c| c.is_numeric()) {
-        suggestions.push("数字を含めてください".to_string());
-    }
-    if !password.chars().any(|c| !c.is_alphanumeric()) {
-        suggestions.push("記号を含めてください".to_string());
-    }
-
     PasswordCheckResponse {
         strength: strength_str.to_string(),
         score,
-        suggestions,
+        guesses_log10: estimate.guesses_log10,
+        crack_time_secs: estimate.crack_time_secs,
+        crack_time: estimate.crack_time_display(),
+        warning: estimate.warning,
+        suggestions: estimate.suggestions,
     }
 }
 
@@ -321,11 +484,35 @@ pub struct RestoreRequest {
     /// 復元するファイル（空の場合は全ファイル）
     pub files: Vec<String>,
 
-    /// パスワード（暗号化バックアップの場合）
-    pub password: Option<String>,
+    /// パスワードまたは回復フレーズ（暗号化バックアップの場合）
+    pub password: Option<SecretString>,
+
+    /// アイデンティティファイルのパス（受信者暗号化バックアップの場合）
+    #[serde(default)]
+    pub identity_file: Option<String>,
+
+    /// 鍵の断片（文字列、パスワードの代わりに必要数を組み合わせる）
+    #[serde(default)]
+    pub key_shares: Vec<SecretString>,
+
+    /// 鍵の断片ファイルのパス
+    #[serde(default)]
+    pub key_share_files: Vec<String>,
 
     /// 既存ファイルを上書きするか
     pub overwrite: bool,
+
+    /// 復元するスナップショットID（省略時は最新）
+    #[serde(default)]
+    pub snapshot: Option<String>,
+
+    /// ハッシュ不一致で直ちに中止するか
+    #[serde(default)]
+    pub strict: bool,
+
+    /// 信頼する署名者の公開鍵（`sbs1...`、指定時はこれらの鍵で署名されたスナップショットのみ復元する）
+    #[serde(default)]
+    pub trusted_signers: Vec<String>,
 }
 
 /// 復元レスポンス
@@ -335,6 +522,9 @@ pub struct RestoreResponse {
     pub restored_files: usize,
     pub restored_bytes: u64,
     pub skipped_files: usize,
+    pub hash_mismatches: usize,
+    /// ハッシュが記録されておらず検証できなかったファイル数
+    pub unverified_files: usize,
     pub duration_secs: f64,
     pub error: Option<String>,
 }
@@ -358,14 +548,38 @@ pub struct BackupFileInfo {
     pub modified: String,
 }
 
+/// パスワードが指定されていればリポジトリの暗号化エンジンを開く
+fn open_repository(backup_dir: &Path, password: Option<&SecretString>) -> Result<Option<Encryptor>, String> {
+    password
+        .map(|password| open_encryptor(backup_dir, password.expose_secret()).map_err(|e| e.to_string()))
+        .transpose()
+}
+
 /// バックアップ情報を取得
+///
+/// 暗号化リポジトリのマニフェストは暗号化されているため、パスワードが必要。
+/// 最新スナップショットの署名を `trusted_signers`（`sbs1...`）と照合した結果もあわせて返す。
 #[tauri::command]
-pub async fn get_backup_info(backup_dir: String) -> Result<BackupInfoResponse, String> {
+pub async fn get_backup_info(
+    backup_dir: String,
+    password: Option<SecretString>,
+    trusted_signers: Option<Vec<String>>,
+) -> Result<BackupInfoResponse, String> {
     let path = PathBuf::from(&backup_dir);
 
-    match load_backup_manifest(&path) {
-        Ok(manifest) => {
-            let info = BackupInfo::from(&manifest);
+    let manifest = parse_signers(&trusted_signers.unwrap_or_default()).and_then(|trusted| {
+        let signature = check_snapshot_signature(&path, None, &trusted).map_err(|e| e.to_string())?;
+        let encryptor = open_repository(&path, password.as_ref())?;
+        let manifest = load_backup_manifest(&path, encryptor.as_ref()).map_err(|e| e.to_string())?;
+        Ok((manifest, signature))
+    });
+
+    match manifest {
+        Ok((manifest, signature)) => {
+            let info = BackupInfo {
+                signature: Some(signature),
+                ..BackupInfo::from(&manifest)
+            };
 
             let files: Vec<BackupFileInfo> = manifest.files.values()
                 .map(|entry| BackupFileInfo {
@@ -388,7 +602,161 @@ pub async fn get_backup_info(backup_dir: String) -> Result<BackupInfoResponse, S
             success: false,
             info: None,
             files: vec![],
-            error: Some(e.to_string()),
+            error: Some(e),
+        }),
+    }
+}
+
+/// スナップショット一覧レスポンス
+#[derive(Debug, Serialize)]
+pub struct SnapshotListResponse {
+    pub success: bool,
+    pub snapshots: Vec<SnapshotInfo>,
+    pub error: Option<String>,
+}
+
+/// スナップショット一覧を取得（古い順）
+#[tauri::command]
+pub async fn list_snapshots(backup_dir: String, password: Option<SecretString>) -> Result<SnapshotListResponse, String> {
+    let path = PathBuf::from(&backup_dir);
+
+    let snapshots = open_repository(&path, password.as_ref())
+        .and_then(|encryptor| list_repository_snapshots(&path, encryptor.as_ref()).map_err(|e| e.to_string()));
+
+    match snapshots {
+        Ok(snapshots) => Ok(SnapshotListResponse {
+            success: true,
+            snapshots,
+            error: None,
+        }),
+        Err(e) => Ok(SnapshotListResponse {
+            success: false,
+            snapshots: vec![],
+            error: Some(e),
+        }),
+    }
+}
+
+/// 整理リクエスト
+#[derive(Debug, Deserialize)]
+pub struct PruneRequest {
+    /// バックアップディレクトリ
+    pub backup_dir: String,
+
+    /// 保持ポリシー
+    pub policy: RetentionPolicy,
+
+    /// 試行のみ（削除対象を報告するだけ）
+    #[serde(default)]
+    pub dry_run: bool,
+
+    /// パスワード（暗号化バックアップの場合）
+    pub password: Option<SecretString>,
+}
+
+/// 整理レスポンス
+#[derive(Debug, Serialize)]
+pub struct PruneResponse {
+    pub success: bool,
+    pub report: Option<PruneReport>,
+    pub error: Option<String>,
+}
+
+/// 保持ポリシーに従ってスナップショットを整理
+#[tauri::command]
+pub async fn prune_backup(request: PruneRequest) -> Result<PruneResponse, String> {
+    let path = PathBuf::from(&request.backup_dir);
+
+    let report = open_repository(&path, request.password.as_ref()).and_then(|encryptor| {
+        prune(&path, &request.policy, request.dry_run, encryptor.as_ref()).map_err(|e| e.to_string())
+    });
+
+    match report {
+        Ok(report) => Ok(PruneResponse {
+            success: true,
+            report: Some(report),
+            error: None,
+        }),
+        Err(e) => Ok(PruneResponse {
+            success: false,
+            report: None,
+            error: Some(e),
+        }),
+    }
+}
+
+/// 検証リクエスト
+#[derive(Debug, Deserialize)]
+pub struct VerifyRequest {
+    /// バックアップディレクトリ
+    pub backup_dir: String,
+
+    /// 検証するスナップショットID（省略時は最新）
+    #[serde(default)]
+    pub snapshot: Option<String>,
+
+    /// 検証モード（省略時は Quick）
+    #[serde(default)]
+    pub mode: VerifyMode,
+
+    /// パスワードまたは回復フレーズ（暗号化バックアップの場合）
+    pub password: Option<SecretString>,
+
+    /// アイデンティティファイルのパス（受信者暗号化バックアップの場合）
+    #[serde(default)]
+    pub identity_file: Option<String>,
+
+    /// 鍵の断片（文字列、パスワードの代わりに必要数を組み合わせる）
+    #[serde(default)]
+    pub key_shares: Vec<SecretString>,
+
+    /// 鍵の断片ファイルのパス
+    #[serde(default)]
+    pub key_share_files: Vec<String>,
+
+    /// 信頼する署名者の公開鍵（`sbs1...`、指定時はこれらの鍵による署名を必須とする）
+    #[serde(default)]
+    pub trusted_signers: Vec<String>,
+}
+
+/// 検証レスポンス
+#[derive(Debug, Serialize)]
+pub struct VerifyResponse {
+    pub success: bool,
+    pub report: Option<VerifyReport>,
+    pub error: Option<String>,
+}
+
+/// バックアップの整合性を検証
+#[tauri::command]
+pub async fn verify_backup(request: VerifyRequest) -> Result<VerifyResponse, String> {
+    let result = parse_signers(&request.trusted_signers).and_then(|trusted| {
+        let key = read_repository_key(
+            request.identity_file.as_deref(),
+            &request.key_shares,
+            &request.key_share_files,
+            request.password.as_ref(),
+        )?;
+        verify_repository(
+            &PathBuf::from(&request.backup_dir),
+            request.snapshot.as_deref(),
+            request.mode,
+            key.as_ref(),
+            &trusted,
+        )
+        .map_err(|e| e.to_string())
+    });
+
+    match result {
+        Ok(report) => Ok(VerifyResponse {
+            success: report.is_ok(),
+            report: Some(report),
+            error: None,
+        }),
+        Err(e) => Ok(VerifyResponse {
+            success: false,
+            report: None,
+            error: Some(e),
         }),
     }
 }
@@ -399,20 +767,58 @@ pub async fn execute_restore(
     request: RestoreRequest,
     state: State<'_, AppState>,
 ) -> Result<RestoreResponse, String> {
+    let trusted_signers = match parse_signers(&request.trusted_signers) {
+        Ok(signers) => signers,
+        Err(e) => {
+            return Ok(RestoreResponse {
+                success: false,
+                restored_files: 0,
+                restored_bytes: 0,
+                skipped_files: 0,
+                hash_mismatches: 0,
+                unverified_files: 0,
+                duration_secs: 0.0,
+                error: Some(e),
+            })
+        }
+    };
+
     let config = RestoreConfig {
         backup_dir: PathBuf::from(&request.backup_dir),
         restore_dir: PathBuf::from(&request.restore_dir),
         files: request.files,
         overwrite: request.overwrite,
+        snapshot: request.snapshot,
+        strict: request.strict,
+        trusted_signers,
     };
 
     let progress_state = state.restore_progress.clone();
 
-    let mut executor = RestoreExecutor::new(config);
+    let key = match read_repository_key(
+        request.identity_file.as_deref(),
+        &request.key_shares,
+        &request.key_share_files,
+        request.password.as_ref(),
+    ) {
+        Ok(key) => key,
+        Err(e) => {
+            return Ok(RestoreResponse {
+                success: false,
+                restored_files: 0,
+                restored_bytes: 0,
+                skipped_files: 0,
+                hash_mismatches: 0,
+                unverified_files: 0,
+                duration_secs: 0.0,
+                error: Some(e),
+            })
+        }
+    };
 
-    // パスワードが指定されている場合
-    if let Some(password) = &request.password {
-        executor = executor.with_password(password);
+    let mut executor = RestoreExecutor::new(config);
+    if let Some(key) = key {
+        executor = executor.with_key(key);
     }
 
     // 進捗コールバックを設定
@@ -434,6 +840,8 @@ pub async fn execute_restore(
                 restored_files: result.restored_files,
                 restored_bytes: result.restored_bytes,
                 skipped_files: result.skipped_files,
+                hash_mismatches: result.hash_mismatches,
+                unverified_files: result.unverified_files.len(),
                 duration_secs: duration,
                 error: if result.failed_files.is_empty() {
                     None
@@ -450,6 +858,8 @@ pub async fn execute_restore(
                 restored_files: 0,
                 restored_bytes: 0,
                 skipped_files: 0,
+                hash_mismatches: 0,
+                unverified_files: 0,
                 duration_secs: start.elapsed().as_secs_f64(),
                 error: Some(e.to_string()),
             })
@@ -457,6 +867,50 @@ pub async fn execute_restore(
     }
 }
 
+/// 信頼する署名者の公開鍵を解析
+fn parse_signers(signers: &[String]) -> Result<Vec<SignerKey>, String> {
+    signers.iter()
+        .map(|s| SignerKey::parse(s).map_err(|e| format!("{}: {}", s, e)))
+        .collect()
+}
+
+/// リクエストで指定された鍵を読み込む（復元・検証用）
+///
+/// アイデンティティ、鍵の断片、パスワード（回復フレーズ）の順に優先する。
+fn read_repository_key(
+    identity_file: Option<&str>,
+    key_shares: &[SecretString],
+    key_share_files: &[String],
+    password: Option<&SecretString>,
+) -> Result<Option<RepositoryKey>, String> {
+    if let Some(identity_file) = identity_file {
+        let content = fs::read_to_string(identity_file).map(Zeroizing::new).map_err(|e| e.to_string())?;
+        let identity = Identity::parse(&content).map_err(|e| e.to_string())?;
+        return Ok(Some(RepositoryKey::Identity(identity)));
+    }
+
+    if !key_shares.is_empty() || !key_share_files.is_empty() {
+        let shares = read_key_shares(key_shares, key_share_files)?;
+        let master = combine_key_shares(&shares).map_err(|e| e.to_string())?;
+        return Ok(Some(RepositoryKey::MasterKey(master)));
+    }
+
+    Ok(password.cloned().map(RepositoryKey::Password))
+}
+
+/// 文字列とファイルから鍵の断片を読み込む
+fn read_key_shares(shares: &[SecretString], files: &[String]) -> Result<Vec<KeyShare>, String> {
+    let contents = files.iter()
+        .map(|path| fs::read_to_string(path).map(Zeroizing::new).map_err(|e| format!("{}: {}", path, e)))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    shares.iter()
+        .map(|share| share.expose_secret())
+        .chain(contents.iter().map(|content| content.as_str()))
+        .map(|share| KeyShare::parse(share).map_err(|e| e.to_string()))
+        .collect()
+}
+
 /// 復元の進捗を取得
 #[tauri::command]
 pub fn get_restore_progress(state: State<'_, AppState>) -> ProgressResponse {
@@ -493,3 +947,419 @@ pub fn get_restore_progress(state: State<'_, AppState>) -> ProgressResponse {
         },
     }
 }
+
+// ========================================
+// 鍵管理コマンド
+// ========================================
+
+/// キースロット追加リクエスト
+#[derive(Debug, Deserialize)]
+pub struct AddKeyRequest {
+    /// バックアップディレクトリ
+    pub backup_dir: String,
+
+    /// 既存のパスワード
+    pub password: SecretString,
+
+    /// 追加するパスワード
+    pub new_password: SecretString,
+
+    /// 鍵導出パラメータ（省略時は既定値）
+    #[serde(default)]
+    pub kdf: Option<KdfParams>,
+}
+
+/// キースロット削除リクエスト
+#[derive(Debug, Deserialize)]
+pub struct RemoveKeyRequest {
+    /// バックアップディレクトリ
+    pub backup_dir: String,
+
+    /// いずれかのスロットのパスワード
+    pub password: SecretString,
+
+    /// 削除するスロットID
+    pub slot_id: u32,
+}
+
+/// パスワード変更リクエスト
+#[derive(Debug, Deserialize)]
+pub struct ChangePasswordRequest {
+    /// バックアップディレクトリ
+    pub backup_dir: String,
+
+    /// 現在のパスワード
+    pub old_password: SecretString,
+
+    /// 新しいパスワード
+    pub new_password: SecretString,
+
+    /// 鍵導出パラメータ（省略時は既定値）
+    #[serde(default)]
+    pub kdf: Option<KdfParams>,
+}
+
+/// 回復キー作成リクエスト
+#[derive(Debug, Deserialize)]
+pub struct CreateRecoveryKeyRequest {
+    /// バックアップディレクトリ
+    pub backup_dir: String,
+
+    /// いずれかのスロットのパスワード（または現在の回復フレーズ）
+    pub password: SecretString,
+}
+
+/// 回復キー作成レスポンス
+#[derive(Debug, Serialize)]
+pub struct RecoveryKeyResponse {
+    pub success: bool,
+    /// 新しい回復フレーズ（一度だけ表示する）
+    pub recovery_phrase: Option<String>,
+    pub error: Option<String>,
+}
+
+/// 鍵分割リクエスト
+#[derive(Debug, Deserialize)]
+pub struct SplitKeyRequest {
+    /// バックアップディレクトリ
+    pub backup_dir: String,
+
+    /// いずれかのスロットのパスワード
+    pub password: SecretString,
+
+    /// 復元に必要な断片数（K）
+    pub threshold: u8,
+
+    /// 作成する断片の総数（N）
+    pub total: u8,
+
+    /// 断片ファイルの出力先ディレクトリ（省略時は文字列のみ返す）
+    #[serde(default)]
+    pub output_dir: Option<String>,
+}
+
+/// 鍵分割レスポンス
+#[derive(Debug, Serialize)]
+pub struct SplitKeyResponse {
+    pub success: bool,
+    /// 断片（文字列）
+    pub shares: Vec<String>,
+    /// 書き出した断片ファイルのパス
+    pub files: Vec<String>,
+    pub error: Option<String>,
+}
+
+/// 鍵管理レスポンス
+#[derive(Debug, Serialize)]
+pub struct KeyResponse {
+    pub success: bool,
+    /// 追加したスロットID
+    pub slot_id: Option<u32>,
+    /// 残っているスロットID一覧
+    pub slots: Vec<u32>,
+    pub error: Option<String>,
+}
+
+impl KeyResponse {
+    fn failed(error: String) -> Self {
+        Self {
+            success: false,
+            slot_id: None,
+            slots: vec![],
+            error: Some(error),
+        }
+    }
+}
+
+/// キースロットを追加
+#[tauri::command]
+pub async fn add_key(request: AddKeyRequest) -> Result<KeyResponse, String> {
+    let path = PathBuf::from(&request.backup_dir);
+
+    let result = require_keyfile(&path).and_then(|mut keyfile| {
+        let id = keyfile.add_key(
+            request.password.expose_secret(),
+            request.new_password.expose_secret(),
+            request.kdf.unwrap_or_default(),
+        )?;
+        save_keyfile(&path, &keyfile)?;
+        Ok((id, keyfile))
+    });
+
+    match result {
+        Ok((id, keyfile)) => Ok(KeyResponse {
+            success: true,
+            slot_id: Some(id),
+            slots: keyfile.slots.iter().map(|s| s.id).collect(),
+            error: None,
+        }),
+        Err(e) => Ok(KeyResponse::failed(e.to_string())),
+    }
+}
+
+/// キースロットを削除
+#[tauri::command]
+pub async fn remove_key(request: RemoveKeyRequest) -> Result<KeyResponse, String> {
+    let path = PathBuf::from(&request.backup_dir);
+
+    let result = require_keyfile(&path).and_then(|mut keyfile| {
+        keyfile.remove_key(request.password.expose_secret(), request.slot_id)?;
+        save_keyfile(&path, &keyfile)?;
+        Ok(keyfile)
+    });
+
+    match result {
+        Ok(keyfile) => Ok(KeyResponse {
+            success: true,
+            slot_id: None,
+            slots: keyfile.slots.iter().map(|s| s.id).collect(),
+            error: None,
+        }),
+        Err(e) => Ok(KeyResponse::failed(e.to_string())),
+    }
+}
+
+/// パスワードを変更（データの再暗号化は不要）
+#[tauri::command]
+pub async fn change_password(request: ChangePasswordRequest) -> Result<KeyResponse, String> {
+    let path = PathBuf::from(&request.backup_dir);
+
+    let result = require_keyfile(&path).and_then(|mut keyfile| {
+        keyfile.change_password(
+            request.old_password.expose_secret(),
+            request.new_password.expose_secret(),
+            request.kdf.unwrap_or_default(),
+        )?;
+        save_keyfile(&path, &keyfile)?;
+        Ok(keyfile)
+    });
+
+    match result {
+        Ok(keyfile) => Ok(KeyResponse {
+            success: true,
+            slot_id: None,
+            slots: keyfile.slots.iter().map(|s| s.id).collect(),
+            error: None,
+        }),
+        Err(e) => Ok(KeyResponse::failed(e.to_string())),
+    }
+}
+
+/// 回復キーを作成（既存の回復キーは無効になる）
+///
+/// 回復キー導入前のリポジトリや、回復フレーズを紛失した場合に使う。
+#[tauri::command]
+pub async fn create_recovery_key(request: CreateRecoveryKeyRequest) -> Result<RecoveryKeyResponse, String> {
+    let path = PathBuf::from(&request.backup_dir);
+
+    let result = require_keyfile(&path).and_then(|mut keyfile| {
+        let master = keyfile.unlock(request.password.expose_secret())?;
+        let recovery = keyfile.create_recovery(&master)?;
+        save_keyfile(&path, &keyfile)?;
+        Ok(recovery)
+    });
+
+    match result {
+        Ok(recovery) => Ok(RecoveryKeyResponse {
+            success: true,
+            recovery_phrase: Some(recovery.phrase()),
+            error: None,
+        }),
+        Err(e) => Ok(RecoveryKeyResponse {
+            success: false,
+            recovery_phrase: None,
+            error: Some(e.to_string()),
+        }),
+    }
+}
+
+/// アイデンティティ生成リクエスト
+#[derive(Debug, Deserialize)]
+pub struct GenerateIdentityRequest {
+    /// アイデンティティファイルの保存先（既存ファイルは上書きしない）
+    pub path: String,
+}
+
+/// アイデンティティ生成レスポンス
+#[derive(Debug, Serialize)]
+pub struct GenerateIdentityResponse {
+    pub success: bool,
+    /// バックアップ側に設定する受信者の公開鍵
+    pub recipient: Option<String>,
+    pub error: Option<String>,
+}
+
+/// 受信者暗号化用のアイデンティティ（秘密鍵）を生成してファイルに保存
+#[tauri::command]
+pub async fn generate_identity(request: GenerateIdentityRequest) -> Result<GenerateIdentityResponse, String> {
+    let identity = Identity::generate();
+
+    match write_secret_file(Path::new(&request.path), &identity.to_file_string()) {
+        Ok(()) => Ok(GenerateIdentityResponse {
+            success: true,
+            recipient: Some(identity.recipient().to_string()),
+            error: None,
+        }),
+        Err(e) => Ok(GenerateIdentityResponse {
+            success: false,
+            recipient: None,
+            error: Some(e.to_string()),
+        }),
+    }
+}
+
+/// 内容ID用の鍵の生成リクエスト
+#[derive(Debug, Deserialize)]
+pub struct GenerateContentKeyRequest {
+    /// 鍵ファイルの保存先（既存ファイルは上書きしない）
+    pub path: String,
+}
+
+/// 内容ID用の鍵の生成レスポンス
+#[derive(Debug, Serialize)]
+pub struct GenerateContentKeyResponse {
+    pub success: bool,
+    pub error: Option<String>,
+}
+
+/// 受信者方式のバックアップジョブ用の内容ID用の鍵を生成してファイルに保存
+#[tauri::command]
+pub async fn generate_content_key(request: GenerateContentKeyRequest) -> Result<GenerateContentKeyResponse, String> {
+    let content_key = ContentKey::generate();
+
+    match write_secret_file(Path::new(&request.path), &content_key.to_file_string()) {
+        Ok(()) => Ok(GenerateContentKeyResponse {
+            success: true,
+            error: None,
+        }),
+        Err(e) => Ok(GenerateContentKeyResponse {
+            success: false,
+            error: Some(e.to_string()),
+        }),
+    }
+}
+
+/// 署名鍵生成リクエスト
+#[derive(Debug, Deserialize)]
+pub struct GenerateSigningKeyRequest {
+    /// 署名鍵ファイルの保存先（既存ファイルは上書きしない）
+    pub path: String,
+}
+
+/// 署名鍵生成レスポンス
+#[derive(Debug, Serialize)]
+pub struct GenerateSigningKeyResponse {
+    pub success: bool,
+    /// 復元・検証側で信頼する鍵として設定する公開鍵
+    pub signer: Option<String>,
+    pub error: Option<String>,
+}
+
+/// スナップショットの署名鍵を生成してファイルに保存
+#[tauri::command]
+pub async fn generate_signing_key(request: GenerateSigningKeyRequest) -> Result<GenerateSigningKeyResponse, String> {
+    let signer = SnapshotSigner::generate();
+
+    match write_secret_file(Path::new(&request.path), &signer.to_file_string()) {
+        Ok(()) => Ok(GenerateSigningKeyResponse {
+            success: true,
+            signer: Some(signer.signer_key().to_string()),
+            error: None,
+        }),
+        Err(e) => Ok(GenerateSigningKeyResponse {
+            success: false,
+            signer: None,
+            error: Some(e.to_string()),
+        }),
+    }
+}
+
+/// リポジトリのマスター鍵を断片に分割（任意の `threshold` 個で復元できる）
+#[tauri::command]
+pub async fn split_key(request: SplitKeyRequest) -> Result<SplitKeyResponse, String> {
+    let path = PathBuf::from(&request.backup_dir);
+
+    let result = require_keyfile(&path)
+        .and_then(|keyfile| Ok(keyfile.unlock(request.password.expose_secret())?))
+        .and_then(|master| Ok(split_master_key(&master, request.threshold, request.total)?))
+        .map_err(|e| e.to_string())
+        .and_then(|shares| {
+            let files = match &request.output_dir {
+                Some(dir) => shares.iter()
+                    .map(|share| {
+                        let file = Path::new(dir).join(format!("key-share-{}-of-{}.txt", share.index, share.total));
+                        write_secret_file(&file, &share.to_file_string()).map_err(|e| e.to_string())?;
+                        Ok(file.to_string_lossy().to_string())
+                    })
+                    .collect::<Result<Vec<_>, String>>()?,
+                None => vec![],
+            };
+            Ok((shares, files))
+        });
+
+    match result {
+        Ok((shares, files)) => Ok(SplitKeyResponse {
+            success: true,
+            shares: shares.iter().map(KeyShare::encode).collect(),
+            files,
+            error: None,
+        }),
+        Err(e) => Ok(SplitKeyResponse {
+            success: false,
+            shares: vec![],
+            files: vec![],
+            error: Some(e),
+        }),
+    }
+}
+
+/// 秘密情報のファイルを作成（既存ファイルは上書きせず、Unix では所有者のみ読み書き可能にする）
+fn write_secret_file(path: &Path, contents: &str) -> std::io::Result<()> {
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+
+    options.open(path)?.write_all(contents.as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_saved_filters_errors_on_unreadable_manifest() {
+        let source = TempDir::new().unwrap();
+        let dest = TempDir::new().unwrap();
+        std::fs::write(source.path().join("test.txt"), "Hello").unwrap();
+
+        // 初回は記録がないだけなのでエラーにしない
+        assert!(saved_filters(dest.path(), None).unwrap().is_none());
+
+        let filters = ScanFilters {
+            exclude: vec!["*.log".to_string()],
+            ..ScanFilters::default()
+        };
+        let result = BackupExecutor::new(BackupConfig {
+            source_dir: source.path().to_path_buf(),
+            dest_dir: dest.path().to_path_buf(),
+            filters: filters.clone(),
+            ..BackupConfig::default()
+        })
+            .with_encryption("test_password_123")
+            .execute()
+            .unwrap();
+        assert!(result.success);
+
+        let password = SecretString::from("test_password_123");
+        assert_eq!(saved_filters(dest.path(), Some(&password)).unwrap().unwrap().exclude, filters.exclude);
+
+        // マニフェストがあるのに読めない場合は既定の条件に戻さない
+        assert!(saved_filters(dest.path(), None).is_err());
+        assert!(saved_filters(dest.path(), Some(&SecretString::from("wrong_password"))).is_err());
+    }
+}
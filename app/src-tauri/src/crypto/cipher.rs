@@ -0,0 +1,105 @@
+//! 暗号スイート - リポジトリ単位で選択する AEAD
+//!
+//! AES-256-GCM は AES 命令を持つ CPU で高速だが、持たない ARM 機などでは
+//! ChaCha20-Poly1305 の方が速い。XChaCha20-Poly1305 は nonce が 192bit あり、
+//! ランダム nonce でも大量のファイルで衝突を気にせずに済む。
+
+use super::{CryptoError, KEY_SIZE};
+use aes_gcm::{
+    aead::{generic_array::GenericArray, Aead, KeyInit, Payload},
+    Aes256Gcm,
+};
+use chacha20poly1305::{ChaCha20Poly1305, XChaCha20Poly1305};
+use serde::{Deserialize, Serialize};
+
+/// 暗号スイート
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CipherSuite {
+    /// AES-256-GCM（96bit nonce）
+    #[default]
+    Aes256Gcm,
+    /// ChaCha20-Poly1305（96bit nonce）
+    ChaCha20Poly1305,
+    /// XChaCha20-Poly1305（192bit nonce）
+    XChaCha20Poly1305,
+}
+
+impl CipherSuite {
+    /// ヘッダに記録するID
+    pub fn id(self) -> u8 {
+        match self {
+            Self::Aes256Gcm => 0,
+            Self::ChaCha20Poly1305 => 1,
+            Self::XChaCha20Poly1305 => 2,
+        }
+    }
+
+    /// ヘッダのIDから取得
+    pub fn from_id(id: u8) -> Result<Self, CryptoError> {
+        match id {
+            0 => Ok(Self::Aes256Gcm),
+            1 => Ok(Self::ChaCha20Poly1305),
+            2 => Ok(Self::XChaCha20Poly1305),
+            _ => Err(CryptoError::UnsupportedCipher(id)),
+        }
+    }
+
+    /// nonce サイズ
+    pub fn nonce_size(self) -> usize {
+        match self {
+            Self::Aes256Gcm | Self::ChaCha20Poly1305 => 12,
+            Self::XChaCha20Poly1305 => 24,
+        }
+    }
+
+    /// 鍵を設定した暗号器を作成
+    pub(super) fn cipher(self, key: &[u8; KEY_SIZE]) -> AeadCipher {
+        let key = GenericArray::from_slice(key);
+        match self {
+            Self::Aes256Gcm => AeadCipher::Aes256Gcm(Box::new(Aes256Gcm::new(key))),
+            Self::ChaCha20Poly1305 => AeadCipher::ChaCha20Poly1305(ChaCha20Poly1305::new(key)),
+            Self::XChaCha20Poly1305 => AeadCipher::XChaCha20Poly1305(XChaCha20Poly1305::new(key)),
+        }
+    }
+}
+
+impl std::fmt::Display for CipherSuite {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Aes256Gcm => write!(f, "AES-256-GCM"),
+            Self::ChaCha20Poly1305 => write!(f, "ChaCha20-Poly1305"),
+            Self::XChaCha20Poly1305 => write!(f, "XChaCha20-Poly1305"),
+        }
+    }
+}
+
+/// 鍵を設定済みの暗号器（AES は鍵スケジュールが大きいためヒープに置く）
+pub(super) enum AeadCipher {
+    Aes256Gcm(Box<Aes256Gcm>),
+    ChaCha20Poly1305(ChaCha20Poly1305),
+    XChaCha20Poly1305(XChaCha20Poly1305),
+}
+
+impl AeadCipher {
+    /// 暗号化（nonce は暗号スイートの nonce サイズであること）
+    pub(super) fn encrypt(&self, nonce: &[u8], msg: &[u8], aad: &[u8]) -> Result<Vec<u8>, CryptoError> {
+        let payload = Payload { msg, aad };
+        match self {
+            Self::Aes256Gcm(c) => c.encrypt(GenericArray::from_slice(nonce), payload),
+            Self::ChaCha20Poly1305(c) => c.encrypt(GenericArray::from_slice(nonce), payload),
+            Self::XChaCha20Poly1305(c) => c.encrypt(GenericArray::from_slice(nonce), payload),
+        }
+        .map_err(|_| CryptoError::EncryptionFailed)
+    }
+
+    /// 復号（認証に失敗すれば `DecryptionFailed`）
+    pub(super) fn decrypt(&self, nonce: &[u8], msg: &[u8], aad: &[u8]) -> Result<Vec<u8>, CryptoError> {
+        let payload = Payload { msg, aad };
+        match self {
+            Self::Aes256Gcm(c) => c.decrypt(GenericArray::from_slice(nonce), payload),
+            Self::ChaCha20Poly1305(c) => c.decrypt(GenericArray::from_slice(nonce), payload),
+            Self::XChaCha20Poly1305(c) => c.decrypt(GenericArray::from_slice(nonce), payload),
+        }
+        .map_err(|_| CryptoError::DecryptionFailed)
+    }
+}
@@ -0,0 +1,176 @@
+//! 鍵導出 - パスワードから暗号鍵を派生
+//!
+//! 新形式は Argon2id（メモリハード）を使用する。
+//! v0.1.x で作成されたバックアップ用に、旧形式の BLAKE3 反復ハッシュも残している。
+
+use super::{CryptoError, SecretKey, KEY_SIZE};
+use argon2::{Algorithm, Argon2, Params, Version};
+use serde::{Deserialize, Serialize};
+use zeroize::Zeroizing;
+
+/// Argon2id のソルトサイズ
+pub const SALT_SIZE: usize = 16;
+
+/// メモリコストの上限（KiB、4GiB）
+///
+/// 改ざんされたヘッダによる過大なメモリ確保を防ぐ。
+const MAX_MEMORY_KIB: u32 = 4 * 1024 * 1024;
+
+/// 反復回数の上限
+const MAX_ITERATIONS: u32 = 64;
+
+/// 並列度の上限
+const MAX_PARALLELISM: u32 = 64;
+
+/// 旧形式の反復回数（v0.1.x 互換）
+const LEGACY_ITERATIONS: u32 = 100_000;
+
+/// 鍵導出パラメータ（Argon2id）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct KdfParams {
+    /// メモリコスト（KiB）
+    pub memory_kib: u32,
+
+    /// 反復回数（時間コスト）
+    pub iterations: u32,
+
+    /// 並列度
+    pub parallelism: u32,
+}
+
+impl Default for KdfParams {
+    /// RFC 9106 の推奨値（64MiB, t=3, p=4）
+    fn default() -> Self {
+        Self {
+            memory_kib: 64 * 1024,
+            iterations: 3,
+            parallelism: 4,
+        }
+    }
+}
+
+impl KdfParams {
+    /// テスト用の軽いパラメータ（8MiB, t=1, p=1）
+    #[cfg(test)]
+    pub(crate) fn for_tests() -> Self {
+        Self {
+            memory_kib: 8 * 1024,
+            iterations: 1,
+            parallelism: 1,
+        }
+    }
+
+    /// パラメータが許容範囲内かチェック
+    pub fn validate(&self) -> Result<(), CryptoError> {
+        let valid = (1..=MAX_PARALLELISM).contains(&self.parallelism)
+            && (1..=MAX_ITERATIONS).contains(&self.iterations)
+            && self.memory_kib >= 8 * self.parallelism
+            && self.memory_kib <= MAX_MEMORY_KIB;
+
+        if valid {
+            Ok(())
+        } else {
+            Err(CryptoError::InvalidKdfParams)
+        }
+    }
+
+    /// Argon2id でパスワードから鍵を派生
+    pub fn derive_key(&self, password: &[u8], salt: &[u8]) -> Result<SecretKey, CryptoError> {
+        self.validate()?;
+
+        let params = Params::new(
+            self.memory_kib,
+            self.iterations,
+            self.parallelism,
+            Some(KEY_SIZE),
+        )
+        .map_err(|_| CryptoError::InvalidKdfParams)?;
+
+        let mut key = SecretKey::new([0u8; KEY_SIZE]);
+        Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+            .hash_password_into(password, salt, &mut key[..])
+            .map_err(|_| CryptoError::InvalidKdfParams)?;
+
+        Ok(key)
+    }
+
+    /// バイト列にエンコード（12バイト、リトルエンディアン）
+    pub fn to_bytes(self) -> [u8; 12] {
+        let mut out = [0u8; 12];
+        out[..4].copy_from_slice(&self.memory_kib.to_le_bytes());
+        out[4..8].copy_from_slice(&self.iterations.to_le_bytes());
+        out[8..].copy_from_slice(&self.parallelism.to_le_bytes());
+        out
+    }
+
+    /// バイト列からデコード
+    pub fn from_bytes(bytes: &[u8; 12]) -> Self {
+        let read = |i: usize| u32::from_le_bytes([bytes[i], bytes[i + 1], bytes[i + 2], bytes[i + 3]]);
+        Self {
+            memory_kib: read(0),
+            iterations: read(4),
+            parallelism: read(8),
+        }
+    }
+}
+
+/// 旧形式の鍵を派生（BLAKE3 反復ハッシュ、v0.1.x 互換）
+///
+/// v0.1.x はゼロソルトで派生した鍵を文字列化し、ファイルごとのソルトで再度派生していた。
+/// 既存バックアップを復号するため、その手順をそのまま再現する。
+pub(super) fn derive_legacy_key(password: &str, file_salt: &[u8]) -> SecretKey {
+    let base = legacy_stretch(password, &[0u8; 32]);
+    let base = Zeroizing::new(String::from_utf8_lossy(&base[..]).into_owned());
+    legacy_stretch(&base, file_salt)
+}
+
+/// 旧形式の反復ハッシュ
+fn legacy_stretch(password: &str, salt: &[u8]) -> SecretKey {
+    use blake3::Hasher;
+
+    let mut hasher = Hasher::new();
+    hasher.update(password.as_bytes());
+    hasher.update(salt);
+
+    let mut result = SecretKey::new(*hasher.finalize().as_bytes());
+    for _ in 0..LEGACY_ITERATIONS / 1000 {
+        let mut h = Hasher::new();
+        h.update(&result[..]);
+        h.update(salt);
+        *result = *h.finalize().as_bytes();
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_params_roundtrip() {
+        let params = KdfParams {
+            memory_kib: 19 * 1024,
+            iterations: 2,
+            parallelism: 1,
+        };
+        assert_eq!(KdfParams::from_bytes(&params.to_bytes()), params);
+    }
+
+    #[test]
+    fn test_params_validation() {
+        assert!(KdfParams::default().validate().is_ok());
+
+        let too_much_memory = KdfParams {
+            memory_kib: u32::MAX,
+            ..KdfParams::default()
+        };
+        assert!(too_much_memory.validate().is_err());
+
+        let zero_iterations = KdfParams {
+            iterations: 0,
+            ..KdfParams::default()
+        };
+        assert!(zero_iterations.validate().is_err());
+    }
+}
@@ -0,0 +1,435 @@
+//! キーファイル - リポジトリのマスター鍵とキースロット
+//!
+//! データはランダムなマスター鍵で暗号化し、マスター鍵はパスワードごとの
+//! キースロットで包む（LUKS方式）。パスワード変更はキースロットの差し替えのみで済む。
+//! パスワードを忘れた場合に備え、回復キーで包んだ回復スロットを1つ持てる。
+
+use super::{
+    check_password_policy, CipherSuite, ContentKey, CryptoError, Encryptor, Identity, KdfParams, Recipient, RecoveryKey, SecretKey,
+    KEY_SIZE, NONCE_SIZE, REDACTED, SALT_SIZE,
+};
+use aes_gcm::{
+    aead::{Aead, KeyInit},
+    Aes256Gcm, Nonce,
+};
+use chrono::{DateTime, Utc};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use zeroize::{Zeroize, ZeroizeOnDrop, Zeroizing};
+
+/// キーファイル形式バージョン
+const KEYFILE_VERSION: u32 = 1;
+
+/// 内容ID用の鍵を暗号化する際のコンテキスト
+const CONTENT_KEY_CONTEXT: &str = "content-key";
+
+/// リポジトリのマスター鍵（破棄時にメモリから消去する）
+#[derive(Zeroize, ZeroizeOnDrop)]
+pub struct MasterKey([u8; KEY_SIZE]);
+
+impl MasterKey {
+    /// ランダムなマスター鍵を生成
+    pub fn generate() -> Self {
+        let mut key = [0u8; KEY_SIZE];
+        rand::thread_rng().fill(&mut key);
+        Self(key)
+    }
+
+    /// バイト列から作成（鍵の断片から復元した場合など）
+    pub(super) fn from_bytes(key: &[u8; KEY_SIZE]) -> Self {
+        Self(*key)
+    }
+
+    /// 用途別のサブ鍵を派生
+    ///
+    /// # Arguments
+    /// * `context` - 用途を表す固定文字列（用途ごとに異なる鍵になる）
+    pub fn derive_subkey(&self, context: &str) -> SecretKey {
+        SecretKey::new(blake3::derive_key(context, &self.0))
+    }
+
+    /// 鍵のバイト列
+    pub(super) fn as_bytes(&self) -> &[u8; KEY_SIZE] {
+        &self.0
+    }
+}
+
+impl fmt::Debug for MasterKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("MasterKey").field(&REDACTED).finish()
+    }
+}
+
+/// キースロット（パスワードで包んだマスター鍵）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KeySlot {
+    /// スロットID
+    pub id: u32,
+
+    /// 鍵導出パラメータ
+    pub kdf: KdfParams,
+
+    /// ソルト（hex）
+    pub salt: String,
+
+    /// nonce（hex）
+    pub nonce: String,
+
+    /// 暗号化されたマスター鍵（hex）
+    pub wrapped_key: String,
+
+    /// 作成日時
+    pub created_at: DateTime<Utc>,
+}
+
+impl KeySlot {
+    /// マスター鍵をパスワードで包んだスロットを作成
+    fn seal(id: u32, master: &MasterKey, password: &str, kdf: KdfParams) -> Result<Self, CryptoError> {
+        let mut salt = [0u8; SALT_SIZE];
+        rand::thread_rng().fill(&mut salt);
+
+        let kek = kdf.derive_key(password.as_bytes(), &salt)?;
+        let (nonce, wrapped_key) = wrap_master(&kek, master)?;
+
+        Ok(Self {
+            id,
+            kdf,
+            salt: hex::encode(salt),
+            nonce,
+            wrapped_key,
+            created_at: Utc::now(),
+        })
+    }
+
+    /// パスワードでマスター鍵を取り出す
+    fn open(&self, password: &str) -> Result<MasterKey, CryptoError> {
+        let salt = hex::decode(&self.salt).map_err(|_| CryptoError::InvalidFormat)?;
+        let kek = self.kdf.derive_key(password.as_bytes(), &salt)?;
+        unwrap_master(&kek, &self.nonce, &self.wrapped_key)
+    }
+}
+
+/// 回復スロット（回復キーで包んだマスター鍵）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecoverySlot {
+    /// nonce（hex）
+    pub nonce: String,
+
+    /// 暗号化されたマスター鍵（hex）
+    pub wrapped_key: String,
+
+    /// 作成日時
+    pub created_at: DateTime<Utc>,
+}
+
+impl RecoverySlot {
+    /// マスター鍵を回復キーで包んだスロットを作成
+    fn seal(master: &MasterKey, recovery: &RecoveryKey) -> Result<Self, CryptoError> {
+        let (nonce, wrapped_key) = wrap_master(&recovery.wrapping_key(), master)?;
+        Ok(Self {
+            nonce,
+            wrapped_key,
+            created_at: Utc::now(),
+        })
+    }
+
+    /// 回復キーでマスター鍵を取り出す
+    fn open(&self, recovery: &RecoveryKey) -> Result<MasterKey, CryptoError> {
+        unwrap_master(&recovery.wrapping_key(), &self.nonce, &self.wrapped_key)
+    }
+}
+
+/// マスター鍵を包む
+///
+/// # Returns
+/// nonce と暗号化されたマスター鍵（いずれも hex）
+fn wrap_master(kek: &[u8; KEY_SIZE], master: &MasterKey) -> Result<(String, String), CryptoError> {
+    let mut nonce = [0u8; NONCE_SIZE];
+    rand::thread_rng().fill(&mut nonce);
+
+    let cipher = Aes256Gcm::new_from_slice(kek)
+        .map_err(|_| CryptoError::EncryptionFailed)?;
+    let wrapped = cipher
+        .encrypt(Nonce::from_slice(&nonce), master.as_bytes().as_slice())
+        .map_err(|_| CryptoError::EncryptionFailed)?;
+
+    Ok((hex::encode(nonce), hex::encode(wrapped)))
+}
+
+/// 包まれたマスター鍵を取り出す
+fn unwrap_master(kek: &[u8; KEY_SIZE], nonce: &str, wrapped: &str) -> Result<MasterKey, CryptoError> {
+    let nonce = hex::decode(nonce).map_err(|_| CryptoError::InvalidFormat)?;
+    let wrapped = hex::decode(wrapped).map_err(|_| CryptoError::InvalidFormat)?;
+    if nonce.len() != NONCE_SIZE {
+        return Err(CryptoError::InvalidFormat);
+    }
+
+    let cipher = Aes256Gcm::new_from_slice(kek)
+        .map_err(|_| CryptoError::DecryptionFailed)?;
+    let key = Zeroizing::new(
+        cipher
+            .decrypt(Nonce::from_slice(&nonce), wrapped.as_slice())
+            .map_err(|_| CryptoError::DecryptionFailed)?,
+    );
+    if key.len() != KEY_SIZE {
+        return Err(CryptoError::InvalidFormat);
+    }
+
+    let mut master = MasterKey([0u8; KEY_SIZE]);
+    master.0.copy_from_slice(&key);
+    Ok(master)
+}
+
+/// キーファイル
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KeyFile {
+    /// 形式バージョン
+    pub version: u32,
+
+    /// データ・メタデータの暗号化に使う暗号スイート（未記録のリポジトリは AES-256-GCM）
+    #[serde(default)]
+    pub cipher: CipherSuite,
+
+    /// キースロット一覧
+    pub slots: Vec<KeySlot>,
+
+    /// 回復スロット（回復キー未作成のリポジトリはNone）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recovery: Option<RecoverySlot>,
+
+    /// 受信者（公開鍵）一覧（受信者方式のリポジトリのみ、マスター鍵とキースロットは持たない）
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub recipients: Vec<String>,
+
+    /// 受信者宛てに暗号化した内容ID用の鍵（hex、受信者方式のリポジトリのみ）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sealed_content_key: Option<String>,
+
+    /// 内容ID用の鍵の照合値（バックアップ側が鍵の一致を確認する）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_key_check: Option<String>,
+}
+
+impl KeyFile {
+    /// 新しいマスター鍵を生成し、最初のキースロットを持つキーファイルを作成
+    ///
+    /// パスワードは最低文字数（[`MIN_PASSWORD_LENGTH`](super::MIN_PASSWORD_LENGTH)）を満たす必要がある。
+    pub fn create(password: &str, kdf: KdfParams, cipher: CipherSuite) -> Result<(Self, MasterKey), CryptoError> {
+        check_password_policy(password)?;
+        let master = MasterKey::generate();
+        let slot = KeySlot::seal(0, &master, password, kdf)?;
+
+        Ok((
+            Self {
+                version: KEYFILE_VERSION,
+                cipher,
+                slots: vec![slot],
+                recovery: None,
+                recipients: Vec::new(),
+                sealed_content_key: None,
+                content_key_check: None,
+            },
+            master,
+        ))
+    }
+
+    /// 受信者方式のキーファイルを作成
+    pub fn for_recipients(recipients: &[Recipient], cipher: CipherSuite) -> Self {
+        Self {
+            version: KEYFILE_VERSION,
+            cipher,
+            slots: Vec::new(),
+            recovery: None,
+            recipients: recipients.iter().map(|r| r.to_string()).collect(),
+            sealed_content_key: None,
+            content_key_check: None,
+        }
+    }
+
+    /// 記録された受信者一覧
+    pub fn recipients(&self) -> Result<Vec<Recipient>, CryptoError> {
+        self.recipients.iter().map(|r| Recipient::parse(r)).collect()
+    }
+
+    /// 内容ID用の鍵を受信者宛てに暗号化して記録
+    pub fn set_content_key(&mut self, key: &ContentKey) -> Result<(), CryptoError> {
+        let sealed = Encryptor::for_recipients(self.recipients()?)
+            .with_cipher(self.cipher)
+            .seal_metadata(&key.key()[..], CONTENT_KEY_CONTEXT)?;
+        self.sealed_content_key = Some(hex::encode(sealed));
+        self.content_key_check = Some(key.check_value());
+        Ok(())
+    }
+
+    /// 指定された内容ID用の鍵が記録と一致するか確認（未記録ならfalse）
+    pub fn check_content_key(&self, key: &ContentKey) -> Result<bool, CryptoError> {
+        match &self.content_key_check {
+            Some(check) if *check == key.check_value() => Ok(true),
+            Some(_) => Err(CryptoError::ContentKeyMismatch),
+            None => Ok(false),
+        }
+    }
+
+    /// アイデンティティで内容ID用の鍵を取り出す（未記録ならNone）
+    pub fn open_content_key(&self, identity: &Identity) -> Result<Option<ContentKey>, CryptoError> {
+        let Some(sealed) = &self.sealed_content_key else {
+            return Ok(None);
+        };
+        let sealed = hex::decode(sealed).map_err(|_| CryptoError::InvalidFormat)?;
+        let key = Zeroizing::new(Encryptor::from_identity(identity.clone()).open_metadata(&sealed, CONTENT_KEY_CONTEXT)?);
+        ContentKey::from_bytes(&key).map(Some)
+    }
+
+    /// パスワードでマスター鍵を取り出す
+    ///
+    /// どのキースロットにも一致しない場合は、回復フレーズとして回復スロットを試す。
+    pub fn unlock(&self, password: &str) -> Result<MasterKey, CryptoError> {
+        match self.unlock_slot(password) {
+            Err(CryptoError::WrongPassword) => self.unlock_recovery(password),
+            result => result.map(|(_, key)| key),
+        }
+    }
+
+    /// 回復フレーズでマスター鍵を取り出す
+    pub fn unlock_recovery(&self, phrase: &str) -> Result<MasterKey, CryptoError> {
+        let recovery = RecoveryKey::parse(phrase).map_err(|_| CryptoError::WrongPassword)?;
+        self.recovery.as_ref()
+            .and_then(|slot| slot.open(&recovery).ok())
+            .ok_or(CryptoError::WrongPassword)
+    }
+
+    /// 回復キーを生成して回復スロットを設定（既存の回復キーは無効になる）
+    ///
+    /// # Returns
+    /// 新しい回復キー（表示は一度だけで、キーファイルには保存されない）
+    pub fn create_recovery(&mut self, master: &MasterKey) -> Result<RecoveryKey, CryptoError> {
+        let recovery = RecoveryKey::generate();
+        self.recovery = Some(RecoverySlot::seal(master, &recovery)?);
+        Ok(recovery)
+    }
+
+    /// パスワードに一致するスロットIDとマスター鍵を取得
+    fn unlock_slot(&self, password: &str) -> Result<(u32, MasterKey), CryptoError> {
+        if self.version != KEYFILE_VERSION {
+            return Err(CryptoError::UnsupportedVersion(self.version as u8));
+        }
+
+        self.slots.iter()
+            .find_map(|slot| slot.open(password).ok().map(|key| (slot.id, key)))
+            .ok_or(CryptoError::WrongPassword)
+    }
+
+    /// キースロットを追加
+    ///
+    /// # Returns
+    /// 追加したスロットのID
+    pub fn add_key(&mut self, password: &str, new_password: &str, kdf: KdfParams) -> Result<u32, CryptoError> {
+        check_password_policy(new_password)?;
+        let master = self.unlock(password)?;
+        let id = self.slots.iter().map(|s| s.id + 1).max().unwrap_or(0);
+        self.slots.push(KeySlot::seal(id, &master, new_password, kdf)?);
+        Ok(id)
+    }
+
+    /// キースロットを削除
+    ///
+    /// 最後のスロットは削除できない（マスター鍵を取り出せなくなるため）。
+    pub fn remove_key(&mut self, password: &str, slot_id: u32) -> Result<(), CryptoError> {
+        self.unlock(password)?;
+
+        if !self.slots.iter().any(|s| s.id == slot_id) {
+            return Err(CryptoError::KeySlotNotFound(slot_id));
+        }
+        if self.slots.len() <= 1 {
+            return Err(CryptoError::LastKeySlot);
+        }
+
+        self.slots.retain(|s| s.id != slot_id);
+        Ok(())
+    }
+
+    /// パスワードを変更（一致したスロットのみ差し替え）
+    pub fn change_password(&mut self, old_password: &str, new_password: &str, kdf: KdfParams) -> Result<(), CryptoError> {
+        check_password_policy(new_password)?;
+        let (id, master) = self.unlock_slot(old_password)?;
+        let slot = KeySlot::seal(id, &master, new_password, kdf)?;
+
+        if let Some(existing) = self.slots.iter_mut().find(|s| s.id == id) {
+            *existing = slot;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_create_and_unlock() {
+        let (keyfile, master) = KeyFile::create("password_one", KdfParams::for_tests(), CipherSuite::default()).unwrap();
+        let unlocked = keyfile.unlock("password_one").unwrap();
+        assert_eq!(unlocked.as_bytes(), master.as_bytes());
+
+        assert!(matches!(keyfile.unlock("wrong"), Err(CryptoError::WrongPassword)));
+
+        // 短すぎるパスワードではリポジトリを作成できない
+        assert!(matches!(
+            KeyFile::create("short", KdfParams::for_tests(), CipherSuite::default()),
+            Err(CryptoError::PasswordTooShort)
+        ));
+    }
+
+    #[test]
+    fn test_add_remove_and_change() {
+        let (mut keyfile, master) = KeyFile::create("password_one", KdfParams::for_tests(), CipherSuite::default()).unwrap();
+
+        let id = keyfile.add_key("password_one", "password_two", KdfParams::for_tests()).unwrap();
+        assert_eq!(keyfile.unlock("password_two").unwrap().as_bytes(), master.as_bytes());
+
+        // パスワード変更後もマスター鍵は同じ
+        keyfile.change_password("password_two", "password_three", KdfParams::for_tests()).unwrap();
+        assert!(keyfile.unlock("password_two").is_err());
+        assert_eq!(keyfile.unlock("password_three").unwrap().as_bytes(), master.as_bytes());
+
+        assert!(matches!(
+            keyfile.change_password("password_three", "short", KdfParams::for_tests()),
+            Err(CryptoError::PasswordTooShort)
+        ));
+
+        keyfile.remove_key("password_one", id).unwrap();
+        assert!(keyfile.unlock("password_three").is_err());
+        assert!(matches!(
+            keyfile.remove_key("password_one", 0),
+            Err(CryptoError::LastKeySlot)
+        ));
+
+        // 回復フレーズでパスワードを追加し直せる
+        let recovery = keyfile.create_recovery(&master).unwrap();
+        assert_eq!(keyfile.unlock(&recovery.phrase()).unwrap().as_bytes(), master.as_bytes());
+        keyfile.add_key(&recovery.phrase(), "password_four", KdfParams::for_tests()).unwrap();
+        assert_eq!(keyfile.unlock("password_four").unwrap().as_bytes(), master.as_bytes());
+    }
+
+    #[test]
+    fn test_content_key_sealed_to_recipients() {
+        let identity = Identity::generate();
+        let key = ContentKey::generate();
+        let mut keyfile = KeyFile::for_recipients(&[identity.recipient()], CipherSuite::default());
+        assert!(!keyfile.check_content_key(&key).unwrap());
+        assert!(keyfile.open_content_key(&identity).unwrap().is_none());
+
+        keyfile.set_content_key(&key).unwrap();
+        assert!(keyfile.check_content_key(&key).unwrap());
+        assert!(matches!(
+            keyfile.check_content_key(&ContentKey::generate()),
+            Err(CryptoError::ContentKeyMismatch)
+        ));
+
+        // 鍵そのものはアイデンティティを持つ者だけが取り出せる
+        let opened = keyfile.open_content_key(&identity).unwrap().unwrap();
+        assert_eq!(opened.check_value(), key.check_value());
+        assert!(keyfile.open_content_key(&Identity::generate()).is_err());
+    }
+}
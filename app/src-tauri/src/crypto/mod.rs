@@ -1,12 +1,45 @@
 //! 暗号化モジュール
-//! AES-256-GCM による安全なファイル暗号化を提供
+//! AES-256-GCM / ChaCha20-Poly1305 / XChaCha20-Poly1305 による安全なファイル暗号化を提供
+//! 暗号スイートはリポジトリ単位で選択し、各ファイルのヘッダにも記録する
+//! 鍵は Argon2id でパスワードから派生し、パラメータはファイルヘッダに記録する
+//! 大きなファイルはセグメント単位のストリーム暗号化で処理する
+//! 暗号文はヘッダ・リポジトリID・保存先を表すコンテキストに結び付け（AEAD の関連データ）、
+//! 別の保存先への差し替えや別リポジトリからの持ち込みを検出する
+//! 鍵とパスワードは破棄時にメモリから消去し（zeroize）、Debug 出力にも含めない
+//! スナップショットには Ed25519 で署名し、作成したマシンと改ざんの有無を確認できる
+
+mod cipher;
+mod kdf;
+mod keyfile;
+mod recipient;
+mod recovery;
+mod shamir;
+mod signing;
+mod strength;
+mod stream;
+
+pub use cipher::*;
+pub use kdf::*;
+pub use keyfile::*;
+pub use recipient::*;
+pub use recovery::*;
+pub use shamir::*;
+pub use signing::*;
+pub use strength::*;
+pub use stream::*;
 
 use aes_gcm::{
     aead::{Aead, KeyInit},
     Aes256Gcm, Nonce,
 };
 use rand::Rng;
+use secrecy::{ExposeSecret, SecretString};
+use std::collections::HashMap;
+use std::fmt;
+use std::io::{self, Cursor, Read, Write};
+use std::sync::Mutex;
 use thiserror::Error;
+use zeroize::Zeroizing;
 
 /// 暗号化関連エラー
 #[derive(Error, Debug)]
@@ -22,18 +55,172 @@ pub enum CryptoError {
 
     #[error("パスワードが短すぎます（最低8文字）")]
     PasswordTooShort,
+
+    #[error("鍵導出パラメータが不正です")]
+    InvalidKdfParams,
+
+    #[error("未対応の暗号化形式です（バージョン {0}）")]
+    UnsupportedVersion(u8),
+
+    #[error("未対応の暗号スイートです（ID {0}）")]
+    UnsupportedCipher(u8),
+
+    #[error("パスワードが正しくありません")]
+    WrongPassword,
+
+    #[error("キースロットが見つかりません: {0}")]
+    KeySlotNotFound(u32),
+
+    #[error("最後のキースロットは削除できません")]
+    LastKeySlot,
+
+    #[error("鍵の形式が正しくありません")]
+    InvalidKey,
+
+    #[error("受信者が多すぎます（最大{0}件）")]
+    TooManyRecipients(usize),
+
+    #[error("このアイデンティティはリポジトリの受信者に含まれていません")]
+    UnknownIdentity,
+
+    #[error("回復フレーズが正しくありません（単語またはチェックサムの誤り）")]
+    InvalidRecoveryPhrase,
+
+    #[error("鍵の断片の形式が正しくありません（転記ミスの可能性があります）")]
+    InvalidKeyShare,
+
+    #[error("署名が正しくありません")]
+    InvalidSignature,
+
+    #[error("鍵の分割数が不正です（必要数 {threshold} / 総数 {total}、必要数は2以上かつ総数以下）")]
+    InvalidShareThreshold { threshold: u8, total: u8 },
+
+    #[error("鍵の断片が足りません（{given}個、必要数 {required}個）")]
+    NotEnoughKeyShares { given: usize, required: usize },
+
+    #[error("異なる鍵の断片が混ざっているか、断片が壊れています")]
+    KeyShareMismatch,
+
+    #[error("内容ID用の鍵がリポジトリに記録された鍵と一致しません")]
+    ContentKeyMismatch,
 }
 
 /// 暗号化設定
-const SALT_SIZE: usize = 32;
 const NONCE_SIZE: usize = 12;
 const KEY_SIZE: usize = 32;
-const PBKDF2_ITERATIONS: u32 = 100_000;
+
+/// 暗号化ファイルのマジックナンバー
+const MAGIC: &[u8; 4] = b"SBEC";
+
+/// ファイル形式バージョン: 一括暗号化
+const FORMAT_VERSION: u8 = 1;
+
+/// ファイル形式バージョン: ストリーム暗号化
+const STREAM_FORMAT_VERSION: u8 = 2;
+
+/// 鍵の種別: リポジトリのマスター鍵
+const KEY_SOURCE_MASTER: u8 = 0;
+
+/// 鍵の種別: パスワードから Argon2id で派生
+const KEY_SOURCE_ARGON2ID: u8 = 1;
+
+/// 鍵の種別: ファイルごとのランダム鍵を受信者の公開鍵で包む
+const KEY_SOURCE_RECIPIENTS: u8 = 2;
+
+/// 受信者の上限（ヘッダに1バイトで記録するため）
+const MAX_RECIPIENTS: usize = u8::MAX as usize;
+
+/// 共通ヘッダサイズ: magic(4) + version(1) + key_source(1) + cipher(1)
+const PREFIX_SIZE: usize = 4 + 1 + 1 + 1;
+
+/// 鍵導出情報のサイズ: params(12) + salt(16)
+const KDF_INFO_SIZE: usize = 12 + SALT_SIZE;
+
+/// 旧形式（v0.1.x）のソルトサイズ
+const LEGACY_SALT_SIZE: usize = 32;
+
+/// マスター鍵からデータ暗号化鍵を派生する際のコンテキスト
+const DATA_KEY_CONTEXT: &str = "SecureBackup 2026-10 data encryption key";
+
+/// マスター鍵からメタデータ暗号化鍵を派生する際のコンテキスト
+const METADATA_KEY_CONTEXT: &str = "SecureBackup 2026-10 metadata encryption key";
+
+/// マスター鍵からファイル名の秘匿用の鍵を派生する際のコンテキスト
+const NAME_KEY_CONTEXT: &str = "SecureBackup 2026-10 blob name key";
+
+/// マスター鍵から内容ID（ファイルハッシュ・チャンクID）用の鍵を派生する際のコンテキスト
+const CONTENT_KEY_CONTEXT: &str = "SecureBackup 2026-10 content id key";
+
+/// マスター鍵からリポジトリIDを派生する際のコンテキスト
+const REPOSITORY_ID_CONTEXT: &str = "SecureBackup 2026-10 repository id";
+
+/// 暗号化メタデータのマジックナンバー
+const METADATA_MAGIC: &[u8; 4] = b"SBEM";
+
+/// Debug 出力で秘密情報の代わりに表示する文字列
+const REDACTED: &str = "[REDACTED]";
+
+/// 破棄時にメモリから消去される鍵
+pub type SecretKey = Zeroizing<[u8; KEY_SIZE]>;
+
+/// 派生済み鍵のキャッシュ（ソルト・パラメータ → 鍵）
+type KeyCache = HashMap<([u8; SALT_SIZE], KdfParams), SecretKey>;
+
+/// 読み込んだファイルヘッダ
+struct Header {
+    /// 形式バージョン
+    version: u8,
+
+    /// 鍵の種別
+    key_source: u8,
+
+    /// 暗号スイート
+    cipher: CipherSuite,
+
+    /// 復号に使う鍵
+    key: SecretKey,
+
+    /// ヘッダ全体（関連データとして認証する）
+    bytes: Vec<u8>,
+}
 
 /// 暗号化エンジン
 pub struct Encryptor {
-    /// パスワードから派生した鍵
-    key: [u8; KEY_SIZE],
+    /// リポジトリのマスター鍵から派生したデータ鍵
+    data_key: Option<SecretKey>,
+
+    /// リポジトリのマスター鍵から派生したメタデータ鍵（マニフェスト用）
+    metadata_key: Option<SecretKey>,
+
+    /// リポジトリのマスター鍵から派生したファイル名の秘匿用の鍵
+    name_key: Option<SecretKey>,
+
+    /// リポジトリのマスター鍵から派生した内容ID用の鍵
+    content_key: Option<SecretKey>,
+
+    /// リポジトリID（マスター鍵から派生、関連データとして認証する）
+    repository_id: Option<[u8; KEY_SIZE]>,
+
+    /// 暗号化パスワード（マスター鍵導入前のファイルの復号にも使用）
+    password: Option<SecretString>,
+
+    /// 暗号化時にファイル鍵を包む受信者（公開鍵）
+    recipients: Vec<Recipient>,
+
+    /// 受信者宛てのファイル鍵を取り出すアイデンティティ（秘密鍵）
+    identity: Option<Identity>,
+
+    /// 暗号化に使う暗号スイート（復号時はヘッダの記録に従う）
+    cipher: CipherSuite,
+
+    /// 暗号化時の鍵導出パラメータ
+    params: KdfParams,
+
+    /// 暗号化時のソルト（インスタンスごとにランダム生成）
+    salt: [u8; SALT_SIZE],
+
+    /// 派生済み鍵のキャッシュ（ソルト・パラメータ単位）
+    key_cache: Mutex<KeyCache>,
 }
 
 impl Encryptor {
@@ -42,120 +229,492 @@ impl Encryptor {
     /// # Arguments
     /// * `password` - 暗号化パスワード（8文字以上推奨）
     pub fn new(password: &str) -> Self {
-        // ランダムソルトを使用（実際の暗号化時に埋め込み）
-        let salt = [0u8; SALT_SIZE]; // 実際の暗号化時にランダム生成
-        let key = Self::derive_key(password, &salt);
-        Self { key }
+        Self::with_params(password, KdfParams::default())
     }
 
-    /// パスワードから鍵を派生（PBKDF2）
-    fn derive_key(password: &str, salt: &[u8]) -> [u8; KEY_SIZE] {
-        use blake3::Hasher;
+    /// 鍵導出パラメータを指定して暗号化エンジンを作成
+    ///
+    /// 鍵は最初の暗号化時に派生される。パラメータはファイルヘッダに記録されるため、
+    /// 後からコストを引き上げても既存のバックアップは復号できる。
+    pub fn with_params(password: &str, params: KdfParams) -> Self {
+        let mut salt = [0u8; SALT_SIZE];
+        rand::thread_rng().fill(&mut salt);
+
+        Self {
+            data_key: None,
+            metadata_key: None,
+            name_key: None,
+            content_key: None,
+            repository_id: None,
+            password: Some(password.into()),
+            recipients: Vec::new(),
+            identity: None,
+            cipher: CipherSuite::default(),
+            params,
+            salt,
+            key_cache: Mutex::new(HashMap::new()),
+        }
+    }
 
-        let mut hasher = Hasher::new();
-        hasher.update(password.as_bytes());
-        hasher.update(salt);
+    /// リポジトリのマスター鍵から暗号化エンジンを作成
+    pub fn from_master_key(master: &MasterKey) -> Self {
+        Self {
+            data_key: Some(master.derive_subkey(DATA_KEY_CONTEXT)),
+            metadata_key: Some(master.derive_subkey(METADATA_KEY_CONTEXT)),
+            name_key: Some(master.derive_subkey(NAME_KEY_CONTEXT)),
+            content_key: Some(master.derive_subkey(CONTENT_KEY_CONTEXT)),
+            repository_id: Some(*master.derive_subkey(REPOSITORY_ID_CONTEXT)),
+            password: None,
+            recipients: Vec::new(),
+            identity: None,
+            cipher: CipherSuite::default(),
+            params: KdfParams::default(),
+            salt: [0u8; SALT_SIZE],
+            key_cache: Mutex::new(HashMap::new()),
+        }
+    }
 
-        // 反復ハッシュでキーストレッチング
-        let mut result = *hasher.finalize().as_bytes();
-        for _ in 0..PBKDF2_ITERATIONS / 1000 {
-            let mut h = Hasher::new();
-            h.update(&result);
-            h.update(salt);
-            result = *h.finalize().as_bytes();
+    /// 受信者の公開鍵で暗号化するエンジンを作成（書き込み専用）
+    ///
+    /// ファイル・メタデータごとにランダムな鍵を生成して受信者ごとに包むため、
+    /// このエンジン自身は作成したデータを復号できない。
+    pub fn for_recipients(recipients: Vec<Recipient>) -> Self {
+        Self {
+            recipients,
+            ..Self::empty()
         }
+    }
 
-        result
+    /// アイデンティティ（秘密鍵）で受信者宛てのデータを復号するエンジンを作成
+    pub fn from_identity(identity: Identity) -> Self {
+        Self {
+            identity: Some(identity),
+            ..Self::empty()
+        }
+    }
+
+    /// 鍵を持たないエンジン
+    fn empty() -> Self {
+        Self {
+            data_key: None,
+            metadata_key: None,
+            name_key: None,
+            content_key: None,
+            repository_id: None,
+            password: None,
+            recipients: Vec::new(),
+            identity: None,
+            cipher: CipherSuite::default(),
+            params: KdfParams::default(),
+            salt: [0u8; SALT_SIZE],
+            key_cache: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// メタデータを復号できるか（受信者の公開鍵のみを持つ書き込み専用エンジンでは false）
+    pub fn can_open_metadata(&self) -> bool {
+        self.metadata_key.is_some() || self.identity.is_some()
+    }
+
+    /// パスワードを併用（マスター鍵導入前に作成されたファイルの復号用）
+    pub fn with_password(mut self, password: &str) -> Self {
+        self.password = Some(password.into());
+        self
+    }
+
+    /// 内容ID用の鍵を指定（受信者方式のリポジトリ用、マスター鍵から作成したエンジンでは不要）
+    pub fn with_content_key(mut self, key: &ContentKey) -> Self {
+        self.content_key = Some(key.key().clone());
+        self
+    }
+
+    /// 暗号化に使う暗号スイートを指定（リポジトリのキーファイルに記録された設定）
+    pub fn with_cipher(mut self, cipher: CipherSuite) -> Self {
+        self.cipher = cipher;
+        self
+    }
+
+    /// ソルトとパラメータに対応する鍵を取得（未派生なら派生してキャッシュ）
+    fn key_for(&self, salt: &[u8; SALT_SIZE], params: KdfParams) -> Result<SecretKey, CryptoError> {
+        let password = self.password.as_ref().ok_or(CryptoError::DecryptionFailed)?;
+
+        let mut cache = self.key_cache.lock().unwrap();
+        if let Some(key) = cache.get(&(*salt, params)) {
+            return Ok(key.clone());
+        }
+
+        let key = params.derive_key(password.expose_secret().as_bytes(), salt)?;
+        cache.insert((*salt, params), key.clone());
+        Ok(key)
+    }
+
+    /// ヘッダを構築し、暗号化に使う鍵を返す
+    fn build_header(&self, version: u8) -> Result<(Vec<u8>, SecretKey), CryptoError> {
+        let mut header = Vec::with_capacity(PREFIX_SIZE + KDF_INFO_SIZE);
+        header.extend_from_slice(MAGIC);
+        header.push(version);
+        let key = self.write_key_info(&mut header, self.data_key.as_ref(), true)?;
+        Ok((header, key))
+    }
+
+    /// 鍵の種別・暗号スイート・鍵情報をヘッダに追加し、暗号化に使う鍵を返す
+    ///
+    /// マスター鍵がある場合はマスター鍵、受信者があればファイルごとのランダム鍵、
+    /// どちらもなければ（`allow_password` の場合のみ）パスワードから派生した鍵を使用する。
+    fn write_key_info(
+        &self,
+        header: &mut Vec<u8>,
+        master_key: Option<&SecretKey>,
+        allow_password: bool,
+    ) -> Result<SecretKey, CryptoError> {
+        if let Some(key) = master_key {
+            header.push(KEY_SOURCE_MASTER);
+            header.push(self.cipher.id());
+            Ok(key.clone())
+        } else if !self.recipients.is_empty() {
+            if self.recipients.len() > MAX_RECIPIENTS {
+                return Err(CryptoError::TooManyRecipients(MAX_RECIPIENTS));
+            }
+            header.push(KEY_SOURCE_RECIPIENTS);
+            header.push(self.cipher.id());
+            header.push(self.recipients.len() as u8);
+
+            let mut file_key = SecretKey::new([0u8; KEY_SIZE]);
+            rand::thread_rng().fill(&mut file_key[..]);
+            for recipient in &self.recipients {
+                header.extend_from_slice(&recipient.wrap(&file_key)?);
+            }
+            Ok(file_key)
+        } else if allow_password {
+            header.push(KEY_SOURCE_ARGON2ID);
+            header.push(self.cipher.id());
+            header.extend_from_slice(&self.params.to_bytes());
+            header.extend_from_slice(&self.salt);
+            self.key_for(&self.salt, self.params)
+                .map_err(|_| CryptoError::EncryptionFailed)
+        } else {
+            Err(CryptoError::EncryptionFailed)
+        }
+    }
+
+    /// マジックナンバー以降のヘッダを読み込む
+    fn read_header(&self, reader: &mut impl Read) -> Result<Header, CryptoError> {
+        let mut version = [0u8; 1];
+        reader.read_exact(&mut version).map_err(|_| CryptoError::InvalidFormat)?;
+
+        let version = version[0];
+        if version != FORMAT_VERSION && version != STREAM_FORMAT_VERSION {
+            return Err(CryptoError::UnsupportedVersion(version));
+        }
+
+        let mut header = Vec::with_capacity(PREFIX_SIZE + KDF_INFO_SIZE);
+        header.extend_from_slice(MAGIC);
+        header.push(version);
+        let (key_source, cipher, key) = self.read_key_info(reader, &mut header, self.data_key.as_ref(), true)?;
+
+        Ok(Header {
+            version,
+            key_source,
+            cipher,
+            key,
+            bytes: header,
+        })
+    }
+
+    /// 鍵の種別・暗号スイート・鍵情報を読み込んでヘッダに追加し、復号に使う鍵を返す
+    fn read_key_info(
+        &self,
+        reader: &mut impl Read,
+        header: &mut Vec<u8>,
+        master_key: Option<&SecretKey>,
+        allow_password: bool,
+    ) -> Result<(u8, CipherSuite, SecretKey), CryptoError> {
+        let mut prefix = [0u8; 2];
+        reader.read_exact(&mut prefix).map_err(|_| CryptoError::InvalidFormat)?;
+        header.extend_from_slice(&prefix);
+        let cipher = CipherSuite::from_id(prefix[1])?;
+
+        // 鍵の種別に応じて鍵を取得
+        let key = match prefix[0] {
+            KEY_SOURCE_MASTER => master_key.ok_or(CryptoError::DecryptionFailed)?.clone(),
+            KEY_SOURCE_ARGON2ID if allow_password => {
+                let mut info = [0u8; KDF_INFO_SIZE];
+                reader.read_exact(&mut info).map_err(|_| CryptoError::InvalidFormat)?;
+                header.extend_from_slice(&info);
+                let params = KdfParams::from_bytes(info[..12].try_into().unwrap());
+                let salt: [u8; SALT_SIZE] = info[12..].try_into().unwrap();
+                self.key_for(&salt, params)?
+            }
+            KEY_SOURCE_RECIPIENTS => {
+                let mut count = [0u8; 1];
+                reader.read_exact(&mut count).map_err(|_| CryptoError::InvalidFormat)?;
+                let mut stanzas = vec![0u8; count[0] as usize * STANZA_SIZE];
+                reader.read_exact(&mut stanzas).map_err(|_| CryptoError::InvalidFormat)?;
+                header.push(count[0]);
+                header.extend_from_slice(&stanzas);
+
+                // 自分宛てのスタンザを探す
+                let identity = self.identity.as_ref().ok_or(CryptoError::DecryptionFailed)?;
+                stanzas.chunks(STANZA_SIZE)
+                    .find_map(|stanza| identity.unwrap(stanza))
+                    .ok_or(CryptoError::DecryptionFailed)?
+            }
+            _ => return Err(CryptoError::InvalidFormat),
+        };
+
+        Ok((prefix[0], cipher, key))
+    }
+
+    /// 関連データを構築: ヘッダ || リポジトリID（マスター鍵時のみ） || コンテキスト長(u32 BE) || コンテキスト
+    ///
+    /// ヘッダには形式バージョン・暗号スイート・受信者ごとのスタンザが含まれるため、それらの差し替えも検出できる。
+    fn associated_data(&self, header: &[u8], key_source: u8, context: &str) -> Result<Vec<u8>, CryptoError> {
+        let mut aad = header.to_vec();
+        if key_source == KEY_SOURCE_MASTER {
+            aad.extend_from_slice(&self.repository_id.ok_or(CryptoError::DecryptionFailed)?);
+        }
+        aad.extend_from_slice(&(context.len() as u32).to_be_bytes());
+        aad.extend_from_slice(context.as_bytes());
+        Ok(aad)
+    }
+
+    /// 名前から推測できない保存名を生成（鍵付き BLAKE3、hex）
+    ///
+    /// 鍵を持たない者には元の名前が分からず、同じ名前からは常に同じ保存名になる。
+    /// マスター鍵から作成したエンジンでのみ使用できる。
+    pub fn opaque_name(&self, name: &str) -> Result<String, CryptoError> {
+        let key = self.name_key.as_ref().ok_or(CryptoError::EncryptionFailed)?;
+        Ok(blake3::keyed_hash(key, name.as_bytes()).to_hex().to_string())
+    }
+
+    /// 内容ID（ファイルハッシュ・チャンクID）を計算する鍵付き BLAKE3
+    ///
+    /// 平文の BLAKE3 では既知のファイルのハッシュと照合して中身を推測できるため、
+    /// 暗号化リポジトリではリポジトリごとの鍵で計算する。
+    /// マスター鍵から作成したエンジン、または内容ID用の鍵を指定したエンジンでのみ使用でき、それ以外では None。
+    pub fn content_hasher(&self) -> Option<blake3::Hasher> {
+        self.content_key.as_ref().map(|key| blake3::Hasher::new_keyed(key))
+    }
+
+    /// 暗号化されたメタデータか（マジックナンバーで判定）
+    pub fn is_sealed_metadata(data: &[u8]) -> bool {
+        data.starts_with(METADATA_MAGIC)
+    }
+
+    /// マニフェストなどのメタデータを暗号化
+    ///
+    /// マスター鍵から作成したエンジン、または受信者を指定したエンジンでのみ使用できる。
+    /// `context` には保存先（`manifest`、`snapshot:<ID>` など）を指定し、復号時にも同じ値が必要。
+    ///
+    /// # Returns
+    /// 暗号化されたデータ: [magic(4)][key_source(1)][cipher(1)][スタンザ（受信者時のみ）][nonce][ciphertext]
+    /// （nonce より前の部分・リポジトリID・コンテキストも認証対象）
+    pub fn seal_metadata(&self, plaintext: &[u8], context: &str) -> Result<Vec<u8>, CryptoError> {
+        let mut header = METADATA_MAGIC.to_vec();
+        let key = self.write_key_info(&mut header, self.metadata_key.as_ref(), false)?;
+        let aad = self.associated_data(&header, header[METADATA_MAGIC.len()], context)?;
+
+        let mut nonce = vec![0u8; self.cipher.nonce_size()];
+        rand::thread_rng().fill(nonce.as_mut_slice());
+        let ciphertext = self.cipher.cipher(&key).encrypt(&nonce, plaintext, &aad)?;
+
+        let mut result = header;
+        result.reserve(nonce.len() + ciphertext.len());
+        result.extend_from_slice(&nonce);
+        result.extend_from_slice(&ciphertext);
+        Ok(result)
+    }
+
+    /// 暗号化されたメタデータを復号（改ざん・別の保存先からの差し替えがあれば失敗する）
+    pub fn open_metadata(&self, data: &[u8], context: &str) -> Result<Vec<u8>, CryptoError> {
+        if !Self::is_sealed_metadata(data) {
+            return Err(CryptoError::InvalidFormat);
+        }
+
+        let mut reader = &data[METADATA_MAGIC.len()..];
+        let mut header = METADATA_MAGIC.to_vec();
+        let (key_source, cipher, key) = self.read_key_info(&mut reader, &mut header, self.metadata_key.as_ref(), false)?;
+        if reader.len() < cipher.nonce_size() {
+            return Err(CryptoError::InvalidFormat);
+        }
+        let (nonce, ciphertext) = reader.split_at(cipher.nonce_size());
+
+        let aad = self.associated_data(&header, key_source, context)?;
+        cipher.cipher(&key).decrypt(nonce, ciphertext, &aad)
     }
 
     /// データを暗号化
     ///
+    /// `context` には保存先（`chunk:<ID>`、`blob:<パス>` など）を指定する。
+    /// ヘッダ・リポジトリID・コンテキストは関連データとして認証され、復号時にも同じ値が必要。
+    ///
     /// # Returns
-    /// 暗号化されたデータ: [salt(32bytes)][nonce(12bytes)][ciphertext]
-    pub fn encrypt(&self, plaintext: &[u8]) -> Result<Vec<u8>, CryptoError> {
-        // ランダムソルトとnonceを生成
-        let mut rng = rand::thread_rng();
-        let mut salt = [0u8; SALT_SIZE];
-        let mut nonce_bytes = [0u8; NONCE_SIZE];
-        rng.fill(&mut salt);
-        rng.fill(&mut nonce_bytes);
-
-        // ソルトから実際の鍵を派生
-        let key = Self::derive_key(
-            &String::from_utf8_lossy(&self.key),
-            &salt
-        );
+    /// 暗号化されたデータ: [magic(4)][version(1)][key_source(1)][cipher(1)][params(12)+salt(16)（パスワード時のみ）][nonce(12 または 24)][ciphertext]
+    pub fn encrypt(&self, plaintext: &[u8], context: &str) -> Result<Vec<u8>, CryptoError> {
+        let (header, key) = self.build_header(FORMAT_VERSION)?;
+        let aad = self.associated_data(&header, header[MAGIC.len() + 1], context)?;
 
-        // AES-256-GCMで暗号化
-        let cipher = Aes256Gcm::new_from_slice(&key)
-            .map_err(|_| CryptoError::EncryptionFailed)?;
-        let nonce = Nonce::from_slice(&nonce_bytes);
+        let mut nonce_bytes = vec![0u8; self.cipher.nonce_size()];
+        rand::thread_rng().fill(nonce_bytes.as_mut_slice());
 
-        let ciphertext = cipher
-            .encrypt(nonce, plaintext)
-            .map_err(|_| CryptoError::EncryptionFailed)?;
+        let ciphertext = self.cipher.cipher(&key).encrypt(&nonce_bytes, plaintext, &aad)?;
 
-        // salt + nonce + ciphertext を結合
-        let mut result = Vec::with_capacity(SALT_SIZE + NONCE_SIZE + ciphertext.len());
-        result.extend_from_slice(&salt);
+        // ヘッダ + nonce + ciphertext を結合
+        let mut result = header;
+        result.reserve(nonce_bytes.len() + ciphertext.len());
         result.extend_from_slice(&nonce_bytes);
         result.extend_from_slice(&ciphertext);
 
         Ok(result)
     }
 
+    /// ストリーム暗号化ライターを作成
+    ///
+    /// ヘッダを書き込んだ後、書き込まれた平文をセグメント単位で暗号化する。
+    /// `context` は `encrypt` と同様に各セグメントの関連データとして認証される。
+    ///
+    /// 同じデータ鍵で多数のストリームを暗号化しても nonce が衝突しないよう、
+    /// ストリームごとのランダムなソルトから派生したサブ鍵を使う。
+    ///
+    /// # Returns
+    /// 出力形式: [magic(4)][version(1)][key_source(1)][cipher(1)][params+salt（パスワード時のみ）][stream_salt(32)][nonce_prefix(7 または 19)][segments...]
+    pub fn encrypt_writer<W: Write>(&self, mut writer: W, context: &str) -> io::Result<StreamEncryptor<W>> {
+        let to_io = |e: CryptoError| io::Error::new(io::ErrorKind::InvalidData, e);
+        let (header, key) = self.build_header(STREAM_FORMAT_VERSION).map_err(to_io)?;
+        let aad = self.associated_data(&header, header[MAGIC.len() + 1], context).map_err(to_io)?;
+
+        let mut salt = [0u8; STREAM_SALT_SIZE];
+        rand::thread_rng().fill(&mut salt);
+        let mut prefix = vec![0u8; nonce_prefix_size(self.cipher)];
+        rand::thread_rng().fill(prefix.as_mut_slice());
+
+        writer.write_all(&header)?;
+        writer.write_all(&salt)?;
+        writer.write_all(&prefix)?;
+
+        StreamEncryptor::new(writer, self.cipher, &stream_key(&key, &salt), prefix, aad).map_err(to_io)
+    }
+
+    /// 復号リーダーを作成
+    ///
+    /// ストリーム形式はセグメント単位で復号し、一括形式・旧形式はまとめて復号する。
+    /// `context` は暗号化時と同じ値が必要（旧形式では使用しない）。
+    /// 復号エラーは `CryptoError` を内包する `InvalidData` の IO エラーになる。
+    pub fn decrypt_reader<'a, R: Read + 'a>(&self, mut reader: R, context: &str) -> io::Result<Box<dyn Read + 'a>> {
+        let to_io = |e: CryptoError| io::Error::new(io::ErrorKind::InvalidData, e);
+
+        let mut magic = Vec::with_capacity(MAGIC.len());
+        (&mut reader).take(MAGIC.len() as u64).read_to_end(&mut magic)?;
+
+        if magic != MAGIC {
+            // 旧形式
+            let mut data = magic;
+            reader.read_to_end(&mut data)?;
+            let plaintext = self.decrypt_legacy(&data).map_err(to_io)?;
+            return Ok(Box::new(Cursor::new(plaintext)));
+        }
+
+        // 暗号スイートは現在の設定ではなくヘッダの記録に従う
+        let header = self.read_header(&mut reader).map_err(to_io)?;
+        let aad = self.associated_data(&header.bytes, header.key_source, context).map_err(to_io)?;
+
+        if header.version == STREAM_FORMAT_VERSION {
+            let mut salt = [0u8; STREAM_SALT_SIZE];
+            let mut prefix = vec![0u8; nonce_prefix_size(header.cipher)];
+            reader.read_exact(&mut salt)
+                .and_then(|_| reader.read_exact(&mut prefix))
+                .map_err(|_| to_io(CryptoError::InvalidFormat))?;
+            let key = stream_key(&header.key, &salt);
+            let decryptor = StreamDecryptor::new(reader, header.cipher, &key, prefix, aad).map_err(to_io)?;
+            Ok(Box::new(decryptor))
+        } else {
+            let mut data = Vec::new();
+            reader.read_to_end(&mut data)?;
+            let plaintext = Self::decrypt_single(header.cipher, &header.key, &data, &aad).map_err(to_io)?;
+            Ok(Box::new(Cursor::new(plaintext)))
+        }
+    }
+
     /// データを復号化
     ///
+    /// ヘッダのマジックナンバーで形式を判別し、旧形式（v0.1.x）も自動的に復号する。
+    pub fn decrypt(&self, data: &[u8], context: &str) -> Result<Vec<u8>, CryptoError> {
+        let mut plaintext = Vec::new();
+        self.decrypt_reader(data, context)
+            .and_then(|mut reader| reader.read_to_end(&mut plaintext))
+            .map_err(|e| match e.into_inner().map(|inner| inner.downcast::<CryptoError>()) {
+                Some(Ok(crypto_error)) => *crypto_error,
+                _ => CryptoError::InvalidFormat,
+            })?;
+        Ok(plaintext)
+    }
+
+    /// 一括形式を復号化
+    ///
+    /// # Arguments
+    /// * `cipher` - ヘッダに記録された暗号スイート
+    /// * `data` - ヘッダ以降のデータ: [nonce][ciphertext]
+    /// * `aad` - 関連データ
+    fn decrypt_single(
+        cipher: CipherSuite,
+        key: &[u8; KEY_SIZE],
+        data: &[u8],
+        aad: &[u8],
+    ) -> Result<Vec<u8>, CryptoError> {
+        if data.len() < cipher.nonce_size() {
+            return Err(CryptoError::InvalidFormat);
+        }
+        let (nonce_bytes, ciphertext) = data.split_at(cipher.nonce_size());
+
+        cipher.cipher(key).decrypt(nonce_bytes, ciphertext, aad)
+    }
+
+    /// 旧形式を復号化
+    ///
     /// # Arguments
     /// * `data` - 暗号化されたデータ: [salt(32bytes)][nonce(12bytes)][ciphertext]
-    pub fn decrypt(&self, data: &[u8]) -> Result<Vec<u8>, CryptoError> {
-        if data.len() < SALT_SIZE + NONCE_SIZE {
+    fn decrypt_legacy(&self, data: &[u8]) -> Result<Vec<u8>, CryptoError> {
+        if data.len() < LEGACY_SALT_SIZE + NONCE_SIZE {
             return Err(CryptoError::InvalidFormat);
         }
+        let password = self.password.as_ref().ok_or(CryptoError::DecryptionFailed)?;
 
         // salt, nonce, ciphertext を分離
-        let salt = &data[..SALT_SIZE];
-        let nonce_bytes = &data[SALT_SIZE..SALT_SIZE + NONCE_SIZE];
-        let ciphertext = &data[SALT_SIZE + NONCE_SIZE..];
-
-        // ソルトから鍵を派生
-        let key = Self::derive_key(
-            &String::from_utf8_lossy(&self.key),
-            salt
-        );
+        let salt = &data[..LEGACY_SALT_SIZE];
+        let nonce_bytes = &data[LEGACY_SALT_SIZE..LEGACY_SALT_SIZE + NONCE_SIZE];
+        let ciphertext = &data[LEGACY_SALT_SIZE + NONCE_SIZE..];
+
+        let key = derive_legacy_key(password.expose_secret(), salt);
 
-        // AES-256-GCMで復号化
-        let cipher = Aes256Gcm::new_from_slice(&key)
+        let cipher = Aes256Gcm::new_from_slice(&key[..])
             .map_err(|_| CryptoError::DecryptionFailed)?;
         let nonce = Nonce::from_slice(nonce_bytes);
 
-        let plaintext = cipher
+        cipher
             .decrypt(nonce, ciphertext)
-            .map_err(|_| CryptoError::DecryptionFailed)?;
-
-        Ok(plaintext)
+            .map_err(|_| CryptoError::DecryptionFailed)
     }
 
     /// パスワード強度をチェック
+    ///
+    /// 辞書・キーボードの並び・繰り返し・日付などのパターンから推測回数を見積もる。
+    /// 詳しい結果や改善の提案は [`estimate_password`] で取得する。
     pub fn check_password_strength(password: &str) -> PasswordStrength {
-        let len = password.len();
-        let has_upper = password.chars().any(|c| c.is_uppercase());
-        let has_lower = password.chars().any(|c| c.is_lowercase());
-        let has_digit = password.chars().any(|c| c.is_numeric());
-        let has_special = password.chars().any(|c| !c.is_alphanumeric());
-
-        let score = (if len >= 8 { 1 } else { 0 })
-            + (if len >= 12 { 1 } else { 0 })
-            + (if has_upper { 1 } else { 0 })
-            + (if has_lower { 1 } else { 0 })
-            + (if has_digit { 1 } else { 0 })
-            + (if has_special { 1 } else { 0 });
+        estimate_password(password).strength()
+    }
+}
 
-        match score {
-            0..=2 => PasswordStrength::Weak,
-            3..=4 => PasswordStrength::Medium,
-            _ => PasswordStrength::Strong,
-        }
+/// Debug 出力では鍵・パスワードの有無のみを示す
+impl fmt::Debug for Encryptor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Encryptor")
+            .field("master_key", &self.data_key.as_ref().map(|_| REDACTED))
+            .field("password", &self.password.as_ref().map(|_| REDACTED))
+            .field("recipients", &self.recipients)
+            .field("identity", &self.identity)
+            .field("cipher", &self.cipher)
+            .field("params", &self.params)
+            .finish_non_exhaustive()
     }
 }
 
@@ -189,11 +748,11 @@ mod tests {
         let encryptor = Encryptor::new("test_password_123");
         let plaintext = b"Hello, World! This is a test message.";
 
-        let encrypted = encryptor.encrypt(plaintext).unwrap();
+        let encrypted = encryptor.encrypt(plaintext, "blob:test.txt").unwrap();
         assert_ne!(&encrypted[..], plaintext);
         assert!(encrypted.len() > plaintext.len());
 
-        let decrypted = encryptor.decrypt(&encrypted).unwrap();
+        let decrypted = encryptor.decrypt(&encrypted, "blob:test.txt").unwrap();
         assert_eq!(&decrypted[..], plaintext);
     }
 
@@ -203,13 +762,175 @@ mod tests {
         let encryptor2 = Encryptor::new("password2");
 
         let plaintext = b"Secret data";
-        let encrypted = encryptor1.encrypt(plaintext).unwrap();
+        let encrypted = encryptor1.encrypt(plaintext, "test").unwrap();
 
         // 異なるパスワードでは復号化に失敗するはず
-        let result = encryptor2.decrypt(&encrypted);
+        let result = encryptor2.decrypt(&encrypted, "test");
         assert!(result.is_err());
     }
 
+    #[test]
+    fn test_header_records_params() {
+        let params = KdfParams::for_tests();
+        let encryptor = Encryptor::with_params("test_password_123", params);
+        let encrypted = encryptor.encrypt(b"data", "test").unwrap();
+
+        assert_eq!(&encrypted[..4], MAGIC);
+        assert_eq!(encrypted[4], FORMAT_VERSION);
+        assert_eq!(encrypted[5], KEY_SOURCE_ARGON2ID);
+        assert_eq!(encrypted[6], CipherSuite::Aes256Gcm.id());
+        assert_eq!(KdfParams::from_bytes(encrypted[7..19].try_into().unwrap()), params);
+
+        // 既定パラメータのエンジンでもヘッダのパラメータで復号できる
+        let decryptor = Encryptor::new("test_password_123");
+        assert_eq!(decryptor.decrypt(&encrypted, "test").unwrap(), b"data");
+    }
+
+    #[test]
+    fn test_master_key_encryption() {
+        let master = MasterKey::generate();
+        let encryptor = Encryptor::from_master_key(&master);
+        let encrypted = encryptor.encrypt(b"data", "test").unwrap();
+        assert_eq!(encrypted[5], KEY_SOURCE_MASTER);
+
+        // 同じマスター鍵なら復号でき、パスワードだけでは復号できない
+        assert_eq!(Encryptor::from_master_key(&master).decrypt(&encrypted, "test").unwrap(), b"data");
+        assert!(Encryptor::new("password").decrypt(&encrypted, "test").is_err());
+    }
+
+    #[test]
+    fn test_cipher_suites() {
+        let master = MasterKey::generate();
+        let plaintext: Vec<u8> = (0..SEGMENT_SIZE + 17).map(|i| (i % 13) as u8).collect();
+
+        for suite in [CipherSuite::Aes256Gcm, CipherSuite::ChaCha20Poly1305, CipherSuite::XChaCha20Poly1305] {
+            let encryptor = Encryptor::from_master_key(&master).with_cipher(suite);
+            let encrypted = encryptor.encrypt(&plaintext, "test").unwrap();
+            assert_eq!(encrypted[6], suite.id());
+            assert_eq!(encrypted.len(), PREFIX_SIZE + suite.nonce_size() + plaintext.len() + 16);
+
+            let mut writer = encryptor.encrypt_writer(Vec::new(), "test").unwrap();
+            writer.write_all(&plaintext).unwrap();
+            let streamed = writer.finish().unwrap();
+
+            let sealed = encryptor.seal_metadata(b"{}", "manifest").unwrap();
+
+            // 復号はヘッダの記録に従うため、既定の暗号スイートのエンジンでも読める
+            let decryptor = Encryptor::from_master_key(&master);
+            assert_eq!(decryptor.decrypt(&encrypted, "test").unwrap(), plaintext);
+            assert_eq!(decryptor.decrypt(&streamed, "test").unwrap(), plaintext);
+            assert_eq!(decryptor.open_metadata(&sealed, "manifest").unwrap(), b"{}");
+        }
+
+        // 記録された暗号スイートの差し替えは認証に失敗する
+        let encryptor = Encryptor::from_master_key(&master).with_cipher(CipherSuite::ChaCha20Poly1305);
+        let mut encrypted = encryptor.encrypt(b"data", "test").unwrap();
+        encrypted[6] = CipherSuite::Aes256Gcm.id();
+        assert!(encryptor.decrypt(&encrypted, "test").is_err());
+        encrypted[6] = 0xff;
+        assert!(matches!(encryptor.decrypt(&encrypted, "test"), Err(CryptoError::UnsupportedCipher(0xff))));
+    }
+
+    #[test]
+    fn test_context_is_authenticated() {
+        let master = MasterKey::generate();
+        let encryptor = Encryptor::from_master_key(&master);
+        let encrypted = encryptor.encrypt(b"data", "blob:a.txt").unwrap();
+
+        // 別の保存先への差し替え・別のリポジトリでは認証に失敗する
+        assert!(encryptor.decrypt(&encrypted, "blob:b.txt").is_err());
+        assert!(Encryptor::from_master_key(&MasterKey::generate()).decrypt(&encrypted, "blob:a.txt").is_err());
+
+        // ヘッダ（形式バージョン・鍵の種別）も認証対象
+        let mut downgraded = encrypted.clone();
+        downgraded[4] = STREAM_FORMAT_VERSION;
+        assert!(encryptor.decrypt(&downgraded, "blob:a.txt").is_err());
+
+        // パスワード由来の鍵でもコンテキストは認証される
+        let encryptor = Encryptor::new("test_password_123");
+        let encrypted = encryptor.encrypt(b"data", "chunk:1").unwrap();
+        assert!(encryptor.decrypt(&encrypted, "chunk:2").is_err());
+    }
+
+    #[test]
+    fn test_metadata_sealing() {
+        let master = MasterKey::generate();
+        let encryptor = Encryptor::from_master_key(&master);
+        let sealed = encryptor.seal_metadata(b"{\"files\":{}}", "manifest").unwrap();
+        assert!(Encryptor::is_sealed_metadata(&sealed));
+        assert_eq!(encryptor.open_metadata(&sealed, "manifest").unwrap(), b"{\"files\":{}}");
+
+        // 改ざん・別の鍵・パスワードのみのエンジンでは開けない
+        let mut tampered = sealed.clone();
+        *tampered.last_mut().unwrap() ^= 0x01;
+        assert!(encryptor.open_metadata(&tampered, "manifest").is_err());
+        assert!(Encryptor::from_master_key(&MasterKey::generate()).open_metadata(&sealed, "manifest").is_err());
+        assert!(Encryptor::new("password").seal_metadata(b"{}", "manifest").is_err());
+    }
+
+    #[test]
+    fn test_stream_encryption() {
+        let master = MasterKey::generate();
+        let encryptor = Encryptor::from_master_key(&master);
+        let plaintext: Vec<u8> = (0..SEGMENT_SIZE * 2 + 123).map(|i| (i % 7) as u8).collect();
+
+        let mut writer = encryptor.encrypt_writer(Vec::new(), "blob:large.bin").unwrap();
+        writer.write_all(&plaintext).unwrap();
+        let encrypted = writer.finish().unwrap();
+        assert_eq!(encrypted[4], STREAM_FORMAT_VERSION);
+
+        // ストリーム・一括のどちらの復号APIでも読める
+        let mut decrypted = Vec::new();
+        encryptor.decrypt_reader(encrypted.as_slice(), "blob:large.bin").unwrap()
+            .read_to_end(&mut decrypted)
+            .unwrap();
+        assert_eq!(decrypted, plaintext);
+        assert_eq!(encryptor.decrypt(&encrypted, "blob:large.bin").unwrap(), plaintext);
+        assert!(encryptor.decrypt(&encrypted, "blob:other.bin").is_err());
+
+        // ストリームごとに別のサブ鍵を使い、ソルトを書き換えると復号できない
+        let salt_offset = PREFIX_SIZE;
+        let mut again = encryptor.encrypt_writer(Vec::new(), "blob:large.bin").unwrap();
+        again.write_all(&plaintext).unwrap();
+        let again = again.finish().unwrap();
+        assert_ne!(again[salt_offset..salt_offset + STREAM_SALT_SIZE], encrypted[salt_offset..salt_offset + STREAM_SALT_SIZE]);
+        let mut tampered = encrypted.clone();
+        tampered[salt_offset] ^= 1;
+        assert!(encryptor.decrypt(&tampered, "blob:large.bin").is_err());
+    }
+
+    #[test]
+    fn test_decrypt_legacy_format() {
+        // v0.1.x と同じ手順で暗号化: [salt(32)][nonce(12)][ciphertext]
+        let salt = [7u8; LEGACY_SALT_SIZE];
+        let nonce_bytes = [9u8; NONCE_SIZE];
+        let key = derive_legacy_key("legacy_password", &salt);
+        let cipher = Aes256Gcm::new_from_slice(&key[..]).unwrap();
+        let ciphertext = cipher
+            .encrypt(Nonce::from_slice(&nonce_bytes), b"old backup".as_slice())
+            .unwrap();
+
+        let mut legacy = Vec::new();
+        legacy.extend_from_slice(&salt);
+        legacy.extend_from_slice(&nonce_bytes);
+        legacy.extend_from_slice(&ciphertext);
+
+        let encryptor = Encryptor::new("legacy_password");
+        assert_eq!(encryptor.decrypt(&legacy, "ignored").unwrap(), b"old backup");
+    }
+
+    #[test]
+    fn test_debug_is_redacted() {
+        let master = MasterKey::generate();
+        let encryptor = Encryptor::from_master_key(&master).with_password("hunter2_password");
+
+        let debug = format!("{:?} {:?}", encryptor, master);
+        assert!(debug.contains(REDACTED));
+        assert!(!debug.contains("hunter2_password"));
+        assert!(!debug.contains(&hex::encode(master.as_bytes())));
+        assert!(!debug.contains(&format!("{:?}", master.as_bytes())));
+    }
+
     #[test]
     fn test_password_strength() {
         assert_eq!(
@@ -217,12 +938,21 @@ mod tests {
             PasswordStrength::Weak
         );
         assert_eq!(
-            Encryptor::check_password_strength("Abc12345"),
-            PasswordStrength::Medium
+            Encryptor::check_password_strength("Password1!"),
+            PasswordStrength::Weak
         );
         assert_eq!(
+            Encryptor::check_password_strength("Abc12345"),
+            PasswordStrength::Weak
+        );
+        // 文字種がそろっていても、よく使われるパスワードの変形は強いと判定しない
+        assert_ne!(
             Encryptor::check_password_strength("MyP@ssw0rd!123"),
             PasswordStrength::Strong
         );
+        assert_eq!(
+            Encryptor::check_password_strength("Kx9#mQ2$vL7!pR4w"),
+            PasswordStrength::Strong
+        );
     }
 }
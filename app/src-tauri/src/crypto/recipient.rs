@@ -0,0 +1,245 @@
+//! 受信者暗号化 - X25519 公開鍵によるファイル鍵の包み込み（age 方式）
+//!
+//! ファイルごとにランダムなファイル鍵を生成し、受信者ごとに一時鍵との ECDH で
+//! 派生した鍵で包む。バックアップを作成するマシンは公開鍵だけを持てばよく、
+//! 秘密鍵（アイデンティティ）を持つ者だけが復号できる。
+
+use super::{AeadCipher, CipherSuite, CryptoError, SecretKey, KEY_SIZE, REDACTED};
+use rand::Rng;
+use std::fmt;
+use x25519_dalek::{PublicKey, SharedSecret, StaticSecret};
+use zeroize::Zeroizing;
+
+/// 受信者（公開鍵）の文字列表現のプレフィックス
+const RECIPIENT_PREFIX: &str = "sbr1";
+
+/// アイデンティティ（秘密鍵）の文字列表現のプレフィックス
+const IDENTITY_PREFIX: &str = "SB-SECRET-KEY-1";
+
+/// 内容ID用の鍵の文字列表現のプレフィックス
+const CONTENT_KEY_PREFIX: &str = "SB-CONTENT-KEY-1";
+
+/// 内容ID用の鍵の照合値を計算する際のコンテキスト
+const CONTENT_KEY_CHECK_CONTEXT: &str = "SecureBackup 2026-10 content id key check";
+
+/// 包み込み鍵を派生する際のコンテキスト
+const WRAP_KEY_CONTEXT: &str = "SecureBackup 2026-10 recipient wrap key";
+
+/// 包み込みタグサイズ
+const WRAP_TAG_SIZE: usize = 16;
+
+/// スタンザ（受信者ごとの包み込み）のサイズ: ephemeral_public(32) + wrapped_key(32) + tag(16)
+pub const STANZA_SIZE: usize = 32 + KEY_SIZE + WRAP_TAG_SIZE;
+
+/// 受信者（X25519 公開鍵）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Recipient(PublicKey);
+
+impl Recipient {
+    /// 文字列表現（`sbr1<hex>`）から作成
+    pub fn parse(s: &str) -> Result<Self, CryptoError> {
+        let hex = s.trim().strip_prefix(RECIPIENT_PREFIX).ok_or(CryptoError::InvalidKey)?;
+        let bytes: [u8; 32] = hex::decode(hex)
+            .ok()
+            .and_then(|b| b.try_into().ok())
+            .ok_or(CryptoError::InvalidKey)?;
+        Ok(Self(PublicKey::from(bytes)))
+    }
+
+    /// ファイル鍵を包んだスタンザを作成
+    pub(super) fn wrap(&self, file_key: &[u8; KEY_SIZE]) -> Result<[u8; STANZA_SIZE], CryptoError> {
+        let mut secret = Zeroizing::new([0u8; 32]);
+        rand::thread_rng().fill(&mut secret[..]);
+        let ephemeral = StaticSecret::from(*secret);
+        let ephemeral_public = PublicKey::from(&ephemeral);
+
+        let wrap_key = wrap_key(ephemeral.diffie_hellman(&self.0), &ephemeral_public, &self.0)?;
+        let wrapped = wrap_cipher(&wrap_key).encrypt(&[0u8; 12], file_key, &[])?;
+
+        let mut stanza = [0u8; STANZA_SIZE];
+        stanza[..32].copy_from_slice(ephemeral_public.as_bytes());
+        stanza[32..].copy_from_slice(&wrapped);
+        Ok(stanza)
+    }
+}
+
+impl std::fmt::Display for Recipient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}{}", RECIPIENT_PREFIX, hex::encode(self.0.as_bytes()))
+    }
+}
+
+/// アイデンティティ（X25519 秘密鍵、破棄時にメモリから消去される）
+#[derive(Clone)]
+pub struct Identity(StaticSecret);
+
+impl Identity {
+    /// 新しいアイデンティティを生成
+    pub fn generate() -> Self {
+        let mut secret = Zeroizing::new([0u8; 32]);
+        rand::thread_rng().fill(&mut secret[..]);
+        Self(StaticSecret::from(*secret))
+    }
+
+    /// 対応する受信者（公開鍵）
+    pub fn recipient(&self) -> Recipient {
+        Recipient(PublicKey::from(&self.0))
+    }
+
+    /// アイデンティティファイルの内容から作成（`#` で始まる行はコメント）
+    pub fn parse(s: &str) -> Result<Self, CryptoError> {
+        let line = s.lines()
+            .map(str::trim)
+            .find(|line| !line.is_empty() && !line.starts_with('#'))
+            .ok_or(CryptoError::InvalidKey)?;
+        let hex = line.strip_prefix(IDENTITY_PREFIX).ok_or(CryptoError::InvalidKey)?;
+        let decoded = Zeroizing::new(hex::decode(hex).map_err(|_| CryptoError::InvalidKey)?);
+        let bytes: &[u8; 32] = decoded.as_slice().try_into().map_err(|_| CryptoError::InvalidKey)?;
+        Ok(Self(StaticSecret::from(*bytes)))
+    }
+
+    /// アイデンティティファイルの内容（公開鍵をコメントとして含む）
+    pub fn to_file_string(&self) -> Zeroizing<String> {
+        Zeroizing::new(format!(
+            "# SecureBackup identity\n# public key: {}\n{}{}\n",
+            self.recipient(),
+            IDENTITY_PREFIX,
+            hex::encode_upper(Zeroizing::new(self.0.to_bytes()).as_slice()),
+        ))
+    }
+
+    /// スタンザからファイル鍵を取り出す（自分宛てでなければNone）
+    pub(super) fn unwrap(&self, stanza: &[u8]) -> Option<SecretKey> {
+        let ephemeral_public: [u8; 32] = stanza.get(..32)?.try_into().ok()?;
+        let ephemeral_public = PublicKey::from(ephemeral_public);
+
+        let shared = self.0.diffie_hellman(&ephemeral_public);
+        let wrap_key = wrap_key(shared, &ephemeral_public, &self.recipient().0).ok()?;
+        let file_key = Zeroizing::new(
+            wrap_cipher(&wrap_key)
+                .decrypt(&[0u8; 12], stanza.get(32..)?, &[])
+                .ok()?,
+        );
+        if file_key.len() != KEY_SIZE {
+            return None;
+        }
+
+        let mut key = SecretKey::new([0u8; KEY_SIZE]);
+        key.copy_from_slice(&file_key);
+        Some(key)
+    }
+}
+
+impl fmt::Debug for Identity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Identity").field(&self.recipient().to_string()).field(&REDACTED).finish()
+    }
+}
+
+/// 受信者方式のリポジトリの内容ID用の鍵（破棄時にメモリから消去される）
+///
+/// 書き込み専用のマシンはマスター鍵を持たないため、チャンクIDを鍵付きで計算する鍵を
+/// バックアップジョブ側で別に保持する。リポジトリには受信者宛てに暗号化した鍵と照合値のみを記録する。
+#[derive(Clone)]
+pub struct ContentKey(SecretKey);
+
+impl ContentKey {
+    /// 新しい鍵を生成
+    pub fn generate() -> Self {
+        let mut key = SecretKey::new([0u8; KEY_SIZE]);
+        rand::thread_rng().fill(&mut key[..]);
+        Self(key)
+    }
+
+    /// 鍵ファイルの内容から作成（`#` で始まる行はコメント）
+    pub fn parse(s: &str) -> Result<Self, CryptoError> {
+        let line = s.lines()
+            .map(str::trim)
+            .find(|line| !line.is_empty() && !line.starts_with('#'))
+            .ok_or(CryptoError::InvalidKey)?;
+        let hex = line.strip_prefix(CONTENT_KEY_PREFIX).ok_or(CryptoError::InvalidKey)?;
+        Self::from_bytes(&Zeroizing::new(hex::decode(hex).map_err(|_| CryptoError::InvalidKey)?))
+    }
+
+    /// 鍵ファイルの内容
+    pub fn to_file_string(&self) -> Zeroizing<String> {
+        Zeroizing::new(format!(
+            "# SecureBackup content id key\n{}{}\n",
+            CONTENT_KEY_PREFIX,
+            hex::encode_upper(&self.0[..]),
+        ))
+    }
+
+    /// 照合値（キーファイルに記録し、ジョブの鍵がリポジトリと一致するか確認する）
+    pub fn check_value(&self) -> String {
+        hex::encode(blake3::keyed_hash(&self.0, CONTENT_KEY_CHECK_CONTEXT.as_bytes()).as_bytes())
+    }
+
+    /// バイト列から作成
+    pub(super) fn from_bytes(bytes: &[u8]) -> Result<Self, CryptoError> {
+        let bytes: &[u8; KEY_SIZE] = bytes.try_into().map_err(|_| CryptoError::InvalidKey)?;
+        Ok(Self(SecretKey::new(*bytes)))
+    }
+
+    /// 内容IDを計算する鍵
+    pub(super) fn key(&self) -> &SecretKey {
+        &self.0
+    }
+}
+
+impl fmt::Debug for ContentKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("ContentKey").field(&REDACTED).finish()
+    }
+}
+
+/// ECDH の共有秘密から包み込み鍵を派生（一時公開鍵と受信者の公開鍵も結び付ける）
+fn wrap_key(shared: SharedSecret, ephemeral_public: &PublicKey, recipient: &PublicKey) -> Result<SecretKey, CryptoError> {
+    // 小位数の点などによる全ゼロの共有秘密は拒否する
+    if !shared.was_contributory() {
+        return Err(CryptoError::InvalidKey);
+    }
+
+    let mut material = Zeroizing::new(Vec::with_capacity(32 * 3));
+    material.extend_from_slice(shared.as_bytes());
+    material.extend_from_slice(ephemeral_public.as_bytes());
+    material.extend_from_slice(recipient.as_bytes());
+    Ok(SecretKey::new(blake3::derive_key(WRAP_KEY_CONTEXT, &material)))
+}
+
+/// 包み込みに使う暗号器（鍵はスタンザごとに一意のため nonce は固定でよい）
+fn wrap_cipher(key: &[u8; KEY_SIZE]) -> AeadCipher {
+    CipherSuite::ChaCha20Poly1305.cipher(key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wrap_and_unwrap() {
+        let identity = Identity::generate();
+        let other = Identity::generate();
+        let file_key = [7u8; KEY_SIZE];
+
+        let stanza = identity.recipient().wrap(&file_key).unwrap();
+        assert_eq!(identity.unwrap(&stanza), Some(SecretKey::new(file_key)));
+        assert!(other.unwrap(&stanza).is_none());
+
+        // 文字列表現の往復
+        let parsed = Identity::parse(&identity.to_file_string()).unwrap();
+        assert_eq!(parsed.recipient(), identity.recipient());
+        assert_eq!(Recipient::parse(&identity.recipient().to_string()).unwrap(), identity.recipient());
+        assert!(Recipient::parse("sbr1zz").is_err());
+    }
+
+    #[test]
+    fn test_content_key_roundtrip() {
+        let key = ContentKey::generate();
+        let parsed = ContentKey::parse(&key.to_file_string()).unwrap();
+        assert_eq!(parsed.check_value(), key.check_value());
+        assert_ne!(ContentKey::generate().check_value(), key.check_value());
+        assert!(ContentKey::parse("SB-CONTENT-KEY-1ABCD").is_err());
+        assert!(!format!("{:?}", key).contains(&hex::encode_upper(&key.key()[..])));
+    }
+}
@@ -0,0 +1,91 @@
+//! 回復キー - パスワードを忘れた場合にマスター鍵を取り出すための鍵
+//!
+//! 256bit のランダムな鍵を BIP39 の英単語24語（チェックサム付き）で表し、
+//! 印刷して保管できるようにする。鍵自体が十分な強度を持つため、
+//! 包み込み鍵は Argon2id を使わず BLAKE3 で派生する。
+
+use super::{CryptoError, SecretKey, KEY_SIZE, REDACTED};
+use bip39::Mnemonic;
+use rand::Rng;
+use std::fmt;
+use zeroize::{Zeroize, ZeroizeOnDrop, Zeroizing};
+
+/// 回復キーから包み込み鍵を派生する際のコンテキスト
+const RECOVERY_KEY_CONTEXT: &str = "SecureBackup 2026-10 recovery key";
+
+/// 回復フレーズの単語数（256bit + 8bit チェックサム）
+pub const RECOVERY_WORD_COUNT: usize = 24;
+
+/// 回復キー（破棄時にメモリから消去する）
+#[derive(Zeroize, ZeroizeOnDrop)]
+pub struct RecoveryKey([u8; KEY_SIZE]);
+
+impl RecoveryKey {
+    /// ランダムな回復キーを生成
+    pub fn generate() -> Self {
+        let mut key = [0u8; KEY_SIZE];
+        rand::thread_rng().fill(&mut key);
+        Self(key)
+    }
+
+    /// 回復フレーズから作成（大文字小文字・空白の違いは無視し、チェックサムを検証する）
+    pub fn parse(phrase: &str) -> Result<Self, CryptoError> {
+        let words = phrase.split_whitespace().collect::<Vec<_>>();
+        if words.len() != RECOVERY_WORD_COUNT {
+            return Err(CryptoError::InvalidRecoveryPhrase);
+        }
+
+        let joined = Zeroizing::new(words.join(" "));
+        let normalized = Zeroizing::new(joined.to_lowercase());
+        let mnemonic = Mnemonic::parse_normalized(&normalized)
+            .map_err(|_| CryptoError::InvalidRecoveryPhrase)?;
+        let entropy = Zeroizing::new(mnemonic.to_entropy());
+        if entropy.len() != KEY_SIZE {
+            return Err(CryptoError::InvalidRecoveryPhrase);
+        }
+
+        let mut key = Self([0u8; KEY_SIZE]);
+        key.0.copy_from_slice(&entropy);
+        Ok(key)
+    }
+
+    /// 回復フレーズ（英単語24語、空白区切り）
+    pub fn phrase(&self) -> String {
+        Mnemonic::from_entropy(&self.0)
+            .expect("32バイトのエントロピーは常に有効")
+            .to_string()
+    }
+
+    /// キースロットの包み込み鍵
+    pub(super) fn wrapping_key(&self) -> SecretKey {
+        SecretKey::new(blake3::derive_key(RECOVERY_KEY_CONTEXT, &self.0))
+    }
+}
+
+impl fmt::Debug for RecoveryKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("RecoveryKey").field(&REDACTED).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_phrase_roundtrip() {
+        let key = RecoveryKey::generate();
+        let phrase = key.phrase();
+        assert_eq!(phrase.split(' ').count(), RECOVERY_WORD_COUNT);
+
+        // 大文字・改行を含んでも同じ鍵になる
+        let messy = phrase.to_uppercase().replacen(' ', "\n  ", 3);
+        assert_eq!(RecoveryKey::parse(&messy).unwrap().0, key.0);
+
+        // 全ゼロの鍵は "abandon" x23 + "art"、最後の語の誤りはチェックサムで検出される
+        let zero = RecoveryKey([0u8; KEY_SIZE]).phrase();
+        assert!(zero.ends_with("abandon art"));
+        assert!(RecoveryKey::parse(&zero.replace("art", "abandon")).is_err());
+        assert!(RecoveryKey::parse("correct horse battery staple").is_err());
+    }
+}
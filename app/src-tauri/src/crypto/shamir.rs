@@ -0,0 +1,280 @@
+//! 鍵の分割 - Shamir の秘密分散によるマスター鍵のエスクロー
+//!
+//! マスター鍵を N 個の断片に分け、そのうち任意の K 個で元に戻せるようにする。
+//! K-1 個以下の断片からはマスター鍵について何も分からないため、
+//! 管理者が1人だけではリポジトリを復号できない運用にできる。
+
+use super::{CryptoError, MasterKey, KEY_SIZE, REDACTED};
+use sharks::{Share, Sharks};
+use std::collections::BTreeMap;
+use std::fmt;
+use zeroize::{Zeroize, ZeroizeOnDrop, Zeroizing};
+
+/// 断片の文字列表現のプレフィックス
+const SHARE_PREFIX: &str = "SB-KEY-SHARE-1";
+
+/// 断片の形式バージョン
+const SHARE_VERSION: u8 = 1;
+
+/// マスター鍵から断片の照合用IDを派生する際のコンテキスト
+const KEY_ID_CONTEXT: &str = "SecureBackup 2026-10 key share id";
+
+/// 復元に必要な断片数の下限（1個で復元できる分割は鍵の複製と変わらないため認めない）
+const MIN_THRESHOLD: u8 = 2;
+
+/// 照合用IDのサイズ
+const KEY_ID_SIZE: usize = 8;
+
+/// 転記ミス検出用のチェックサムサイズ
+const CHECKSUM_SIZE: usize = 4;
+
+/// 断片のバイト列サイズ: version(1) + threshold(1) + total(1) + key_id(8) + x(1) + y(32) + checksum(4)
+const SHARE_SIZE: usize = 3 + KEY_ID_SIZE + 1 + KEY_SIZE + CHECKSUM_SIZE;
+
+/// マスター鍵の断片（破棄時にメモリから消去する）
+#[derive(Clone, Zeroize, ZeroizeOnDrop)]
+pub struct KeyShare {
+    /// 復元に必要な断片数（K）
+    pub threshold: u8,
+
+    /// 作成した断片の総数（N）
+    pub total: u8,
+
+    /// 断片の番号（1..=N）
+    pub index: u8,
+
+    /// 同じマスター鍵から作った断片かを照合するID
+    key_id: [u8; KEY_ID_SIZE],
+
+    /// 断片の値
+    value: [u8; KEY_SIZE],
+}
+
+impl KeyShare {
+    /// 文字列表現から作成（`#` で始まる行はコメント、チェックサムを検証する）
+    pub fn parse(s: &str) -> Result<Self, CryptoError> {
+        let line = s.lines()
+            .map(str::trim)
+            .find(|line| !line.is_empty() && !line.starts_with('#'))
+            .ok_or(CryptoError::InvalidKeyShare)?;
+        let hex = line.strip_prefix(SHARE_PREFIX).ok_or(CryptoError::InvalidKeyShare)?;
+        let decoded = Zeroizing::new(hex::decode(hex).map_err(|_| CryptoError::InvalidKeyShare)?);
+        let bytes: &[u8; SHARE_SIZE] = decoded.as_slice().try_into().map_err(|_| CryptoError::InvalidKeyShare)?;
+
+        let (body, checksum) = bytes.split_at(SHARE_SIZE - CHECKSUM_SIZE);
+        if checksum != &blake3::hash(body).as_bytes()[..CHECKSUM_SIZE] {
+            return Err(CryptoError::InvalidKeyShare);
+        }
+        if body[0] != SHARE_VERSION {
+            return Err(CryptoError::UnsupportedVersion(body[0]));
+        }
+
+        let mut key_id = [0u8; KEY_ID_SIZE];
+        key_id.copy_from_slice(&body[3..3 + KEY_ID_SIZE]);
+        let mut value = [0u8; KEY_SIZE];
+        value.copy_from_slice(&body[4 + KEY_ID_SIZE..]);
+
+        Ok(Self {
+            threshold: body[1],
+            total: body[2],
+            index: body[3 + KEY_ID_SIZE],
+            key_id,
+            value,
+        })
+    }
+
+    /// 文字列表現（1行）
+    pub fn encode(&self) -> String {
+        let mut body = Zeroizing::new(Vec::with_capacity(SHARE_SIZE));
+        body.extend_from_slice(&[SHARE_VERSION, self.threshold, self.total]);
+        body.extend_from_slice(&self.key_id);
+        body.push(self.index);
+        body.extend_from_slice(&self.value);
+        let checksum = blake3::hash(&body);
+        body.extend_from_slice(&checksum.as_bytes()[..CHECKSUM_SIZE]);
+
+        format!("{}{}", SHARE_PREFIX, hex::encode_upper(&body[..]))
+    }
+
+    /// 断片ファイルの内容（番号と必要数をコメントとして含む）
+    pub fn to_file_string(&self) -> String {
+        format!(
+            "# SecureBackup key share {} of {} (any {} shares restore the key)\n{}\n",
+            self.index,
+            self.total,
+            self.threshold,
+            self.encode(),
+        )
+    }
+}
+
+impl fmt::Debug for KeyShare {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("KeyShare")
+            .field("threshold", &self.threshold)
+            .field("total", &self.total)
+            .field("index", &self.index)
+            .field("value", &REDACTED)
+            .finish_non_exhaustive()
+    }
+}
+
+/// マスター鍵を `total` 個の断片に分割し、任意の `threshold` 個で復元できるようにする
+pub fn split_master_key(master: &MasterKey, threshold: u8, total: u8) -> Result<Vec<KeyShare>, CryptoError> {
+    if threshold < MIN_THRESHOLD || threshold > total {
+        return Err(CryptoError::InvalidShareThreshold { threshold, total });
+    }
+
+    let key_id = key_id(master);
+    Sharks(threshold)
+        .dealer(master.as_bytes())
+        .take(total as usize)
+        .map(|share| {
+            let mut value = [0u8; KEY_SIZE];
+            if share.y.len() != KEY_SIZE {
+                return Err(CryptoError::EncryptionFailed);
+            }
+            for (v, y) in value.iter_mut().zip(&share.y) {
+                *v = y.0;
+            }
+            let share = KeyShare {
+                threshold,
+                total,
+                index: share.x.0,
+                key_id,
+                value,
+            };
+            value.zeroize();
+            Ok(share)
+        })
+        .collect()
+}
+
+/// 断片を組み合わせてマスター鍵を復元
+///
+/// 別の鍵の断片の混在や断片の不足はエラーとする。
+pub fn combine_key_shares(shares: &[KeyShare]) -> Result<MasterKey, CryptoError> {
+    let first = shares.first().ok_or(CryptoError::NotEnoughKeyShares {
+        given: 0,
+        required: MIN_THRESHOLD as usize,
+    })?;
+    if shares.iter().any(|s| s.key_id != first.key_id || s.threshold != first.threshold) {
+        return Err(CryptoError::KeyShareMismatch);
+    }
+
+    // 同じ番号の断片は1つにまとめる（重複したまま補間すると項が打ち消し合う）。
+    // 同じ番号で値が異なる断片は混在として拒否する
+    let mut unique = BTreeMap::new();
+    for share in shares {
+        if let Some(existing) = unique.insert(share.index, share) {
+            if existing.value != share.value {
+                return Err(CryptoError::KeyShareMismatch);
+            }
+        }
+    }
+    if unique.len() < first.threshold as usize {
+        return Err(CryptoError::NotEnoughKeyShares {
+            given: unique.len(),
+            required: first.threshold as usize,
+        });
+    }
+
+    let shares = unique.values()
+        .map(|s| {
+            let mut bytes = Zeroizing::new(Vec::with_capacity(1 + KEY_SIZE));
+            bytes.push(s.index);
+            bytes.extend_from_slice(&s.value);
+            Share::try_from(bytes.as_slice()).map_err(|_| CryptoError::InvalidKeyShare)
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let key = Zeroizing::new(
+        Sharks(first.threshold)
+            .recover(&shares)
+            .map_err(|_| CryptoError::KeyShareMismatch)?,
+    );
+    let key: &[u8; KEY_SIZE] = key.as_slice().try_into().map_err(|_| CryptoError::KeyShareMismatch)?;
+
+    // 同じ番号で値の異なる断片などが混ざると別の鍵になるため、照合用IDで確認する
+    let master = MasterKey::from_bytes(key);
+    if key_id(&master) != first.key_id {
+        return Err(CryptoError::KeyShareMismatch);
+    }
+    Ok(master)
+}
+
+/// マスター鍵の照合用ID
+fn key_id(master: &MasterKey) -> [u8; KEY_ID_SIZE] {
+    let mut id = [0u8; KEY_ID_SIZE];
+    id.copy_from_slice(&master.derive_subkey(KEY_ID_CONTEXT)[..KEY_ID_SIZE]);
+    id
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::seq::SliceRandom;
+
+    #[test]
+    fn test_random_combinations() {
+        let mut rng = rand::thread_rng();
+
+        for _ in 0..20 {
+            let master = MasterKey::generate();
+            let total = *[2u8, 3, 5, 7, 10].choose(&mut rng).unwrap();
+            let threshold = (2..=total).collect::<Vec<_>>().choose(&mut rng).copied().unwrap();
+
+            let mut shares = split_master_key(&master, threshold, total).unwrap();
+            assert_eq!(shares.len(), total as usize);
+
+            // 文字列を経由した任意の K 個で復元できる
+            shares.shuffle(&mut rng);
+            let picked = shares[..threshold as usize].iter()
+                .map(|s| KeyShare::parse(&s.to_file_string()).unwrap())
+                .collect::<Vec<_>>();
+            assert_eq!(combine_key_shares(&picked).unwrap().as_bytes(), master.as_bytes());
+
+            // K-1 個では復元できない
+            assert!(matches!(
+                combine_key_shares(&picked[1..]),
+                Err(CryptoError::NotEnoughKeyShares { .. })
+            ));
+
+            // 同じ断片を重ねて指定しても復元できる（文字列とファイルの両方で指定した場合など）
+            let mut duplicated = picked.clone();
+            duplicated.insert(1, picked[0].clone());
+            assert_eq!(combine_key_shares(&duplicated).unwrap().as_bytes(), master.as_bytes());
+            assert!(matches!(
+                combine_key_shares(&duplicated[..threshold as usize]),
+                Err(CryptoError::NotEnoughKeyShares { .. })
+            ));
+        }
+    }
+
+    #[test]
+    fn test_rejects_foreign_and_corrupted_shares() {
+        let shares = split_master_key(&MasterKey::generate(), 2, 3).unwrap();
+        let other = split_master_key(&MasterKey::generate(), 2, 3).unwrap();
+
+        let mixed = [shares[0].clone(), other[1].clone()];
+        assert!(matches!(combine_key_shares(&mixed), Err(CryptoError::KeyShareMismatch)));
+
+        // 同じ番号で値の異なる断片は混在として拒否する
+        let mut forged = shares[1].clone();
+        forged.value[0] ^= 1;
+        assert!(matches!(
+            combine_key_shares(&[shares[0].clone(), shares[1].clone(), forged]),
+            Err(CryptoError::KeyShareMismatch)
+        ));
+
+        // 1文字の転記ミスはチェックサムで検出される
+        let encoded = shares[0].encode();
+        let last = encoded.chars().last().unwrap();
+        let typo = format!("{}{}", &encoded[..encoded.len() - 1], if last == '0' { '1' } else { '0' });
+        assert!(matches!(KeyShare::parse(&typo), Err(CryptoError::InvalidKeyShare)));
+
+        assert!(matches!(
+            split_master_key(&MasterKey::generate(), 1, 3),
+            Err(CryptoError::InvalidShareThreshold { .. })
+        ));
+    }
+}
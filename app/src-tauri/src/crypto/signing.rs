@@ -0,0 +1,166 @@
+//! スナップショット署名 - Ed25519 によるスナップショットの出所証明
+//!
+//! バックアップジョブごとに署名鍵を持ち、書き込んだスナップショットに署名する。
+//! 検証側は信頼する公開鍵の一覧と照合し、どのマシンが作成したか・以後改ざんされていないかを確認する。
+//! 署名は保存したバイト列（暗号化リポジトリでは暗号文）に対して行うため、復号せずに検証できる。
+
+use super::{CryptoError, REDACTED};
+use ed25519_dalek::{Signature, Signer, SigningKey, VerifyingKey, SIGNATURE_LENGTH};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use zeroize::Zeroizing;
+
+/// 署名者（公開鍵）の文字列表現のプレフィックス
+const SIGNER_PREFIX: &str = "sbs1";
+
+/// 署名鍵（秘密鍵）の文字列表現のプレフィックス
+const SIGNING_KEY_PREFIX: &str = "SB-SIGNING-KEY-1";
+
+/// 署名対象のダイジェストを計算する際のコンテキスト
+const SIGNATURE_CONTEXT: &str = "SecureBackup 2026-10 snapshot signature";
+
+/// 署名者（Ed25519 公開鍵）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct SignerKey(VerifyingKey);
+
+impl SignerKey {
+    /// 文字列表現（`sbs1<hex>`）から作成
+    pub fn parse(s: &str) -> Result<Self, CryptoError> {
+        let hex = s.trim().strip_prefix(SIGNER_PREFIX).ok_or(CryptoError::InvalidKey)?;
+        let bytes: [u8; 32] = hex::decode(hex)
+            .ok()
+            .and_then(|b| b.try_into().ok())
+            .ok_or(CryptoError::InvalidKey)?;
+        VerifyingKey::from_bytes(&bytes)
+            .map(Self)
+            .map_err(|_| CryptoError::InvalidKey)
+    }
+
+    /// 署名を検証
+    ///
+    /// # Arguments
+    /// * `context` - 署名時と同じ保存先を表す文字列
+    /// * `data` - 署名対象のバイト列
+    /// * `signature` - 署名（hex）
+    pub fn verify(&self, context: &str, data: &[u8], signature: &str) -> Result<(), CryptoError> {
+        let bytes: [u8; SIGNATURE_LENGTH] = hex::decode(signature)
+            .ok()
+            .and_then(|b| b.try_into().ok())
+            .ok_or(CryptoError::InvalidSignature)?;
+        self.0
+            .verify_strict(&signed_digest(context, data), &Signature::from_bytes(&bytes))
+            .map_err(|_| CryptoError::InvalidSignature)
+    }
+}
+
+impl fmt::Display for SignerKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", SIGNER_PREFIX, hex::encode(self.0.as_bytes()))
+    }
+}
+
+impl From<SignerKey> for String {
+    fn from(key: SignerKey) -> Self {
+        key.to_string()
+    }
+}
+
+impl TryFrom<String> for SignerKey {
+    type Error = CryptoError;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        Self::parse(&s)
+    }
+}
+
+/// スナップショットの署名鍵（Ed25519 秘密鍵、破棄時にメモリから消去される）
+#[derive(Clone)]
+pub struct SnapshotSigner(SigningKey);
+
+impl SnapshotSigner {
+    /// 新しい署名鍵を生成
+    pub fn generate() -> Self {
+        let mut secret = Zeroizing::new([0u8; 32]);
+        rand::thread_rng().fill(&mut secret[..]);
+        Self(SigningKey::from_bytes(&secret))
+    }
+
+    /// 対応する署名者（公開鍵）
+    pub fn signer_key(&self) -> SignerKey {
+        SignerKey(self.0.verifying_key())
+    }
+
+    /// 署名鍵ファイルの内容から作成（`#` で始まる行はコメント）
+    pub fn parse(s: &str) -> Result<Self, CryptoError> {
+        let line = s.lines()
+            .map(str::trim)
+            .find(|line| !line.is_empty() && !line.starts_with('#'))
+            .ok_or(CryptoError::InvalidKey)?;
+        let hex = line.strip_prefix(SIGNING_KEY_PREFIX).ok_or(CryptoError::InvalidKey)?;
+        let decoded = Zeroizing::new(hex::decode(hex).map_err(|_| CryptoError::InvalidKey)?);
+        let bytes: &[u8; 32] = decoded.as_slice().try_into().map_err(|_| CryptoError::InvalidKey)?;
+        Ok(Self(SigningKey::from_bytes(bytes)))
+    }
+
+    /// 署名鍵ファイルの内容（公開鍵をコメントとして含む）
+    pub fn to_file_string(&self) -> Zeroizing<String> {
+        Zeroizing::new(format!(
+            "# SecureBackup snapshot signing key\n# public key: {}\n{}{}\n",
+            self.signer_key(),
+            SIGNING_KEY_PREFIX,
+            hex::encode_upper(Zeroizing::new(self.0.to_bytes()).as_slice()),
+        ))
+    }
+
+    /// 署名を作成
+    ///
+    /// # Returns
+    /// 署名（hex）
+    pub fn sign(&self, context: &str, data: &[u8]) -> String {
+        hex::encode(self.0.sign(&signed_digest(context, data)).to_bytes())
+    }
+}
+
+impl fmt::Debug for SnapshotSigner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("SnapshotSigner").field(&self.signer_key().to_string()).field(&REDACTED).finish()
+    }
+}
+
+/// 署名対象のダイジェスト（保存先を表すコンテキストも結び付け、別の保存先への差し替えを検出する）
+fn signed_digest(context: &str, data: &[u8]) -> [u8; 32] {
+    let mut hasher = blake3::Hasher::new_derive_key(SIGNATURE_CONTEXT);
+    hasher.update(&(context.len() as u64).to_le_bytes());
+    hasher.update(context.as_bytes());
+    hasher.update(data);
+    *hasher.finalize().as_bytes()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sign_and_verify() {
+        let signer = SnapshotSigner::generate();
+        let key = signer.signer_key();
+        let signature = signer.sign("snapshot:a", b"manifest");
+
+        assert!(key.verify("snapshot:a", b"manifest", &signature).is_ok());
+        // 内容・保存先・鍵が異なれば失敗する
+        assert!(key.verify("snapshot:a", b"manifest!", &signature).is_err());
+        assert!(key.verify("snapshot:b", b"manifest", &signature).is_err());
+        assert!(SnapshotSigner::generate().signer_key().verify("snapshot:a", b"manifest", &signature).is_err());
+        assert!(key.verify("snapshot:a", b"manifest", "00").is_err());
+
+        // 文字列表現の往復
+        let parsed = SnapshotSigner::parse(&signer.to_file_string()).unwrap();
+        assert_eq!(parsed.signer_key(), key);
+        assert_eq!(SignerKey::parse(&key.to_string()).unwrap(), key);
+        assert_eq!(serde_json::from_value::<SignerKey>(serde_json::to_value(key).unwrap()).unwrap(), key);
+        assert!(SignerKey::parse("sbs1zz").is_err());
+        assert!(!format!("{:?}", signer).contains(&hex::encode_upper(signer.0.to_bytes())));
+    }
+}
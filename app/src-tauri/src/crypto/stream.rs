@@ -0,0 +1,285 @@
+//! ストリーム暗号化 - セグメント単位の AEAD（STREAM 構成）
+//!
+//! 平文を固定長セグメントに分割し、セグメントごとに認証付き暗号化する。
+//! nonce は [prefix][counter(4, BE)][last_flag(1)] で、カウンタにより
+//! セグメントの並べ替えを、最終フラグにより末尾の切り詰めを検出する。
+//! 全セグメントに同じ関連データ（AAD）を付け、ヘッダや保存先との結び付きを認証する。
+//! プレフィックスの長さは暗号スイートの nonce サイズによる（96bit なら 7、192bit なら 19 バイト）。
+//!
+//! チャンクごとに1ストリームとなるため、1つのデータ鍵で数百万のストリームを暗号化する。
+//! 96bit の nonce ではランダムなプレフィックスが 56bit しかなく、数千ストリームで衝突確率が
+//! 2^-32 を超える（GCM では衝突1回で認証が破れる）。そのためストリームごとにランダムな
+//! 256bit ソルトをヘッダに記録し、データ鍵とソルトから派生したサブ鍵で暗号化する（HKDF 方式の STREAM 構成）。
+//! メモリ使用量はファイルサイズによらずセグメント1つ分に収まる。
+
+use super::{AeadCipher, CipherSuite, CryptoError, SecretKey, KEY_SIZE};
+use zeroize::Zeroizing;
+use std::io::{self, Read, Write};
+
+/// 平文セグメントサイズ（64KiB）
+pub const SEGMENT_SIZE: usize = 64 * 1024;
+
+/// ストリームごとのサブ鍵のソルトサイズ
+pub const STREAM_SALT_SIZE: usize = 32;
+
+/// サブ鍵を派生する際のコンテキスト
+const STREAM_KEY_CONTEXT: &str = "SecureBackup 2026-10 stream subkey";
+
+/// nonce のうちカウンタと最終フラグが占めるサイズ
+const NONCE_SUFFIX_SIZE: usize = 4 + 1;
+
+/// 認証タグサイズ
+const TAG_SIZE: usize = 16;
+
+/// 暗号化セグメントサイズ
+const ENCRYPTED_SEGMENT_SIZE: usize = SEGMENT_SIZE + TAG_SIZE;
+
+/// 暗号スイートに対応する nonce プレフィックスサイズ
+pub fn nonce_prefix_size(suite: CipherSuite) -> usize {
+    suite.nonce_size() - NONCE_SUFFIX_SIZE
+}
+
+/// データ鍵とストリームのソルトからサブ鍵を派生
+pub(super) fn stream_key(key: &[u8; KEY_SIZE], salt: &[u8; STREAM_SALT_SIZE]) -> SecretKey {
+    let mut material = Zeroizing::new([0u8; KEY_SIZE + STREAM_SALT_SIZE]);
+    material[..KEY_SIZE].copy_from_slice(key);
+    material[KEY_SIZE..].copy_from_slice(salt);
+    SecretKey::new(blake3::derive_key(STREAM_KEY_CONTEXT, &material[..]))
+}
+
+/// セグメントの nonce を構築
+fn segment_nonce(prefix: &[u8], counter: u32, last: bool) -> Vec<u8> {
+    let mut nonce = Vec::with_capacity(prefix.len() + NONCE_SUFFIX_SIZE);
+    nonce.extend_from_slice(prefix);
+    nonce.extend_from_slice(&counter.to_be_bytes());
+    nonce.push(last as u8);
+    nonce
+}
+
+/// 暗号化エラーを IO エラーに変換
+fn crypto_io_error(e: CryptoError) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, e)
+}
+
+/// ストリーム暗号化ライター
+///
+/// 書き込まれた平文をセグメント単位で暗号化して内部ライターに出力する。
+/// 最終セグメントを書き出すため、必ず [`StreamEncryptor::finish`] を呼ぶこと。
+pub struct StreamEncryptor<W: Write> {
+    inner: W,
+    cipher: AeadCipher,
+    prefix: Vec<u8>,
+    aad: Vec<u8>,
+    counter: u32,
+    buffer: Vec<u8>,
+}
+
+impl<W: Write> StreamEncryptor<W> {
+    /// 新しいストリーム暗号化ライターを作成（ヘッダは呼び出し側で書き込み済みとする）
+    pub(super) fn new(
+        inner: W,
+        suite: CipherSuite,
+        key: &[u8; KEY_SIZE],
+        prefix: Vec<u8>,
+        aad: Vec<u8>,
+    ) -> Result<Self, CryptoError> {
+        if prefix.len() != nonce_prefix_size(suite) {
+            return Err(CryptoError::EncryptionFailed);
+        }
+
+        Ok(Self {
+            inner,
+            cipher: suite.cipher(key),
+            prefix,
+            aad,
+            counter: 0,
+            buffer: Vec::with_capacity(SEGMENT_SIZE),
+        })
+    }
+
+    /// バッファ中のセグメントを暗号化して出力
+    fn flush_segment(&mut self, last: bool) -> io::Result<()> {
+        let nonce = segment_nonce(&self.prefix, self.counter, last);
+        let ciphertext = self.cipher
+            .encrypt(&nonce, &self.buffer, &self.aad)
+            .map_err(crypto_io_error)?;
+
+        self.inner.write_all(&ciphertext)?;
+        self.buffer.clear();
+        self.counter = self.counter
+            .checked_add(1)
+            .ok_or_else(|| crypto_io_error(CryptoError::EncryptionFailed))?;
+        Ok(())
+    }
+
+    /// 最終セグメントを書き出して内部ライターを返す
+    pub fn finish(mut self) -> io::Result<W> {
+        self.flush_segment(true)?;
+        self.inner.flush()?;
+        Ok(self.inner)
+    }
+}
+
+impl<W: Write> Write for StreamEncryptor<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        // 最終セグメントは finish で書き出すため、満杯になっても次の書き込みまで保持する
+        if self.buffer.len() == SEGMENT_SIZE {
+            self.flush_segment(false)?;
+        }
+
+        let n = buf.len().min(SEGMENT_SIZE - self.buffer.len());
+        self.buffer.extend_from_slice(&buf[..n]);
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// ストリーム復号リーダー
+pub struct StreamDecryptor<R: Read> {
+    inner: R,
+    cipher: AeadCipher,
+    prefix: Vec<u8>,
+    aad: Vec<u8>,
+    counter: u32,
+    /// 復号済みの平文
+    plaintext: Vec<u8>,
+    /// plaintext の読み出し位置
+    position: usize,
+    /// 先読みした次セグメントの先頭バイト
+    lookahead: Option<u8>,
+    /// 最終セグメントを処理済みか
+    finished: bool,
+}
+
+impl<R: Read> StreamDecryptor<R> {
+    /// 新しいストリーム復号リーダーを作成（ヘッダは呼び出し側で読み込み済みとする）
+    pub(super) fn new(
+        inner: R,
+        suite: CipherSuite,
+        key: &[u8; KEY_SIZE],
+        prefix: Vec<u8>,
+        aad: Vec<u8>,
+    ) -> Result<Self, CryptoError> {
+        if prefix.len() != nonce_prefix_size(suite) {
+            return Err(CryptoError::InvalidFormat);
+        }
+
+        Ok(Self {
+            inner,
+            cipher: suite.cipher(key),
+            prefix,
+            aad,
+            counter: 0,
+            plaintext: Vec::new(),
+            position: 0,
+            lookahead: None,
+            finished: false,
+        })
+    }
+
+    /// 次のセグメントを読み込んで復号
+    fn next_segment(&mut self) -> io::Result<()> {
+        let mut segment = Vec::with_capacity(ENCRYPTED_SEGMENT_SIZE);
+        segment.extend(self.lookahead.take());
+        (&mut self.inner)
+            .take((ENCRYPTED_SEGMENT_SIZE - segment.len()) as u64)
+            .read_to_end(&mut segment)?;
+
+        // 1バイト先読みして、これが最終セグメントかを判定
+        let mut next = [0u8; 1];
+        let last = loop {
+            match self.inner.read(&mut next) {
+                Ok(0) => break true,
+                Ok(_) => {
+                    self.lookahead = Some(next[0]);
+                    break false;
+                }
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            }
+        };
+
+        if segment.len() < TAG_SIZE || (!last && segment.len() != ENCRYPTED_SEGMENT_SIZE) {
+            return Err(crypto_io_error(CryptoError::InvalidFormat));
+        }
+
+        let nonce = segment_nonce(&self.prefix, self.counter, last);
+        self.plaintext = self.cipher
+            .decrypt(&nonce, &segment, &self.aad)
+            .map_err(crypto_io_error)?;
+        self.position = 0;
+        self.finished = last;
+        self.counter = self.counter
+            .checked_add(1)
+            .ok_or_else(|| crypto_io_error(CryptoError::InvalidFormat))?;
+        Ok(())
+    }
+}
+
+impl<R: Read> Read for StreamDecryptor<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.position == self.plaintext.len() {
+            if self.finished {
+                return Ok(0);
+            }
+            self.next_segment()?;
+        }
+
+        let n = buf.len().min(self.plaintext.len() - self.position);
+        buf[..n].copy_from_slice(&self.plaintext[self.position..self.position + n]);
+        self.position += n;
+        Ok(n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn roundtrip(suite: CipherSuite, len: usize) -> Vec<u8> {
+        let key = [3u8; 32];
+        let prefix = vec![5u8; nonce_prefix_size(suite)];
+        let plaintext: Vec<u8> = (0..len).map(|i| (i % 251) as u8).collect();
+
+        let mut writer = StreamEncryptor::new(Vec::new(), suite, &key, prefix.clone(), b"aad".to_vec()).unwrap();
+        writer.write_all(&plaintext).unwrap();
+        let encrypted = writer.finish().unwrap();
+
+        let mut reader = StreamDecryptor::new(encrypted.as_slice(), suite, &key, prefix.clone(), b"aad".to_vec()).unwrap();
+        let mut decrypted = Vec::new();
+        reader.read_to_end(&mut decrypted).unwrap();
+        assert_eq!(decrypted, plaintext);
+
+        // 関連データが異なれば復号できない
+        let mut reader = StreamDecryptor::new(encrypted.as_slice(), suite, &key, prefix, b"other".to_vec()).unwrap();
+        assert!(reader.read_to_end(&mut Vec::new()).is_err());
+        encrypted
+    }
+
+    #[test]
+    fn test_stream_roundtrip() {
+        for suite in [CipherSuite::Aes256Gcm, CipherSuite::ChaCha20Poly1305, CipherSuite::XChaCha20Poly1305] {
+            for len in [0, 1, SEGMENT_SIZE - 1, SEGMENT_SIZE, SEGMENT_SIZE + 1, 3 * SEGMENT_SIZE] {
+                roundtrip(suite, len);
+            }
+        }
+    }
+
+    #[test]
+    fn test_stream_truncation_detected() {
+        let key = [3u8; 32];
+        let suite = CipherSuite::default();
+        let prefix = vec![5u8; nonce_prefix_size(suite)];
+
+        // セグメント境界で切り詰めても最終フラグが一致せず失敗する
+        let encrypted = roundtrip(suite, 2 * SEGMENT_SIZE + 10);
+        let truncated = &encrypted[..2 * ENCRYPTED_SEGMENT_SIZE];
+
+        let mut reader = StreamDecryptor::new(truncated, suite, &key, prefix, b"aad".to_vec()).unwrap();
+        let mut out = Vec::new();
+        assert!(reader.read_to_end(&mut out).is_err());
+    }
+}
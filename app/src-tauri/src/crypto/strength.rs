@@ -0,0 +1,1024 @@
+//! パスワード強度の推定（zxcvbn 方式）
+//!
+//! パスワードを辞書の単語・キーボードの並び・繰り返し・連続・日付などのパターンに分解し、
+//! 攻撃者が試す推測回数が最小になる分解から強度を見積もる。
+//! 文字種の数ではなく推測回数で評価するため、`Password1!` のようなパスワードは弱いと判定される。
+
+use super::{CryptoError, PasswordStrength};
+use chrono::{Datelike, Utc};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+/// リポジトリ作成時に要求するパスワードの最小文字数
+pub const MIN_PASSWORD_LENGTH: usize = 8;
+
+/// パターン解析の対象とする最大文字数（超えた分は総当たりとして数える）
+const MAX_ANALYZED_CHARS: usize = 64;
+
+/// 辞書照合する単語の最大文字数
+const MAX_WORD_CHARS: usize = 20;
+
+/// 解読時間の見積もりに使う推測速度（回/秒、低速なパスワードハッシュへのオフライン攻撃）
+const GUESSES_PER_SECOND: f64 = 1e4;
+
+/// 総当たりの1文字あたりの候補数
+const BRUTEFORCE_CARDINALITY: f64 = 10.0;
+
+/// 部分パターンの最小推測回数（1文字・複数文字）
+const MIN_GUESSES_SINGLE_CHAR: f64 = 10.0;
+const MIN_GUESSES_MULTI_CHAR: f64 = 50.0;
+
+/// パターン数が増えるごとに加える推測回数の基数
+const MIN_GUESSES_BEFORE_GROWING_SEQUENCE: f64 = 10_000.0;
+
+/// 日付として扱う年の範囲と、年の推測空間の最小値
+const DATE_MIN_YEAR: i32 = 1000;
+const DATE_MAX_YEAR: i32 = 2050;
+const MIN_YEAR_SPACE: f64 = 20.0;
+
+/// l33t 置き換えの組み合わせ数の上限
+const MAX_L33T_VARIANTS: usize = 16;
+
+// 辞書は zxcvbn の頻度リスト（MIT License、wordlists/LICENSE）から作成。
+// 各単語は最も順位の高いリストにのみ含まれる。
+
+/// よく使われるパスワード（漏洩したパスワードの頻度順、30,000語）
+const COMMON_PASSWORDS: &str = include_str!("wordlists/passwords.txt");
+
+/// 英単語（英語版 Wikipedia の出現頻度順、30,000語）
+const ENGLISH_WORDS: &str = include_str!("wordlists/words.txt");
+
+/// 米国のテレビ・映画の字幕に現れる単語（頻度順）
+const TV_AND_FILM_WORDS: &str = include_str!("wordlists/tv_and_film.txt");
+
+/// 米国の国勢調査の名（女性・男性）と姓（頻度順）
+const FEMALE_NAMES: &str = include_str!("wordlists/female_names.txt");
+const MALE_NAMES: &str = include_str!("wordlists/male_names.txt");
+const SURNAMES: &str = include_str!("wordlists/surnames.txt");
+
+/// キーボード配列（QWERTY、各行の非シフト文字・シフト文字・行の横方向のずれ）
+const KEYBOARD_ROWS: [(&str, &str, f64); 4] = [
+    ("`1234567890-=", "~!@#$%^&*()_+", 0.0),
+    ("qwertyuiop[]\\", "QWERTYUIOP{}|", 1.5),
+    ("asdfghjkl;'", "ASDFGHJKL:\"", 1.75),
+    ("zxcvbnm,./", "ZXCVBNM<>?", 2.25),
+];
+
+/// 辞書の種類
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Dictionary {
+    /// よく使われるパスワード
+    CommonPasswords,
+    /// 英単語
+    EnglishWords,
+    /// テレビ・映画に現れる単語
+    TvAndFilm,
+    /// 名
+    FirstNames,
+    /// 姓
+    Surnames,
+}
+
+/// 検出したパターン
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "pattern", rename_all = "snake_case")]
+pub enum Pattern {
+    /// 辞書の単語（逆綴り・l33t 置き換えを含む）
+    Dictionary {
+        dictionary: Dictionary,
+        rank: usize,
+        reversed: bool,
+        l33t: bool,
+    },
+    /// キーボード上で隣り合うキーの並び
+    Spatial { turns: usize, shifted: usize },
+    /// 同じ文字・文字列の繰り返し
+    Repeat { unit_chars: usize, count: usize },
+    /// 一定間隔の文字の連続（abc、9753 など）
+    Sequence { ascending: bool },
+    /// 日付
+    Date { year: i32, separator: bool },
+    /// 年
+    Year { year: i32 },
+    /// いずれのパターンにも当てはまらない部分
+    Bruteforce,
+}
+
+/// パスワード中のパターンの一致（位置は文字単位、パスワードの文字列自体は保持しない）
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PatternMatch {
+    /// 開始位置
+    pub start: usize,
+    /// 終了位置（この位置を含む）
+    pub end: usize,
+    /// このパターンの推測回数
+    pub guesses: f64,
+    /// パターン
+    #[serde(flatten)]
+    pub pattern: Pattern,
+}
+
+impl PatternMatch {
+    fn len(&self) -> usize {
+        self.end - self.start + 1
+    }
+}
+
+/// パスワード強度の推定結果
+#[derive(Debug, Clone, Serialize)]
+pub struct PasswordEstimate {
+    /// 推定推測回数
+    pub guesses: f64,
+    /// 推定推測回数の常用対数
+    pub guesses_log10: f64,
+    /// スコア（0〜4）
+    pub score: u8,
+    /// 解読に要する推定時間（秒、低速なパスワードハッシュへのオフライン攻撃）
+    pub crack_time_secs: f64,
+    /// 推測回数が最小になるパターンの分解
+    pub matches: Vec<PatternMatch>,
+    /// 主な弱点
+    pub warning: Option<String>,
+    /// 改善の提案
+    pub suggestions: Vec<String>,
+}
+
+impl PasswordEstimate {
+    /// 3段階の強度
+    pub fn strength(&self) -> PasswordStrength {
+        match self.score {
+            0..=2 => PasswordStrength::Weak,
+            3 => PasswordStrength::Medium,
+            _ => PasswordStrength::Strong,
+        }
+    }
+
+    /// 解読に要する推定時間（表示用）
+    pub fn crack_time_display(&self) -> String {
+        const MINUTE: f64 = 60.0;
+        const HOUR: f64 = MINUTE * 60.0;
+        const DAY: f64 = HOUR * 24.0;
+        const MONTH: f64 = DAY * 31.0;
+        const YEAR: f64 = MONTH * 12.0;
+        const CENTURY: f64 = YEAR * 100.0;
+
+        let secs = self.crack_time_secs;
+        if secs < 1.0 {
+            "1秒未満".to_string()
+        } else if secs < MINUTE {
+            format!("{}秒", secs.round())
+        } else if secs < HOUR {
+            format!("{}分", (secs / MINUTE).round())
+        } else if secs < DAY {
+            format!("{}時間", (secs / HOUR).round())
+        } else if secs < MONTH {
+            format!("{}日", (secs / DAY).round())
+        } else if secs < YEAR {
+            format!("{}か月", (secs / MONTH).round())
+        } else if secs < CENTURY {
+            format!("{}年", (secs / YEAR).round())
+        } else {
+            "数世紀以上".to_string()
+        }
+    }
+}
+
+/// パスワードの強度を推定
+pub fn estimate_password(password: &str) -> PasswordEstimate {
+    let chars: Vec<char> = password.chars().collect();
+    let analyzed = &chars[..chars.len().min(MAX_ANALYZED_CHARS)];
+
+    let (guesses, matches) = most_guessable(analyzed);
+    let guesses = guesses * BRUTEFORCE_CARDINALITY.powi((chars.len() - analyzed.len()) as i32);
+    let score = score_for(guesses);
+    let (warning, suggestions) = feedback(analyzed, &matches, score);
+
+    PasswordEstimate {
+        guesses,
+        guesses_log10: guesses.log10(),
+        score,
+        crack_time_secs: guesses / GUESSES_PER_SECOND,
+        matches,
+        warning,
+        suggestions,
+    }
+}
+
+/// 新しいパスワードが最低限の条件（文字数）を満たすか確認
+pub fn check_password_policy(password: &str) -> Result<(), CryptoError> {
+    if password.chars().count() < MIN_PASSWORD_LENGTH {
+        return Err(CryptoError::PasswordTooShort);
+    }
+    Ok(())
+}
+
+/// 推測回数からスコア（0〜4）を求める
+fn score_for(guesses: f64) -> u8 {
+    const DELTA: f64 = 5.0;
+    if guesses < 1e3 + DELTA {
+        0
+    } else if guesses < 1e6 + DELTA {
+        1
+    } else if guesses < 1e8 + DELTA {
+        2
+    } else if guesses < 1e10 + DELTA {
+        3
+    } else {
+        4
+    }
+}
+
+/// 推測回数が最小になるパターンの分解を求める
+///
+/// 末尾位置・パターン数ごとに推測回数の積の最小値を動的計画法で求め、
+/// パターン数 l に対して `l! * 積 + 基数^(l-1)` が最小になる分解を選ぶ。
+fn most_guessable(chars: &[char]) -> (f64, Vec<PatternMatch>) {
+    let n = chars.len();
+    if n == 0 {
+        return (1.0, Vec::new());
+    }
+
+    let mut candidates = omnimatch(chars);
+    for m in &mut candidates {
+        let min = if m.len() == n {
+            1.0
+        } else if m.len() == 1 {
+            MIN_GUESSES_SINGLE_CHAR
+        } else {
+            MIN_GUESSES_MULTI_CHAR
+        };
+        m.guesses = m.guesses.max(min);
+    }
+
+    let mut by_end: Vec<Vec<usize>> = vec![Vec::new(); n];
+    for (i, m) in candidates.iter().enumerate() {
+        by_end[m.end].push(i);
+    }
+
+    // best[k][l] = 位置 k で終わる l 個のパターンの推測回数の積の最小値と、最後のパターン
+    let mut best: Vec<Vec<Option<(f64, Candidate)>>> = vec![vec![None; n + 1]; n];
+    for (k, ends) in by_end.iter().enumerate() {
+        for &i in ends {
+            let m = &candidates[i];
+            extend(&mut best, m.start, k, m.guesses, Candidate::Match(i));
+        }
+        for start in 0..=k {
+            extend(&mut best, start, k, bruteforce_guesses(k - start + 1, n), Candidate::Bruteforce(start));
+        }
+    }
+
+    let (l, guesses) = (1..=n)
+        .filter_map(|l| best[n - 1][l].map(|(product, _)| (l, sequence_guesses(l, product))))
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .expect("総当たりの分解は常に存在する");
+
+    let mut matches = Vec::with_capacity(l);
+    let (mut k, mut l) = (n - 1, l);
+    while l > 0 {
+        let (_, candidate) = best[k][l].expect("分解の途中の状態は記録済み");
+        let m = match candidate {
+            Candidate::Match(i) => candidates[i].clone(),
+            Candidate::Bruteforce(start) => PatternMatch {
+                start,
+                end: k,
+                guesses: bruteforce_guesses(k - start + 1, n),
+                pattern: Pattern::Bruteforce,
+            },
+        };
+        l -= 1;
+        if m.start > 0 {
+            k = m.start - 1;
+        }
+        matches.push(m);
+    }
+    matches.reverse();
+
+    (guesses, matches)
+}
+
+/// 分解の候補（検出したパターンまたは総当たり）
+#[derive(Debug, Clone, Copy)]
+enum Candidate {
+    Match(usize),
+    Bruteforce(usize),
+}
+
+/// `start..=end` の候補を、`start` の直前で終わる分解の後ろに追加する
+fn extend(best: &mut [Vec<Option<(f64, Candidate)>>], start: usize, end: usize, guesses: f64, candidate: Candidate) {
+    if start == 0 {
+        relax(&mut best[end][1], guesses, candidate);
+        return;
+    }
+    for l in 1..=start {
+        if let Some((product, _)) = best[start - 1][l] {
+            relax(&mut best[end][l + 1], product * guesses, candidate);
+        }
+    }
+}
+
+fn relax(slot: &mut Option<(f64, Candidate)>, product: f64, candidate: Candidate) {
+    if slot.is_none_or(|(current, _)| product < current) {
+        *slot = Some((product, candidate));
+    }
+}
+
+/// l 個のパターンからなる分解の推測回数
+fn sequence_guesses(l: usize, product: f64) -> f64 {
+    factorial(l) * product + MIN_GUESSES_BEFORE_GROWING_SEQUENCE.powi(l as i32 - 1)
+}
+
+/// 総当たりの推測回数
+fn bruteforce_guesses(len: usize, total: usize) -> f64 {
+    let guesses = BRUTEFORCE_CARDINALITY.powi(len as i32);
+    if len == total {
+        guesses
+    } else if len == 1 {
+        guesses.max(MIN_GUESSES_SINGLE_CHAR + 1.0)
+    } else {
+        guesses.max(MIN_GUESSES_MULTI_CHAR + 1.0)
+    }
+}
+
+/// すべての検出器でパターンを探す
+fn omnimatch(chars: &[char]) -> Vec<PatternMatch> {
+    let lower: Vec<char> = chars.iter().map(|c| c.to_lowercase().next().unwrap_or(*c)).collect();
+
+    let mut matches = dictionary_matches(chars, &lower);
+    matches.extend(spatial_matches(chars));
+    matches.extend(repeat_matches(chars));
+    matches.extend(sequence_matches(chars));
+    matches.extend(date_matches(chars));
+    matches
+}
+
+// ========================================
+// 辞書
+// ========================================
+
+/// 辞書ごとの単語と順位
+fn ranked_dictionaries() -> &'static [(Dictionary, HashMap<&'static str, usize>)] {
+    static DICTIONARIES: OnceLock<Vec<(Dictionary, HashMap<&'static str, usize>)>> = OnceLock::new();
+    DICTIONARIES.get_or_init(|| {
+        let rank = |list: &'static str| {
+            let mut ranked = HashMap::new();
+            for (i, word) in list.lines().map(str::trim).filter(|w| !w.is_empty()).enumerate() {
+                ranked.entry(word).or_insert(i + 1);
+            }
+            ranked
+        };
+        vec![
+            (Dictionary::CommonPasswords, rank(COMMON_PASSWORDS)),
+            (Dictionary::EnglishWords, rank(ENGLISH_WORDS)),
+            (Dictionary::TvAndFilm, rank(TV_AND_FILM_WORDS)),
+            (Dictionary::FirstNames, rank(FEMALE_NAMES)),
+            (Dictionary::FirstNames, rank(MALE_NAMES)),
+            (Dictionary::Surnames, rank(SURNAMES)),
+        ]
+    })
+}
+
+/// l33t 置き換えの元の文字の候補
+fn l33t_letters(c: char) -> &'static [char] {
+    match c {
+        '4' | '@' => &['a'],
+        '8' => &['b'],
+        '(' | '{' | '[' | '<' => &['c'],
+        '3' => &['e'],
+        '6' | '9' => &['g'],
+        '1' | '|' => &['i', 'l'],
+        '!' => &['i'],
+        '0' => &['o'],
+        '$' | '5' => &['s'],
+        '+' => &['t'],
+        '7' => &['t', 'l'],
+        '%' => &['x'],
+        '2' => &['z'],
+        _ => &[],
+    }
+}
+
+/// l33t 置き換えを戻した候補（置き換えた文字の組を伴う）
+fn unl33t_variants(token: &[char]) -> Vec<(String, Vec<(char, char)>)> {
+    let mut variants = vec![(String::new(), Vec::new())];
+    for &c in token {
+        let letters = l33t_letters(c);
+        if letters.is_empty() {
+            for (word, _) in &mut variants {
+                word.push(c);
+            }
+            continue;
+        }
+
+        variants = variants
+            .into_iter()
+            .flat_map(|(word, subs)| {
+                letters.iter().map(move |&letter| {
+                    let mut word = word.clone();
+                    let mut subs = subs.clone();
+                    word.push(letter);
+                    subs.push((c, letter));
+                    (word, subs)
+                })
+            })
+            .take(MAX_L33T_VARIANTS)
+            .collect();
+    }
+    variants.retain(|(_, subs)| !subs.is_empty());
+    variants
+}
+
+fn dictionary_matches(chars: &[char], lower: &[char]) -> Vec<PatternMatch> {
+    let n = chars.len();
+    let mut matches = Vec::new();
+
+    for start in 0..n {
+        for end in start + 1..n.min(start + MAX_WORD_CHARS) {
+            let token = &lower[start..=end];
+            let word: String = token.iter().collect();
+            let reversed: String = token.iter().rev().collect();
+            let variants = unl33t_variants(token);
+
+            for (dictionary, ranked) in ranked_dictionaries() {
+                let uppercase = uppercase_variations(&chars[start..=end]);
+                let mut push = |rank: usize, reversed: bool, l33t: f64| {
+                    matches.push(PatternMatch {
+                        start,
+                        end,
+                        guesses: rank as f64 * uppercase * l33t * if reversed { 2.0 } else { 1.0 },
+                        pattern: Pattern::Dictionary {
+                            dictionary: *dictionary,
+                            rank,
+                            reversed,
+                            l33t: l33t > 1.0,
+                        },
+                    });
+                };
+
+                if let Some(&rank) = ranked.get(word.as_str()) {
+                    push(rank, false, 1.0);
+                }
+                if token.len() > 2 && reversed != word {
+                    if let Some(&rank) = ranked.get(reversed.as_str()) {
+                        push(rank, true, 1.0);
+                    }
+                }
+                if let Some((rank, subs)) = variants
+                    .iter()
+                    .filter_map(|(variant, subs)| ranked.get(variant.as_str()).map(|&rank| (rank, subs)))
+                    .min_by_key(|(rank, _)| *rank)
+                {
+                    push(rank, false, l33t_variations(token, subs));
+                }
+            }
+        }
+    }
+
+    matches
+}
+
+/// 大文字の使い方による推測回数の倍率
+fn uppercase_variations(token: &[char]) -> f64 {
+    let upper = token.iter().filter(|c| c.is_uppercase()).count();
+    let lower = token.iter().filter(|c| c.is_lowercase()).count();
+    if upper == 0 {
+        return 1.0;
+    }
+
+    // 先頭のみ・末尾のみ・すべて大文字はよくある使い方
+    let first_only = token[0].is_uppercase() && upper == 1;
+    let last_only = token[token.len() - 1].is_uppercase() && upper == 1;
+    if first_only || last_only || lower == 0 {
+        return 2.0;
+    }
+
+    (1..=upper.min(lower)).map(|i| n_choose_k(upper + lower, i)).sum()
+}
+
+/// l33t 置き換えによる推測回数の倍率
+fn l33t_variations(token: &[char], subs: &[(char, char)]) -> f64 {
+    let mut seen = Vec::new();
+    let mut variations = 1.0;
+    for &(sub, letter) in subs {
+        if seen.contains(&(sub, letter)) {
+            continue;
+        }
+        seen.push((sub, letter));
+
+        let substituted = token.iter().filter(|&&c| c == sub).count();
+        let unsubstituted = token.iter().filter(|&&c| c == letter).count();
+        variations *= if unsubstituted == 0 {
+            2.0
+        } else {
+            (1..=substituted.min(unsubstituted))
+                .map(|i| n_choose_k(substituted + unsubstituted, i))
+                .sum()
+        };
+    }
+    variations
+}
+
+// ========================================
+// キーボードの並び
+// ========================================
+
+/// キーボード上の位置（行、横方向の座標、シフトの有無）
+fn key_position(c: char) -> Option<(usize, f64, bool)> {
+    KEYBOARD_ROWS.iter().enumerate().find_map(|(row, (plain, shifted, offset))| {
+        plain
+            .chars()
+            .position(|k| k == c)
+            .map(|col| (row, col as f64 + offset, false))
+            .or_else(|| shifted.chars().position(|k| k == c).map(|col| (row, col as f64 + offset, true)))
+    })
+}
+
+/// 隣り合うキーなら移動方向を返す
+fn key_direction(a: char, b: char) -> Option<(i8, i8)> {
+    let (row_a, x_a, _) = key_position(a)?;
+    let (row_b, x_b, _) = key_position(b)?;
+    let dx = x_b - x_a;
+    let adjacent = match row_a.abs_diff(row_b) {
+        0 => (dx.abs() - 1.0).abs() < f64::EPSILON,
+        1 => dx.abs() < 1.0,
+        _ => false,
+    };
+    adjacent.then(|| (row_b as i8 - row_a as i8, dx.signum() as i8))
+}
+
+/// キーボードのキー数と平均の隣接キー数
+fn keyboard_stats() -> (f64, f64) {
+    static STATS: OnceLock<(f64, f64)> = OnceLock::new();
+    *STATS.get_or_init(|| {
+        let keys: Vec<char> = KEYBOARD_ROWS.iter().flat_map(|(plain, _, _)| plain.chars()).collect();
+        let degrees: usize = keys
+            .iter()
+            .map(|&a| keys.iter().filter(|&&b| key_direction(a, b).is_some()).count())
+            .sum();
+        ((keys.len() * 2) as f64, degrees as f64 / keys.len() as f64)
+    })
+}
+
+fn spatial_matches(chars: &[char]) -> Vec<PatternMatch> {
+    let n = chars.len();
+    let mut matches = Vec::new();
+    let mut start = 0;
+
+    while start + 2 < n {
+        let mut end = start;
+        let mut turns = 0;
+        let mut last_direction = None;
+        while end + 1 < n {
+            match key_direction(chars[end], chars[end + 1]) {
+                Some(direction) => {
+                    if last_direction != Some(direction) {
+                        turns += 1;
+                        last_direction = Some(direction);
+                    }
+                    end += 1;
+                }
+                None => break,
+            }
+        }
+
+        if end - start + 1 >= 3 {
+            let token = &chars[start..=end];
+            let shifted = token.iter().filter(|&&c| key_position(c).is_some_and(|(_, _, s)| s)).count();
+            matches.push(PatternMatch {
+                start,
+                end,
+                guesses: spatial_guesses(token.len(), turns, shifted),
+                pattern: Pattern::Spatial { turns, shifted },
+            });
+        }
+        start = end.max(start + 1);
+    }
+
+    matches
+}
+
+fn spatial_guesses(len: usize, turns: usize, shifted: usize) -> f64 {
+    let (starts, degree) = keyboard_stats();
+    let mut guesses = 0.0;
+    for i in 2..=len {
+        for j in 1..=turns.min(i - 1) {
+            guesses += n_choose_k(i - 1, j - 1) * starts * degree.powi(j as i32);
+        }
+    }
+
+    let unshifted = len - shifted;
+    if shifted > 0 {
+        guesses *= if unshifted == 0 {
+            2.0
+        } else {
+            (1..=shifted.min(unshifted)).map(|i| n_choose_k(len, i)).sum()
+        };
+    }
+    guesses
+}
+
+// ========================================
+// 繰り返し・連続
+// ========================================
+
+fn repeat_matches(chars: &[char]) -> Vec<PatternMatch> {
+    let n = chars.len();
+    let mut matches = Vec::new();
+    let mut start = 0;
+
+    while start + 1 < n {
+        // 最も長く続く繰り返し（同じ長さなら短い単位）
+        let best = (1..=(n - start) / 2)
+            .map(|unit| {
+                let count = 1 + (1..)
+                    .take_while(|r| {
+                        let offset = start + r * unit;
+                        offset + unit <= n && chars[offset..offset + unit] == chars[start..start + unit]
+                    })
+                    .count();
+                (unit, count)
+            })
+            .filter(|&(_, count)| count >= 2)
+            .fold(None, |best: Option<(usize, usize)>, (unit, count)| match best {
+                Some((u, c)) if u * c >= unit * count => best,
+                _ => Some((unit, count)),
+            });
+
+        match best {
+            Some((unit, count)) => {
+                let (base_guesses, _) = most_guessable(&chars[start..start + unit]);
+                matches.push(PatternMatch {
+                    start,
+                    end: start + unit * count - 1,
+                    guesses: base_guesses * count as f64,
+                    pattern: Pattern::Repeat { unit_chars: unit, count },
+                });
+                start += unit * count;
+            }
+            None => start += 1,
+        }
+    }
+
+    matches
+}
+
+/// 連続とみなす文字の種類（同じ種類の文字の間でのみ連続を検出する）
+fn sequence_class(c: char) -> Option<u8> {
+    if c.is_ascii_lowercase() {
+        Some(0)
+    } else if c.is_ascii_uppercase() {
+        Some(1)
+    } else if c.is_ascii_digit() {
+        Some(2)
+    } else {
+        None
+    }
+}
+
+fn sequence_matches(chars: &[char]) -> Vec<PatternMatch> {
+    let n = chars.len();
+    let mut matches = Vec::new();
+    let mut start = 0;
+
+    while start + 1 < n {
+        let delta = chars[start + 1] as i64 - chars[start] as i64;
+        let mut end = start + 1;
+        while end + 1 < n && chars[end + 1] as i64 - chars[end] as i64 == delta {
+            end += 1;
+        }
+
+        let token = &chars[start..=end];
+        let class = sequence_class(token[0]);
+        if token.len() >= 3
+            && (1..=5).contains(&delta.abs())
+            && class.is_some()
+            && token.iter().all(|&c| sequence_class(c) == class)
+        {
+            let base = match token[0] {
+                'a' | 'z' | 'A' | 'Z' | '0' | '1' | '9' => 4.0,
+                c if c.is_ascii_digit() => 10.0,
+                _ => 26.0,
+            };
+            let ascending = delta > 0;
+            matches.push(PatternMatch {
+                start,
+                end,
+                guesses: base * if ascending { 1.0 } else { 2.0 } * token.len() as f64 * delta.abs() as f64,
+                pattern: Pattern::Sequence { ascending },
+            });
+        }
+        start = end;
+    }
+
+    matches
+}
+
+// ========================================
+// 日付
+// ========================================
+
+/// 日付の区切り文字
+const DATE_SEPARATORS: &[char] = &[' ', '-', '/', '.', '_', '\\'];
+
+/// 年の推測空間
+fn year_space(year: i32) -> f64 {
+    ((year - Utc::now().year()).abs() as f64).max(MIN_YEAR_SPACE)
+}
+
+/// 2桁の年を4桁にする
+fn expand_year(value: u32, digits: usize) -> Option<i32> {
+    let year = match digits {
+        2 if value > 50 => 1900 + value as i32,
+        2 => 2000 + value as i32,
+        4 => value as i32,
+        _ => return None,
+    };
+    (DATE_MIN_YEAR..=DATE_MAX_YEAR).contains(&year).then_some(year)
+}
+
+/// 3つの数から日付を解釈し、現在に最も近い年を返す
+fn parse_date(parts: [(u32, usize); 3]) -> Option<i32> {
+    let valid_day_month = |a: u32, b: u32| (1..=31).contains(&a) && (1..=12).contains(&b) || (1..=12).contains(&a) && (1..=31).contains(&b);
+    let [(p0, d0), (p1, d1), (p2, d2)] = parts;
+
+    // 年が末尾（日月年・月日年）または先頭（年月日・年日月）
+    let candidates = [
+        expand_year(p2, d2).filter(|_| d0 <= 2 && d1 <= 2 && valid_day_month(p0, p1)),
+        expand_year(p0, d0).filter(|_| d1 <= 2 && d2 <= 2 && valid_day_month(p1, p2)),
+    ];
+    candidates
+        .into_iter()
+        .flatten()
+        .min_by_key(|year| (year - Utc::now().year()).abs())
+}
+
+/// 区切り文字のない数字列を年・月・日に分ける位置の候補
+fn date_splits(len: usize) -> &'static [(usize, usize)] {
+    match len {
+        4 => &[(1, 2)],
+        5 => &[(1, 3), (2, 3)],
+        6 => &[(2, 4), (1, 2), (4, 5)],
+        7 => &[(1, 3), (2, 3), (4, 5), (4, 6)],
+        8 => &[(2, 4), (4, 6)],
+        _ => &[],
+    }
+}
+
+fn date_matches(chars: &[char]) -> Vec<PatternMatch> {
+    let n = chars.len();
+    let mut matches = Vec::new();
+    let number = |token: &[char]| token.iter().collect::<String>().parse::<u32>().ok();
+
+    for start in 0..n {
+        for end in start + 3..n.min(start + 10) {
+            let token = &chars[start..=end];
+
+            // 年のみ（19xx・20xx）
+            if token.len() == 4 && token.iter().all(char::is_ascii_digit) && matches!(&token[..2], ['1', '9'] | ['2', '0']) {
+                let year = number(token).unwrap_or_default() as i32;
+                matches.push(PatternMatch {
+                    start,
+                    end,
+                    guesses: year_space(year),
+                    pattern: Pattern::Year { year },
+                });
+            }
+
+            let (year, separator) = if token.iter().all(char::is_ascii_digit) {
+                let year = date_splits(token.len())
+                    .iter()
+                    .filter_map(|&(a, b)| {
+                        let parts = [&token[..a], &token[a..b], &token[b..]];
+                        parse_date(parts.map(|p| (number(p).unwrap_or_default(), p.len())))
+                    })
+                    .min_by_key(|year| (year - Utc::now().year()).abs());
+                (year, false)
+            } else {
+                let separator = token.iter().find(|c| !c.is_ascii_digit()).copied().unwrap_or(' ');
+                let parts: Vec<&[char]> = token.split(|&c| c == separator).collect();
+                let valid = DATE_SEPARATORS.contains(&separator)
+                    && parts.len() == 3
+                    && parts.iter().all(|p| !p.is_empty() && p.len() <= 4 && p.iter().all(char::is_ascii_digit));
+                let year = valid
+                    .then(|| parse_date([0, 1, 2].map(|i| (number(parts[i]).unwrap_or_default(), parts[i].len()))))
+                    .flatten();
+                (year, true)
+            };
+
+            if let Some(year) = year {
+                matches.push(PatternMatch {
+                    start,
+                    end,
+                    guesses: year_space(year) * 365.0 * if separator { 4.0 } else { 1.0 },
+                    pattern: Pattern::Date { year, separator },
+                });
+            }
+        }
+    }
+
+    matches
+}
+
+// ========================================
+// 改善の提案
+// ========================================
+
+/// 主な弱点と改善の提案
+fn feedback(chars: &[char], matches: &[PatternMatch], score: u8) -> (Option<String>, Vec<String>) {
+    let mut suggestions = Vec::new();
+    if chars.len() < MIN_PASSWORD_LENGTH {
+        suggestions.push(format!("{}文字以上にしてください", MIN_PASSWORD_LENGTH));
+    }
+
+    if chars.is_empty() {
+        suggestions.push("複数の単語を組み合わせた長いパスフレーズを推奨".to_string());
+        return (None, suggestions);
+    }
+    if score >= 3 {
+        return (None, suggestions);
+    }
+
+    // 最も長いパターンについて指摘する
+    let longest = matches
+        .iter()
+        .filter(|m| m.pattern != Pattern::Bruteforce)
+        .max_by_key(|m| m.len());
+    let warning = longest.and_then(|m| match_feedback(chars, m, matches.len() == 1, &mut suggestions));
+
+    suggestions.push("あまり使われない単語を1〜2語追加してください".to_string());
+    (warning, suggestions)
+}
+
+fn match_feedback(chars: &[char], m: &PatternMatch, sole_match: bool, suggestions: &mut Vec<String>) -> Option<String> {
+    match m.pattern {
+        Pattern::Dictionary { dictionary, rank, reversed, l33t } => {
+            let token = &chars[m.start..=m.end];
+            if token.iter().any(|c| c.is_uppercase()) {
+                if token.iter().all(|c| !c.is_lowercase()) {
+                    suggestions.push("すべて大文字にしても小文字とほとんど変わりません".to_string());
+                } else {
+                    suggestions.push("先頭だけの大文字はあまり効果がありません".to_string());
+                }
+            }
+            if reversed {
+                suggestions.push("単語を逆に綴っても推測は難しくなりません".to_string());
+            }
+            if l33t {
+                suggestions.push("「@」→「a」のような予測しやすい置き換えはあまり効果がありません".to_string());
+            }
+
+            match dictionary {
+                Dictionary::CommonPasswords if sole_match && !l33t && !reversed && rank <= 10 => {
+                    Some("最もよく使われるパスワードの1つです".to_string())
+                }
+                Dictionary::CommonPasswords if sole_match && !l33t && !reversed => {
+                    Some("非常によく使われるパスワードです".to_string())
+                }
+                Dictionary::CommonPasswords => Some("よく使われるパスワードに似ています".to_string()),
+                Dictionary::EnglishWords | Dictionary::TvAndFilm if sole_match => {
+                    Some("単語1つだけでは推測されやすくなります".to_string())
+                }
+                Dictionary::FirstNames | Dictionary::Surnames if sole_match => {
+                    Some("名前や姓だけでは推測されやすくなります".to_string())
+                }
+                Dictionary::FirstNames | Dictionary::Surnames => {
+                    Some("よくある名前や姓は推測されやすくなります".to_string())
+                }
+                Dictionary::EnglishWords | Dictionary::TvAndFilm => None,
+            }
+        }
+        Pattern::Spatial { turns, .. } => {
+            suggestions.push("より長く、曲がりの多いキーボードの並びにしてください".to_string());
+            Some(if turns == 1 {
+                "キーボード上で一直線に並んだ文字は推測されやすくなります".to_string()
+            } else {
+                "短いキーボードの並びは推測されやすくなります".to_string()
+            })
+        }
+        Pattern::Repeat { unit_chars, .. } => {
+            suggestions.push("単語や文字の繰り返しを避けてください".to_string());
+            Some(if unit_chars == 1 {
+                "「aaa」のような同じ文字の繰り返しは推測されやすくなります".to_string()
+            } else {
+                "「abcabcabc」のような繰り返しは「abc」より少し推測しにくいだけです".to_string()
+            })
+        }
+        Pattern::Sequence { .. } => {
+            suggestions.push("連続した文字を避けてください".to_string());
+            Some("「abc」や「6543」のような連続は推測されやすくなります".to_string())
+        }
+        Pattern::Date { .. } | Pattern::Year { .. } => {
+            suggestions.push("自分に関係のある日付や年を避けてください".to_string());
+            Some("日付や年は推測されやすくなります".to_string())
+        }
+        Pattern::Bruteforce => None,
+    }
+}
+
+fn n_choose_k(n: usize, k: usize) -> f64 {
+    if k > n {
+        return 0.0;
+    }
+    (1..=k).fold(1.0, |acc, i| acc * (n - k + i) as f64 / i as f64)
+}
+
+fn factorial(n: usize) -> f64 {
+    (2..=n).fold(1.0, |acc, i| acc * i as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn patterns(password: &str) -> Vec<Pattern> {
+        estimate_password(password).matches.into_iter().map(|m| m.pattern).collect()
+    }
+
+    #[test]
+    fn test_common_password_is_weak() {
+        // 文字種がそろっていても、よく使われる単語と数字・記号の組み合わせは弱い
+        let estimate = estimate_password("Password1!");
+        assert_eq!(estimate.strength(), PasswordStrength::Weak);
+        assert!(estimate.warning.is_some());
+        assert!(matches!(
+            estimate.matches[0].pattern,
+            Pattern::Dictionary { dictionary: Dictionary::CommonPasswords, .. }
+        ));
+
+        assert_eq!(estimate_password("password").score, 0);
+        assert_eq!(estimate_password("").score, 0);
+    }
+
+    #[test]
+    fn test_common_words_and_names_are_weak() {
+        // 辞書は数万語規模で、ありふれた単語・名前はそれだけでは弱い
+        for word in ["butterfly", "treasure", "kitchen", "government", "jennifer", "johnson"] {
+            let estimate = estimate_password(word);
+            assert!(estimate.score <= 1, "{} の評価が高すぎます: {}", word, estimate.score);
+            assert!(matches!(estimate.matches[..], [PatternMatch { pattern: Pattern::Dictionary { .. }, .. }]));
+        }
+        assert!(ranked_dictionaries().iter().map(|(_, words)| words.len()).sum::<usize>() > 90_000);
+    }
+
+    #[test]
+    fn test_l33t_and_reversed_words() {
+        assert!(patterns("p@ssw0rd").iter().any(|p| matches!(p, Pattern::Dictionary { l33t: true, .. })));
+        assert!(patterns("nogard").iter().any(|p| matches!(p, Pattern::Dictionary { reversed: true, .. })));
+        assert_eq!(estimate_password("P@ssw0rd").strength(), PasswordStrength::Weak);
+    }
+
+    #[test]
+    fn test_keyboard_walks() {
+        assert!(matches!(patterns("wertyuio")[..], [Pattern::Spatial { turns: 1, .. }]));
+        assert!(matches!(patterns("zxcvfr")[..], [Pattern::Spatial { turns: 2, .. }]));
+        assert_eq!(estimate_password("1qaz2wsx3edc").strength(), PasswordStrength::Weak);
+    }
+
+    #[test]
+    fn test_repeats_and_sequences() {
+        assert!(matches!(patterns("aaaaaaaaaa")[..], [Pattern::Repeat { unit_chars: 1, count: 10 }]));
+        assert!(matches!(patterns("xkcdxkcdxkcd")[..], [Pattern::Repeat { unit_chars: 4, count: 3 }]));
+        assert!(matches!(patterns("abcdefgh")[..], [Pattern::Sequence { ascending: true }]));
+        assert!(matches!(patterns("98765432")[..], [Pattern::Sequence { ascending: false }]));
+        assert_eq!(estimate_password("abcdefghijkl").score, 0);
+    }
+
+    #[test]
+    fn test_dates() {
+        assert!(matches!(patterns("1990-05-17")[..], [Pattern::Date { year: 1990, separator: true }]));
+        assert!(matches!(patterns("17051990")[..], [Pattern::Date { year: 1990, separator: false }]));
+        assert!(patterns("taro1987").iter().any(|p| matches!(p, Pattern::Year { year: 1987 })));
+    }
+
+    #[test]
+    fn test_random_password_is_strong() {
+        let estimate = estimate_password("Kx9#mQ2$vL7!pR4w");
+        assert_eq!(estimate.strength(), PasswordStrength::Strong);
+        assert!(estimate.warning.is_none());
+        assert!(estimate.crack_time_secs > 1e8);
+        assert_eq!(estimate.crack_time_display(), "数世紀以上");
+
+        // 推定値は文字列の長さに応じて増え、64文字を超えても計算できる
+        let long = "Kx9#mQ2$vL7!pR4w".repeat(8);
+        assert!(estimate_password(&long).guesses > estimate.guesses);
+    }
+
+    #[test]
+    fn test_password_policy() {
+        assert!(matches!(check_password_policy("short"), Err(CryptoError::PasswordTooShort)));
+        assert!(check_password_policy("long enough").is_ok());
+        // 文字数はバイト数ではなく文字単位で数える
+        assert!(matches!(check_password_policy("パスワード"), Err(CryptoError::PasswordTooShort)));
+        assert!(check_password_policy("長いパスワードです").is_ok());
+    }
+
+    #[test]
+    fn test_estimate_does_not_expose_password() {
+        let estimate = estimate_password("hunter2_password");
+        let debug = format!("{:?}", estimate);
+        let json = serde_json::to_string(&estimate).unwrap();
+        assert!(!debug.contains("hunter"));
+        assert!(!json.contains("hunter"));
+    }
+}
@@ -1,13 +1,13 @@
 //! SecureBackup - 差分・暗号化バックアップツール
 //!
 //! 大切なデータを安全に自動バックアップするTauriアプリケーション。
-//! - AES-256-GCM暗号化
+//! - AES-256-GCM暗号化（Argon2id鍵導出）
 //! - BLAKE3ハッシュによる差分検出
 //! - Zstd圧縮
 //! - 復元機能（暗号化ファイルの復号・解凍）
 //! - クロスプラットフォーム対応
 
-mod backup;
+pub mod backup;
 mod crypto;
 mod commands;
 
@@ -23,12 +23,24 @@ pub fn run() {
         .invoke_handler(tauri::generate_handler![
             // バックアップ関連
             commands::scan_directory,
+            commands::preview_exclusions,
             commands::execute_backup,
             commands::get_progress,
             commands::check_password,
+            commands::add_key,
+            commands::remove_key,
+            commands::change_password,
+            commands::create_recovery_key,
+            commands::split_key,
+            commands::generate_identity,
+            commands::generate_content_key,
+            commands::generate_signing_key,
             commands::format_file_size,
             // 復元関連
             commands::get_backup_info,
+            commands::list_snapshots,
+            commands::prune_backup,
+            commands::verify_backup,
             commands::execute_restore,
             commands::get_restore_progress,
         ])
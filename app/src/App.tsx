@@ -4,10 +4,47 @@ import { open } from "@tauri-apps/plugin-dialog";
 import "./App.css";
 
 // APIレスポンス型定義
+interface IgnoreFileReport {
+  path: string;
+  excluded: number;
+}
+
+interface ScanFilters {
+  exclude: string[];
+  include: string[];
+  use_gitignore: boolean;
+  max_file_size: number | null;
+  max_age_days: number | null;
+  exclude_content_types: string[];
+}
+
+type ExcludeReason =
+  | { kind: "pattern"; pattern: string }
+  | { kind: "ignore_file"; file: string; pattern: string }
+  | { kind: "too_large"; size: number; limit: number }
+  | { kind: "too_old"; modified: string; max_age_days: number }
+  | { kind: "content_type"; content_type: string };
+
+interface ExcludedEntry {
+  path: string;
+  is_dir: boolean;
+  reason: ExcludeReason;
+}
+
+interface ExclusionPreviewResponse {
+  success: boolean;
+  total_files: number;
+  total_size: number;
+  excluded: ExcludedEntry[];
+  ignore_files: IgnoreFileReport[];
+  error: string | null;
+}
+
 interface ScanResponse {
   success: boolean;
   total_files: number;
   total_size: number;
+  ignore_files: IgnoreFileReport[];
   error: string | null;
 }
 
@@ -17,6 +54,7 @@ interface BackupResponse {
   backed_up_bytes: number;
   skipped_files: number;
   duration_secs: number;
+  recovery_phrase: string | null;
   error: string | null;
 }
 
@@ -43,6 +81,10 @@ interface ProgressResponse {
 interface PasswordCheckResponse {
   strength: string;
   score: number;
+  guesses_log10: number;
+  crack_time_secs: number;
+  crack_time: string;
+  warning: string | null;
   suggestions: string[];
 }
 
@@ -54,6 +96,11 @@ interface BackupFileInfo {
   modified: string;
 }
 
+interface SignatureStatus {
+  status: "trusted" | "untrusted" | "unsigned" | "invalid";
+  signer?: string;
+}
+
 interface BackupInfo {
   source_dir: string;
   created_at: string;
@@ -61,6 +108,8 @@ interface BackupInfo {
   total_size: number;
   encrypted: boolean;
   compressed: boolean;
+  signature: SignatureStatus | null;
+  filters: ScanFilters | null;
 }
 
 interface BackupInfoResponse {
@@ -80,6 +129,26 @@ interface Toast {
   message: string;
 }
 
+// 既定の除外パターン（バックエンドの DEFAULT_EXCLUDE_PATTERNS と同じ）
+const DEFAULT_EXCLUDE_PATTERNS = [".git", "node_modules", "target", ".DS_Store", "Thumbs.db"];
+
+// マジックバイトで判定できるディスクイメージの種類（バックエンドの DISK_IMAGE_CONTENT_TYPES と同じ）
+const DISK_IMAGE_CONTENT_TYPES = [
+  "application/x-iso9660-image",
+  "application/x-qemu-disk",
+  "application/x-vmdk",
+  "application/x-vhd",
+  "application/x-vhdx",
+  "application/x-virtualbox-vdi",
+];
+
+// 除外プレビューで表示する最大件数
+const PREVIEW_LIMIT = 200;
+
+// 1行1パターンのテキストを配列に変換（空行と # で始まるコメント行は無視）
+const parsePatterns = (text: string): string[] =>
+  text.split("\n").map((line) => line.trim()).filter((line) => line && !line.startsWith("#"));
+
 function App() {
   // タブ状態
   const [activeTab, setActiveTab] = useState<TabType>("backup");
@@ -93,6 +162,17 @@ function App() {
   const [compress, setCompress] = useState(true);
   const [incremental, setIncremental] = useState(true);
 
+  // 除外設定（バックアップ先に前回の設定があれば読み込む）
+  const [excludePatterns, setExcludePatterns] = useState(DEFAULT_EXCLUDE_PATTERNS.join("\n"));
+  const [includePatterns, setIncludePatterns] = useState("");
+  const [useGitignore, setUseGitignore] = useState(false);
+  const [maxFileSizeMb, setMaxFileSizeMb] = useState("");
+  const [maxAgeDays, setMaxAgeDays] = useState("");
+  const [excludeDiskImages, setExcludeDiskImages] = useState(false);
+  const [excludeContentTypes, setExcludeContentTypes] = useState("");
+  const [exclusionPreview, setExclusionPreview] = useState<ExclusionPreviewResponse | null>(null);
+  const [previewing, setPreviewing] = useState(false);
+
   // 復元設定
   const [backupDir, setBackupDir] = useState("");
   const [restoreDir, setRestoreDir] = useState("");
@@ -182,6 +262,7 @@ function App() {
       if (selected) {
         setDestDir(selected as string);
         addToast("info", "バックアップ先フォルダを選択しました");
+        await loadSavedFilters(selected as string);
       }
     } catch (e) {
       addToast("error", `フォルダ選択エラー: ${e}`);
@@ -224,6 +305,75 @@ function App() {
     }
   };
 
+  // 入力中の除外設定
+  const buildFilters = (): ScanFilters => ({
+    exclude: parsePatterns(excludePatterns),
+    include: parsePatterns(includePatterns),
+    use_gitignore: useGitignore,
+    max_file_size: maxFileSizeMb ? Math.round(parseFloat(maxFileSizeMb) * 1024 * 1024) : null,
+    max_age_days: maxAgeDays ? parseInt(maxAgeDays, 10) : null,
+    exclude_content_types: [
+      ...(excludeDiskImages ? DISK_IMAGE_CONTENT_TYPES : []),
+      ...excludeContentTypes.split(",").map((t) => t.trim()).filter((t) => t),
+    ],
+  });
+
+  // バックアップ先に記録された前回の除外設定を読み込み
+  const loadSavedFilters = async (dir: string) => {
+    try {
+      const result = await invoke<BackupInfoResponse>("get_backup_info", {
+        backupDir: dir,
+        password: encrypt && password ? password : null,
+      });
+      const filters = result.info?.filters;
+      if (filters) {
+        setExcludePatterns(filters.exclude.join("\n"));
+        setIncludePatterns(filters.include.join("\n"));
+        setUseGitignore(filters.use_gitignore);
+        setMaxFileSizeMb(filters.max_file_size ? String(filters.max_file_size / (1024 * 1024)) : "");
+        setMaxAgeDays(filters.max_age_days ? String(filters.max_age_days) : "");
+        const contentTypes = filters.exclude_content_types ?? [];
+        const diskImages = DISK_IMAGE_CONTENT_TYPES.every((t) => contentTypes.includes(t));
+        setExcludeDiskImages(diskImages);
+        setExcludeContentTypes(
+          contentTypes.filter((t) => !(diskImages && DISK_IMAGE_CONTENT_TYPES.includes(t))).join(", ")
+        );
+        setExclusionPreview(null);
+        addToast("info", "前回の除外設定を読み込みました");
+      }
+    } catch {
+      // 新しいバックアップ先や暗号化されたバックアップ先では読み込めないため、現在の設定を使う
+    }
+  };
+
+  // 除外されるファイルをプレビュー
+  const handlePreviewExclusions = async () => {
+    if (!sourceDir) {
+      addToast("warning", "バックアップ元フォルダを選択してください");
+      return;
+    }
+
+    setPreviewing(true);
+
+    try {
+      const result = await invoke<ExclusionPreviewResponse>("preview_exclusions", {
+        request: {
+          path: sourceDir,
+          compute_hash: false,
+          filters: buildFilters(),
+        },
+      });
+      setExclusionPreview(result);
+      if (!result.success && result.error) {
+        addToast("error", result.error);
+      }
+    } catch (e) {
+      addToast("error", `プレビューエラー: ${e}`);
+    } finally {
+      setPreviewing(false);
+    }
+  };
+
   // バックアップ情報を読み込み
   const loadBackupInfo = async () => {
     if (!backupDir) {
@@ -236,6 +386,7 @@ function App() {
     try {
       const result = await invoke<BackupInfoResponse>("get_backup_info", {
         backupDir: backupDir,
+        password: restorePassword || null,
       });
       setBackupInfo(result);
       if (result.success) {
@@ -264,6 +415,7 @@ function App() {
         request: {
           path: sourceDir,
           compute_hash: true,
+          filters: buildFilters(),
         },
       });
       setScanResult(result);
@@ -319,6 +471,7 @@ function App() {
           password: encrypt ? password : null,
           compress,
           incremental,
+          filters: buildFilters(),
         },
       });
       setBackupResult(result);
@@ -509,6 +662,24 @@ function App() {
     return date.toLocaleString("ja-JP");
   };
 
+  // 除外理由を表示用の文字列に変換
+  const describeExcludeReason = (reason: ExcludeReason): string => {
+    switch (reason.kind) {
+      case "pattern":
+        return `除外パターン ${reason.pattern}`;
+      case "ignore_file":
+        return `${reason.file} の ${reason.pattern}`;
+      case "too_large":
+        return `サイズ上限超過（${formatSize(reason.size)}）`;
+      case "too_old":
+        return `${reason.max_age_days}日より前に更新（${formatDate(reason.modified)}）`;
+      case "content_type":
+        return DISK_IMAGE_CONTENT_TYPES.includes(reason.content_type)
+          ? `ディスクイメージ（${reason.content_type}）`
+          : `種類 ${reason.content_type}`;
+    }
+  };
+
   // パスワード強度の色
   const getStrengthColor = (score: number): string => {
     switch (score) {
@@ -751,6 +922,10 @@ function App() {
                     >
                       {getStrengthLabel(passwordStrength.score)}
                     </span>
+                    <span className="crack-time">解読の推定時間: {passwordStrength.crack_time}</span>
+                    {passwordStrength.warning && (
+                      <p className="strength-warning">{passwordStrength.warning}</p>
+                    )}
                     {passwordStrength.suggestions.length > 0 && (
                       <ul className="suggestions" aria-label="パスワード改善の提案">
                         {passwordStrength.suggestions.map((s, i) => (
@@ -792,6 +967,138 @@ function App() {
             </div>
           </section>
 
+          {/* 除外設定 */}
+          <section className="card" aria-labelledby="filter-settings">
+            <h2 id="filter-settings">
+              <span aria-hidden="true">🧹</span> 除外設定
+            </h2>
+
+            <div className="form-group">
+              <label htmlFor="exclude-patterns">除外パターン</label>
+              <textarea
+                id="exclude-patterns"
+                rows={4}
+                value={excludePatterns}
+                onChange={(e) => setExcludePatterns(e.target.value)}
+                aria-describedby="exclude-patterns-hint"
+              />
+              <span id="exclude-patterns-hint" className="input-hint">
+                1行に1つ、.gitignore と同じ書式（例: *.tmp、/build、logs/**、cache/、!keep.log）
+              </span>
+            </div>
+
+            <div className="form-group">
+              <label htmlFor="include-patterns">再包含パターン</label>
+              <textarea
+                id="include-patterns"
+                rows={2}
+                value={includePatterns}
+                onChange={(e) => setIncludePatterns(e.target.value)}
+                aria-describedby="include-patterns-hint"
+              />
+              <span id="include-patterns-hint" className="input-hint">
+                除外パターンや .backupignore に一致しても必ずバックアップするファイル
+              </span>
+            </div>
+
+            <div className="input-row">
+              <div className="form-group">
+                <label htmlFor="max-file-size">最大ファイルサイズ（MB）</label>
+                <input
+                  id="max-file-size"
+                  type="number"
+                  min="0"
+                  value={maxFileSizeMb}
+                  onChange={(e) => setMaxFileSizeMb(e.target.value)}
+                  placeholder="制限なし"
+                />
+              </div>
+              <div className="form-group">
+                <label htmlFor="max-age-days">更新からの日数</label>
+                <input
+                  id="max-age-days"
+                  type="number"
+                  min="1"
+                  value={maxAgeDays}
+                  onChange={(e) => setMaxAgeDays(e.target.value)}
+                  placeholder="制限なし"
+                />
+              </div>
+            </div>
+
+            <div className="checkbox-group">
+              <label className="checkbox-label" data-tooltip="各フォルダの .gitignore も除外ルールとして使います">
+                <input
+                  type="checkbox"
+                  checked={useGitignore}
+                  onChange={(e) => setUseGitignore(e.target.checked)}
+                />
+                <span className="checkbox-text">
+                  <span aria-hidden="true">🐙</span> .gitignore を適用
+                </span>
+              </label>
+              <span className="option-desc">.backupignore は常に適用されます</span>
+            </div>
+
+            <div className="checkbox-group">
+              <label className="checkbox-label" data-tooltip="拡張子ではなくファイル先頭の内容で判定します">
+                <input
+                  type="checkbox"
+                  checked={excludeDiskImages}
+                  onChange={(e) => setExcludeDiskImages(e.target.checked)}
+                />
+                <span className="checkbox-text">
+                  <span aria-hidden="true">💿</span> ISO・ディスクイメージを除外
+                </span>
+              </label>
+              <span className="option-desc">ISO、qcow2、VMDK、VHD、VHDX、VDI</span>
+            </div>
+
+            <div className="form-group">
+              <label htmlFor="exclude-content-types">除外するファイルの種類</label>
+              <input
+                id="exclude-content-types"
+                type="text"
+                value={excludeContentTypes}
+                onChange={(e) => setExcludeContentTypes(e.target.value)}
+                placeholder="video/*, application/zip"
+                aria-describedby="exclude-content-types-hint"
+              />
+              <span id="exclude-content-types-hint" className="input-hint">
+                MIMEタイプをカンマ区切りで指定（video/* のように種類ごとの指定も可）
+              </span>
+            </div>
+
+            <button
+              className="btn-secondary"
+              onClick={handlePreviewExclusions}
+              disabled={!sourceDir || previewing}
+              aria-busy={previewing}
+            >
+              <span aria-hidden="true">👀</span> 除外されるファイルを確認
+            </button>
+
+            {exclusionPreview && exclusionPreview.success && (
+              <div className="exclusion-preview" aria-live="polite">
+                <p>
+                  対象 {exclusionPreview.total_files.toLocaleString()}ファイル（{formatSize(exclusionPreview.total_size)}）、
+                  除外 {exclusionPreview.excluded.length.toLocaleString()}件
+                </p>
+                <ul>
+                  {exclusionPreview.excluded.slice(0, PREVIEW_LIMIT).map((entry) => (
+                    <li key={entry.path}>
+                      <code>{entry.path}{entry.is_dir ? "/" : ""}</code>
+                      <span className="exclude-reason">{describeExcludeReason(entry.reason)}</span>
+                    </li>
+                  ))}
+                </ul>
+                {exclusionPreview.excluded.length > PREVIEW_LIMIT && (
+                  <p className="input-hint">ほか {exclusionPreview.excluded.length - PREVIEW_LIMIT}件</p>
+                )}
+              </div>
+            )}
+          </section>
+
           {/* スキャン結果 */}
           {scanResult && scanResult.success && (
             <section className="card scan-result" aria-labelledby="scan-result-heading">
@@ -808,6 +1115,15 @@ function App() {
                   <span className="stat-label">合計サイズ</span>
                 </div>
               </div>
+              {scanResult.ignore_files.length > 0 && (
+                <ul className="ignore-files">
+                  {scanResult.ignore_files.map((file) => (
+                    <li key={file.path}>
+                      <code>{file.path}</code>: {file.excluded.toLocaleString()}件を除外
+                    </li>
+                  ))}
+                </ul>
+              )}
             </section>
           )}
 
@@ -871,6 +1187,13 @@ function App() {
                   </div>
                 </div>
               )}
+              {backupResult.recovery_phrase && (
+                <div className="recovery-phrase" role="alert">
+                  <strong>回復フレーズ（この画面でのみ表示されます）:</strong>
+                  <p>パスワードを忘れた場合、パスワードの代わりにこのフレーズで復元できます。印刷して安全な場所に保管してください。</p>
+                  <code>{backupResult.recovery_phrase}</code>
+                </div>
+              )}
               {backupResult.error && (
                 <div className="error-detail" role="alert">
                   <strong>エラー詳細:</strong> {backupResult.error}
@@ -997,6 +1320,38 @@ function App() {
             </button>
           </section>
 
+          {/* 暗号化パスワード */}
+          {backupInfo && (!backupInfo.success || backupInfo.info?.encrypted) && (
+            <section className="card" aria-labelledby="decrypt-password-heading">
+              <h2 id="decrypt-password-heading">
+                <span aria-hidden="true">🔐</span> 復号パスワード
+              </h2>
+              <div className="form-group">
+                <div className="password-input-row">
+                  <input
+                    id="restore-password"
+                    type={showRestorePassword ? "text" : "password"}
+                    value={restorePassword}
+                    onChange={(e) => setRestorePassword(e.target.value)}
+                    placeholder="暗号化時に設定したパスワード（または回復フレーズ）"
+                    autoComplete="current-password"
+                  />
+                  <button
+                    type="button"
+                    className="password-toggle"
+                    onClick={() => setShowRestorePassword(!showRestorePassword)}
+                    aria-label={showRestorePassword ? "パスワードを隠す" : "パスワードを表示"}
+                  >
+                    {showRestorePassword ? "🙈" : "👁️"}
+                  </button>
+                </div>
+                <span className="input-hint">
+                  バックアップ作成時に設定したパスワードを入力してください（暗号化されたバックアップは情報の読み込みにも必要です）
+                </span>
+              </div>
+            </section>
+          )}
+
           {/* バックアップ情報 */}
           {backupInfo && backupInfo.success && backupInfo.info && (
             <>
@@ -1035,41 +1390,22 @@ function App() {
                       {backupInfo.info.compressed ? "📦 有効" : "📄 なし"}
                     </span>
                   </div>
+                  {backupInfo.info.signature && (
+                    <div className="info-item">
+                      <span className="info-label">署名</span>
+                      <span className="info-value" title={backupInfo.info.signature.signer}>
+                        {{
+                          trusted: "✅ 信頼済み",
+                          untrusted: "⚠️ 未確認の鍵",
+                          unsigned: "⚠️ 署名なし",
+                          invalid: "❌ 不一致（改ざんの可能性）",
+                        }[backupInfo.info.signature.status]}
+                      </span>
+                    </div>
+                  )}
                 </div>
               </section>
 
-              {/* 暗号化パスワード */}
-              {backupInfo.info.encrypted && (
-                <section className="card" aria-labelledby="decrypt-password-heading">
-                  <h2 id="decrypt-password-heading">
-                    <span aria-hidden="true">🔐</span> 復号パスワード
-                  </h2>
-                  <div className="form-group">
-                    <div className="password-input-row">
-                      <input
-                        id="restore-password"
-                        type={showRestorePassword ? "text" : "password"}
-                        value={restorePassword}
-                        onChange={(e) => setRestorePassword(e.target.value)}
-                        placeholder="暗号化時に設定したパスワード"
-                        autoComplete="current-password"
-                      />
-                      <button
-                        type="button"
-                        className="password-toggle"
-                        onClick={() => setShowRestorePassword(!showRestorePassword)}
-                        aria-label={showRestorePassword ? "パスワードを隠す" : "パスワードを表示"}
-                      >
-                        {showRestorePassword ? "🙈" : "👁️"}
-                      </button>
-                    </div>
-                    <span className="input-hint">
-                      バックアップ作成時に設定したパスワードを入力してください
-                    </span>
-                  </div>
-                </section>
-              )}
-
               {/* オプション */}
               <section className="card" aria-labelledby="restore-options-heading">
                 <h2 id="restore-options-heading">